@@ -6,7 +6,7 @@
 
 use std::path::PathBuf;
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 /// The main CLI structure, representing the `rjot` command itself.
 #[derive(Parser, Debug)]
@@ -27,6 +27,15 @@ pub struct Cli {
     #[arg(long, global = true)]
     pub notebook: Option<String>,
 
+    /// Disable colored output, overriding the `color` preference.
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Columns for jot listings, comma-separated, overriding the `list_columns` preference.
+    /// Choose from pinned, id, date, age, title, tags, tasks, notebook, and words.
+    #[arg(long, global = true, value_delimiter = ',')]
+    pub columns: Option<Vec<String>>,
+
     /// The message for a new jot. This captures all positional arguments
     /// that are not part of a subcommand.
     pub message: Vec<String>,
@@ -62,6 +71,9 @@ pub enum Commands {
         /// A flag to show only jots containing incomplete tasks.
         #[arg(long)] // Or short('t') if you prefer
         tasks: bool,
+        /// Show only jots that have all of these tags (comma-separated).
+        #[arg(long, value_delimiter = ',')]
+        tag: Vec<String>,
     },
     /// Pin a jot.
     Pin {
@@ -97,11 +109,15 @@ pub enum Commands {
         /// Search across all notebooks.
         #[arg(long, short)] // Or --global if you prefer
         all: bool,
+
+        /// Also search archived notebooks when using --all.
+        #[arg(long, requires = "all")]
+        include_archived: bool,
     },
-    /// Interactively select a note using a fuzzy finder.
+    /// Interactively select jots using a fuzzy finder and act on them.
     #[command(alias = "s")]
     #[cfg(not(windows))] // Fuzzy finder is not supported on Windows
-    Select,
+    Select(SelectArgs),
     /// List jots that have specific tags.
     Tags {
         /// Tags to filter by (can be comma-separated or space-separated).
@@ -113,16 +129,25 @@ pub enum Commands {
         /// Compile all of today's jots into a single summary.
         #[arg(long, short)]
         compile: bool,
+        /// Print the summary as plain markdown, even in a terminal.
+        #[arg(long, requires = "compile")]
+        raw: bool,
     },
     /// List jots from yesterday.
     Yesterday {
         #[arg(long, short)]
         compile: bool,
+        /// Print the summary as plain markdown, even in a terminal.
+        #[arg(long, requires = "compile")]
+        raw: bool,
     },
     /// List jots from this week.
     Week {
         #[arg(long, short)]
         compile: bool,
+        /// Print the summary as plain markdown, even in a terminal.
+        #[arg(long, requires = "compile")]
+        raw: bool,
     },
     /// List jots from a specific date or date range.
     On {
@@ -131,6 +156,9 @@ pub enum Commands {
         date_spec: String,
         #[arg(long, short)]
         compile: bool,
+        /// Print the summary as plain markdown, even in a terminal.
+        #[arg(long, requires = "compile")]
+        raw: bool,
     },
     /// Open an existing jot in the default editor.
     Edit {
@@ -149,6 +177,46 @@ pub enum Commands {
         /// Show the Nth most recent jot.
         #[arg(long, short, group = "target", num_args(0..=1), default_missing_value = "1")]
         last: Option<usize>,
+        /// Show the jot as it was at a revision (e.g., HEAD~2 or a commit hash) or date (YYYY-MM-DD).
+        #[arg(long)]
+        at: Option<String>,
+        /// Print the jot file as it is stored, frontmatter included, even in a terminal.
+        #[arg(long)]
+        raw: bool,
+    },
+    /// List the commits that changed a jot. Requires a Git-initialized journal.
+    History {
+        /// The prefix of the jot ID. Deleted jots are found too.
+        #[arg(group = "target", required = true)]
+        id_prefix: Option<String>,
+        /// Show the history of the Nth most recent jot.
+        #[arg(long, short, group = "target", num_args(0..=1), default_missing_value = "1")]
+        last: Option<usize>,
+    },
+    /// Show how a jot has changed since a revision.
+    Diff {
+        /// The prefix of the jot ID. Deleted jots are found too.
+        #[arg(group = "target", required = true)]
+        id_prefix: Option<String>,
+        /// Diff the Nth most recent jot.
+        #[arg(long, short, group = "target", num_args(0..=1), default_missing_value = "1")]
+        last: Option<usize>,
+        /// The revision or date (YYYY-MM-DD) to compare against. Defaults to the last commit.
+        #[arg(long)]
+        at: Option<String>,
+    },
+    /// Restore an old version of a jot, or bring back a deleted one.
+    Restore {
+        /// The prefix of the jot ID. Deleted jots are found too.
+        #[arg(group = "target", required = true)]
+        id_prefix: Option<String>,
+        /// Restore the Nth most recent jot.
+        #[arg(long, short, group = "target", num_args(0..=1), default_missing_value = "1")]
+        last: Option<usize>,
+        /// The revision or date (YYYY-MM-DD) to restore from. Defaults to the last commit
+        /// that contained the jot.
+        #[arg(long)]
+        at: Option<String>,
     },
     /// Delete a jot with confirmation.
     #[command(alias = "rm")]
@@ -163,6 +231,12 @@ pub enum Commands {
         #[arg(long, short)]
         force: bool,
     },
+    /// Move jots to another notebook.
+    #[command(alias = "mv")]
+    Move(TransferArgs),
+    /// Copy jots to another notebook.
+    #[command(alias = "cp")]
+    Copy(TransferArgs),
     /// Display information about your rjot setup.
     Info(InfoArgs),
     /// Manage tags on an existing jot.
@@ -170,6 +244,10 @@ pub enum Commands {
     /// Manage notebooks for organizing jots.
     #[command(alias = "n")]
     Notebook(NotebookArgs),
+    /// View and change preferences such as the editor and date formats.
+    Config(ConfigArgs),
+    /// Manage the templates used by `new --template`.
+    Template(TemplateArgs),
     /// Initialize the rjot directory, optionally with Git and/or encryption.
     Init {
         /// Initialize the rjot directory as a Git repository.
@@ -179,9 +257,23 @@ pub enum Commands {
         #[arg(long)]
         encrypt: bool,
     },
-    /// Commit and push changes to a remote Git repository.
-    Sync,
-    /// Permanently decrypt all notes in the rjot directory.
+    /// Commit local changes, merge remote changes, and push to a remote Git repository.
+    Sync {
+        /// Resolve conflicts left by a previous sync.
+        ///
+        /// Without a value, each conflicting jot is shown and you choose how to resolve it.
+        /// Pass `ours`, `theirs`, or `both` to resolve every conflict the same way.
+        #[arg(long, num_args(0..=1), default_missing_value = "ask", value_enum)]
+        resolve: Option<ResolveStrategy>,
+        /// Show what would be committed and where it would be pushed, without changing anything.
+        #[arg(long, conflicts_with = "resolve")]
+        dry_run: bool,
+    },
+    /// Encrypt a notebook with its own key.
+    ///
+    /// Applies to the active notebook, or the one given with `--notebook`.
+    Encrypt,
+    /// Permanently decrypt all notes, or only those in the notebook given with `--notebook`.
     Decrypt {
         /// Force decryption without a confirmation prompt.
         #[arg(long, short)]
@@ -191,11 +283,172 @@ pub enum Commands {
     Export(ExportArgs),
 
     /// Import a notebook from a ZIP archive or a JSON file.
+    ///
+    /// Archives encrypted with `export --encrypt` are detected and decrypted automatically.
     Import(ImportArgs),
 
     /// Enter the interactive rjot shell.
     #[command(alias = "sh")]
     Shell,
+    /// Browse and manage jots in a full-screen interface.
+    Tui,
+}
+
+/// How `sync --resolve` should resolve conflicting jots.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResolveStrategy {
+    /// Prompt for each conflicting jot.
+    Ask,
+    /// Keep the local version.
+    Ours,
+    /// Keep the remote version.
+    Theirs,
+    /// Keep both versions, local first.
+    Both,
+}
+
+/// Arguments for the `select` subcommand.
+#[cfg(not(windows))]
+#[derive(Args, Debug)]
+pub struct SelectArgs {
+    /// What to do with the selected jots when you press Enter.
+    #[arg(long, short, value_enum, default_value_t = SelectAction::Edit)]
+    pub action: SelectAction,
+    /// Tags to add with the `tag` action (comma-separated). Asked for if not given.
+    #[arg(long, value_delimiter = ',')]
+    pub tag: Vec<String>,
+    /// The notebook to move jots to with the `move` action. Asked for if not given.
+    #[arg(long)]
+    pub to: Option<String>,
+    /// Delete without asking for confirmation.
+    #[arg(long, short)]
+    pub force: bool,
+    /// Select from every notebook instead of just the active one.
+    #[arg(long)]
+    pub all: bool,
+    /// Also include archived notebooks when using --all.
+    #[arg(long, requires = "all")]
+    pub include_archived: bool,
+}
+
+/// What `select` does with the chosen jots.
+#[cfg(not(windows))]
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SelectAction {
+    /// Open each jot in the editor.
+    Edit,
+    /// Print each jot.
+    Show,
+    /// Delete the jots.
+    Delete,
+    /// Pin the jots.
+    Pin,
+    /// Copy the jot IDs to the clipboard.
+    CopyId,
+    /// Print the path of each jot file.
+    PrintPath,
+    /// Add tags to the jots.
+    Tag,
+    /// Move the jots to another notebook.
+    Move,
+}
+
+/// Arguments for the `config` subcommand.
+#[derive(Args, Debug)]
+pub struct ConfigArgs {
+    /// The config action to perform.
+    #[command(subcommand)]
+    pub action: ConfigAction,
+}
+
+/// An enumeration of all possible config actions.
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Print the effective value of a setting.
+    Get {
+        /// The name of the setting (e.g., `editor` or `list_count`).
+        #[arg(required = true)]
+        key: String,
+    },
+    /// Change a setting.
+    Set {
+        /// The name of the setting.
+        #[arg(required = true)]
+        key: String,
+        /// The new value.
+        #[arg(required = true)]
+        value: String,
+        /// Which config file to change.
+        #[arg(long, value_enum, default_value_t = ConfigScope::User)]
+        scope: ConfigScope,
+    },
+    /// Remove a setting, so a lower level or the built-in default applies.
+    Unset {
+        /// The name of the setting.
+        #[arg(required = true)]
+        key: String,
+        /// Which config file to change.
+        #[arg(long, value_enum, default_value_t = ConfigScope::User)]
+        scope: ConfigScope,
+    },
+    /// List every setting with its effective value and where it was set.
+    #[command(alias = "ls")]
+    List,
+}
+
+/// Which config file `config set` and `config unset` change.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigScope {
+    /// The system-wide config file, shared by every user.
+    System,
+    /// The user's `config.toml` in the rjot directory.
+    User,
+    /// The active notebook's `notebook.toml`.
+    Notebook,
+}
+
+/// Arguments for the `template` subcommand.
+#[derive(Args, Debug)]
+pub struct TemplateArgs {
+    /// The template action to perform.
+    #[command(subcommand)]
+    pub action: TemplateAction,
+}
+
+/// An enumeration of all possible template actions.
+#[derive(Subcommand, Debug)]
+pub enum TemplateAction {
+    /// List all templates.
+    #[command(alias = "ls")]
+    List,
+    /// Print a template's source.
+    Show {
+        /// The name of the template.
+        #[arg(required = true)]
+        name: String,
+    },
+    /// Create a new template and open it in your editor.
+    New {
+        /// The name for the new template.
+        #[arg(required = true)]
+        name: String,
+    },
+    /// Open a template in your editor.
+    Edit {
+        /// The name of the template.
+        #[arg(required = true)]
+        name: String,
+    },
+    /// Delete a template.
+    #[command(alias = "rm")]
+    Delete {
+        /// The name of the template.
+        #[arg(required = true)]
+        name: String,
+        /// Delete without a confirmation prompt.
+        #[arg(long, short)]
+        force: bool,
+    },
 }
 
 /// Arguments for the `notebook` subcommand.
@@ -217,17 +470,69 @@ pub enum NotebookAction {
     },
     /// List all available notebooks.
     #[command(alias = "ls")]
-    List,
-    /// Print the command to switch the active notebook for the current shell session.
+    List {
+        /// Include archived notebooks.
+        #[arg(long, short)]
+        all: bool,
+    },
+    /// Switch the active notebook. The choice is saved and applies to new shells too.
     ///
-    /// Usage: eval $(rjot notebook use <NAME>)
+    /// When the output is captured, as in `eval $(rjot notebook use <NAME>)`, an `export`
+    /// line for the current shell is printed as well.
     Use {
         /// The name of the notebook to switch to.
         #[arg(required = true)]
         name: String,
+        /// Bind the current directory (and everything below it) to the notebook instead,
+        /// by writing a `.rjot-notebook` file.
+        #[arg(long)]
+        here: bool,
     },
     /// Show the currently active notebook.
     Status,
+    /// Rename a notebook.
+    Rename {
+        /// The current name of the notebook.
+        #[arg(required = true)]
+        old: String,
+        /// The new name for the notebook.
+        #[arg(required = true)]
+        new: String,
+    },
+    /// Delete a notebook by moving it to the trash.
+    #[command(alias = "rm")]
+    Delete {
+        /// The name of the notebook to delete.
+        #[arg(required = true)]
+        name: String,
+        /// Delete without a confirmation prompt.
+        #[arg(long, short)]
+        force: bool,
+        /// Delete the notebook permanently instead of moving it to the trash.
+        #[arg(long)]
+        purge: bool,
+    },
+    /// Archive a notebook, hiding it from `notebook list`, `find --all`, and stats.
+    Archive {
+        /// The name of the notebook to archive.
+        #[arg(required = true)]
+        name: String,
+    },
+    /// Bring an archived notebook back.
+    Unarchive {
+        /// The name of the notebook to unarchive.
+        #[arg(required = true)]
+        name: String,
+    },
+    /// Move every jot from one notebook into another and remove the first.
+    Merge {
+        /// The notebook to merge from. It is removed afterwards.
+        #[arg(required = true)]
+        source: String,
+        /// The notebook to merge into.
+        #[arg(required = true)]
+        destination: String,
+    },
 }
 
 /// Arguments for the `info` subcommand.
@@ -242,6 +547,29 @@ pub struct InfoArgs {
     /// Show stats for all notebooks combined.
     #[arg(long, requires = "stats")]
     pub all: bool,
+    /// Include archived notebooks in the combined stats.
+    #[arg(long, requires = "all")]
+    pub include_archived: bool,
+}
+
+/// Arguments for the `move` and `copy` subcommands.
+#[derive(Args, Debug)]
+pub struct TransferArgs {
+    /// The prefix of the jot ID. Must be unique.
+    #[arg(group = "target", required = true)]
+    pub id_prefix: Option<String>,
+    /// Target the Nth most recent jot.
+    #[arg(long, short, group = "target", num_args(0..=1), default_missing_value = "1")]
+    pub last: Option<usize>,
+    /// Target every jot with any of these tags (comma-separated).
+    #[arg(long, short, group = "target", value_delimiter = ',')]
+    pub tag: Option<Vec<String>>,
+    /// Target every jot from a date or date range (e.g., 2025-07-21 or 2025-07-01..2025-07-31).
+    #[arg(long, group = "target")]
+    pub on: Option<String>,
+    /// The notebook to put the jots in.
+    #[arg(long, required = true)]
+    pub to: String,
 }
 
 /// Arguments for the `tag` subcommand.
@@ -308,6 +636,26 @@ pub struct ExportArgs {
     /// The path for the output file.
     #[arg(long, short, required = true)]
     pub output: PathBuf,
+
+    /// Wrap the archive in `age` encryption.
+    ///
+    /// Uses the notebook's own key unless `--recipient` or `--passphrase` is given.
+    #[arg(long)]
+    pub encrypt: bool,
+
+    /// Encrypt the archive for this `age` recipient (public key).
+    #[arg(long, requires = "encrypt", conflicts_with = "passphrase")]
+    pub recipient: Option<String>,
+
+    /// Encrypt the archive with a passphrase (read from $RJOT_PASSPHRASE or prompted).
+    #[arg(long, requires = "encrypt")]
+    pub passphrase: bool,
+
+    /// Export the note files exactly as stored on disk, without decrypting them.
+    ///
+    /// Useful for backups of encrypted notebooks. Only supported for zip exports.
+    #[arg(long)]
+    pub raw: bool,
 }
 
 /// Arguments for the `import` subcommand.
@@ -4,26 +4,18 @@
 //! It uses functions from the `helpers` module to interact with the file system
 //! and perform other utility tasks.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs;
-use std::io::{self, Read, Write};
-use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process;
 
-use age::{secrecy::ExposeSecret, x25519, Decryptor, Identity};
+use age::{secrecy::ExposeSecret, x25519, Identity};
 use anyhow::{anyhow, bail, Context, Result};
-use chrono::{Datelike, Local, NaiveDate};
-use clap::Parser;
-use git2::{Cred, PushOptions, RemoteCallbacks, Repository, Signature};
-use rand::Rng;
-use rustyline::completion::Completer;
-use rustyline::config::Configurer;
-use rustyline::CompletionType;
-use rustyline::Editor;
-use rustyline_derive::{Helper, Highlighter, Hinter, Validator};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDate};
+use git2::Repository;
 use serde::{Deserialize, Serialize};
-use std::fs::File;
 use uuid::Uuid;
 use zip::write::{FileOptions, ZipWriter};
 use zip::ZipArchive;
@@ -31,18 +23,24 @@ use zip::ZipArchive;
 // Conditionally compile everything related to skim
 #[cfg(not(windows))]
 use {
+    crate::cli::{SelectAction, SelectArgs},
+    clap::ValueEnum,
     crossbeam_channel::unbounded,
     skim::prelude::*,
     std::{borrow::Cow, sync::Arc},
 };
 
 use crate::cli::{
-    ExportArgs, ImportArgs, InfoArgs, NotebookAction, NotebookArgs, TagAction, TagArgs,
+    ConfigAction, ConfigArgs, ConfigScope, ExportArgs, ImportArgs, InfoArgs, NotebookAction,
+    NotebookArgs, ResolveStrategy, TagAction, TagArgs, TemplateAction, TemplateArgs, TransferArgs,
 };
+use crate::git::{self, MergeOutcome};
 use crate::helpers::{
     self, display_note_list, get_note_path_for_action, get_notebooks_dir, get_rjot_dir_root,
-    get_templates_dir, parse_note_from_file, Frontmatter, TaskStats,
+    get_templates_dir, list_note_paths, load_notebook_config, parse_note_from_file,
+    save_notebook_config, Frontmatter, TaskStats, IDENTITY_FILE,
 };
+use crate::template;
 
 #[derive(Serialize, Deserialize, Debug)]
 struct JsonExport {
@@ -56,68 +54,237 @@ struct JsonJot {
     content: String,
 }
 
-// Define a helper struct for rustyline autocompletion and hints.
-#[derive(Helper, Hinter, Highlighter, Validator)]
-struct RjotHelper {
-    // This can be expanded later to hold state, like a list of notebook names or tags.
+// --- Config Commands ---
+
+/// Handles all `config` subcommands.
+pub fn command_config(args: ConfigArgs, notebook: Option<String>) -> Result<()> {
+    match args.action {
+        ConfigAction::Get { key } => command_config_get(&key, notebook),
+        ConfigAction::Set { key, value, scope } => {
+            command_config_update(&key, Some(&value), scope, notebook)
+        }
+        ConfigAction::Unset { key, scope } => command_config_update(&key, None, scope, notebook),
+        ConfigAction::List => command_config_list(notebook),
+    }
 }
 
-// Implement the completion logic.
-impl Completer for RjotHelper {
-    type Candidate = String;
+/// Returns the active notebook's directory if it exists, so its `notebook.toml` applies.
+fn config_notebook_dir(notebook: Option<String>) -> Result<Option<PathBuf>> {
+    let (name, _) = helpers::resolve_active_notebook(notebook)?;
+    let notebook_dir = helpers::existing_notebook_path(&name)?;
+    if notebook_dir.is_none() {
+        helpers::validate_notebook_name(&name)?;
+    }
+    Ok(notebook_dir)
+}
 
-    fn complete(
-        &self,
-        line: &str,
-        pos: usize,
-        _ctx: &rustyline::Context<'_>,
-    ) -> rustyline::Result<(usize, Vec<Self::Candidate>)> {
-        let mut candidates = Vec::new();
-        let mut start_pos = 0;
+/// Prints the effective value of a single setting.
+fn command_config_get(key: &str, notebook: Option<String>) -> Result<()> {
+    let notebook_dir = config_notebook_dir(notebook)?;
+    let preferences = helpers::load_preferences(notebook_dir.as_deref())?;
+    match preferences.get(key)? {
+        Some(value) => println!("{value}"),
+        None if key == "editor" => println!("{}", helpers::get_editor(&preferences)?.join(" ")),
+        None => println!("{}", helpers::preference_default(key)),
+    }
+    Ok(())
+}
 
-        let parts: Vec<&str> = line[..pos].split_whitespace().collect();
-        let parts_count = parts.len();
+/// Prints every setting with its effective value and the level that set it.
+fn command_config_list(notebook: Option<String>) -> Result<()> {
+    let notebook_dir = config_notebook_dir(notebook)?;
+    let layers = helpers::preference_layers(notebook_dir.as_deref())?;
+    let mut rows = Vec::new();
+    for key in helpers::PREFERENCE_KEYS {
+        let mut row = (key, helpers::preference_default(key).to_string(), "default");
+        for (level, preferences) in &layers {
+            if let Some(value) = preferences.get(key)? {
+                row = (key, value, level);
+            }
+        }
+        rows.push(row);
+    }
+    let width = rows
+        .iter()
+        .map(|(_, value, _)| value.len())
+        .max()
+        .unwrap_or(0);
+    for (key, value, level) in rows {
+        println!("{key:<16} = {value:<width$}  ({level})");
+    }
+    Ok(())
+}
 
-        // If the line is empty or we are still typing the first word.
-        if parts.is_empty() || (parts_count == 1 && !line.ends_with(' ')) {
-            let first_word = parts.first().unwrap_or(&"");
-            start_pos = pos - first_word.len(); // Start replacement at the beginning of the current word.
+/// Sets or, when `value` is `None`, removes a setting at one level.
+fn command_config_update(
+    key: &str,
+    value: Option<&str>,
+    scope: ConfigScope,
+    notebook: Option<String>,
+) -> Result<()> {
+    let location = match scope {
+        ConfigScope::System => {
+            let mut preferences = helpers::load_system_preferences()?;
+            preferences.set(key, value)?;
+            helpers::save_system_preferences(&preferences)?;
+            format!("{:?}", helpers::system_config_path())
+        }
+        ConfigScope::User => {
+            let mut config = helpers::load_config()?;
+            config.preferences.set(key, value)?;
+            helpers::save_config(&config)?;
+            "config.toml".to_string()
+        }
+        ConfigScope::Notebook => {
+            if key == "default_notebook" {
+                bail!("'default_notebook' can't be set for a single notebook.");
+            }
+            let (name, _) = helpers::resolve_active_notebook(notebook)?;
+            let notebook_dir = existing_notebook_dir(&name)?;
+            let mut notebook_config = load_notebook_config(&notebook_dir)?;
+            notebook_config.preferences.set(key, value)?;
+            helpers::save_notebook_config(&notebook_dir, &notebook_config)?;
+            format!("the '{name}' notebook")
+        }
+    };
+    match value {
+        Some(value) => println!("Set {key} = {value} in {location}."),
+        None => println!("Unset {key} in {location}."),
+    }
+    Ok(())
+}
 
-            let all_commands = vec![
-                "list", "find", "new", "task", "todo", "t", "today", "week", "tags", "notebook",
-                "pin", "unpin", "edit", "show", "delete", "info", "use", "exit", "quit",
-            ];
+// --- Template Commands ---
 
-            for cmd in all_commands {
-                if cmd.starts_with(first_word) {
-                    candidates.push(cmd.to_string());
-                }
-            }
-        // If we are completing the argument for `use` or `notebook`.
-        } else if parts_count > 0 {
-            let command = parts[0];
-            if command == "use" || command == "notebook" {
-                let current_arg = parts.get(1).unwrap_or(&"");
-                // The replacement should start at the beginning of the notebook name argument.
-                start_pos = pos - current_arg.len();
-
-                if let Ok(notebooks_dir) = helpers::get_notebooks_dir() {
-                    if let Ok(entries) = std::fs::read_dir(notebooks_dir) {
-                        for entry in entries.filter_map(Result::ok) {
-                            if entry.path().is_dir() {
-                                let notebook_name = entry.file_name().to_string_lossy().to_string();
-                                if notebook_name.starts_with(current_arg) {
-                                    candidates.push(notebook_name);
-                                }
-                            }
-                        }
-                    }
+/// Handles all `template` subcommands.
+pub fn command_template(args: TemplateArgs) -> Result<()> {
+    match args.action {
+        TemplateAction::List => command_template_list(),
+        TemplateAction::Show { name } => {
+            print!("{}", fs::read_to_string(existing_template_path(&name)?)?);
+            Ok(())
+        }
+        TemplateAction::New { name } => command_template_new(&name),
+        TemplateAction::Edit { name } => {
+            let path = existing_template_path(&name)?;
+            helpers::open_in_editor(
+                &helpers::get_editor(&helpers::load_preferences(None)?)?,
+                &path,
+            )?;
+            println!("Finished editing template '{name}'.");
+            git::auto_commit(&format!("template edit {name}"))
+        }
+        TemplateAction::Delete { name, force } => command_template_delete(&name, force),
+    }
+}
+
+/// Builds the error for a template that doesn't exist.
+fn template_not_found(name: &str) -> anyhow::Error {
+    anyhow!("Template '{name}' not found. Run `rjot template list` to see your templates.")
+}
+
+/// Returns the path of an existing template, or an error if it doesn't exist.
+fn existing_template_path(name: &str) -> Result<PathBuf> {
+    let path = template_path(&get_templates_dir()?, name)?;
+    if !path.is_file() {
+        return Err(template_not_found(name));
+    }
+    Ok(path)
+}
+
+/// Collects the names of all templates, including those in subdirectories.
+pub fn template_names(dir: &Path, prefix: &str, names: &mut Vec<String>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap().to_string_lossy();
+        if path.is_dir() {
+            template_names(&path, &format!("{prefix}{file_name}/"), names)?;
+        } else if let Some(stem) = file_name.strip_suffix(".md") {
+            names.push(format!("{prefix}{stem}"));
+        }
+    }
+    Ok(())
+}
+
+/// Lists all templates with their descriptions and target notebooks.
+fn command_template_list() -> Result<()> {
+    let templates_dir = get_templates_dir()?;
+    let mut names = Vec::new();
+    template_names(&templates_dir, "", &mut names)?;
+    names.sort();
+    if names.is_empty() {
+        println!("No templates found. Create one with `rjot template new <name>`.");
+        return Ok(());
+    }
+
+    let width = names.iter().map(String::len).max().unwrap_or(0);
+    println!("Available templates:");
+    for name in names {
+        let source = fs::read_to_string(template_path(&templates_dir, &name)?)?;
+        let about = match template::split_meta(&source) {
+            Ok((meta, _)) => {
+                let mut about = meta.description.unwrap_or_default();
+                if let Some(notebook) = meta.notebook {
+                    about = format!("{about} (notebook: {notebook})");
                 }
+                about
             }
-        }
+            Err(e) => format!("(invalid: {e})"),
+        };
+        let line = format!("  {name:<width$}  {}", about.trim());
+        println!("{}", line.trim_end());
+    }
+    Ok(())
+}
 
-        Ok((start_pos, candidates))
+/// Creates a template from a starter skeleton and opens it in the editor.
+fn command_template_new(name: &str) -> Result<()> {
+    let path = template_path(&get_templates_dir()?, name)?;
+    if path.exists() {
+        bail!("Template '{name}' already exists. Edit it with `rjot template edit {name}`.");
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(
+        &path,
+        format!(
+            "---\n\
+             template:\n  \
+             description: {name}\n  \
+             # notebook: {name}\n  \
+             # tags: [{name}]\n  \
+             # pinned: false\n  \
+             # variables:\n  \
+             #   title: What is this jot about?\n\
+             ---\n\n\
+             # {{{{ date | format(\"%A, %B %d\") }}}}\n\n"
+        ),
+    )?;
+    helpers::open_in_editor(
+        &helpers::get_editor(&helpers::load_preferences(None)?)?,
+        &path,
+    )?;
+    println!("Created template '{name}' at {path:?}.");
+    git::auto_commit(&format!("template new {name}"))
+}
+
+/// Deletes a template after confirmation.
+fn command_template_delete(name: &str, force: bool) -> Result<()> {
+    let path = existing_template_path(name)?;
+    if !force {
+        print!("Are you sure you want to delete template '{name}'? [y/N] ");
+        io::stdout().flush()?;
+        let mut confirmation = String::new();
+        io::stdin().read_line(&mut confirmation)?;
+        if confirmation.trim().to_lowercase() != "y" {
+            println!("Deletion aborted.");
+            return Ok(());
+        }
     }
+    fs::remove_file(&path)?;
+    println!("Deleted template '{name}'.");
+    git::auto_commit(&format!("template delete {name}"))
 }
 
 // --- Notebook Commands ---
@@ -126,22 +293,35 @@ impl Completer for RjotHelper {
 pub fn command_notebook(args: NotebookArgs) -> Result<()> {
     match args.action {
         NotebookAction::New { name } => command_notebook_new(&name)?,
-        NotebookAction::List => command_notebook_list()?,
-        NotebookAction::Use { name } => command_notebook_use(&name)?,
+        NotebookAction::List { all } => command_notebook_list(all)?,
+        NotebookAction::Use { name, here } => command_notebook_use(&name, here)?,
         NotebookAction::Status => command_notebook_status()?,
+        NotebookAction::Rename { old, new } => command_notebook_rename(&old, &new)?,
+        NotebookAction::Delete { name, force, purge } => {
+            command_notebook_delete(&name, force, purge)?
+        }
+        NotebookAction::Archive { name } => command_notebook_set_archived(&name, true)?,
+        NotebookAction::Unarchive { name } => command_notebook_set_archived(&name, false)?,
+        NotebookAction::Merge {
+            source,
+            destination,
+        } => command_notebook_merge(&source, &destination)?,
     }
     Ok(())
 }
 
+/// Returns the directory of an existing notebook, or an error if it doesn't exist.
+pub fn existing_notebook_dir(name: &str) -> Result<PathBuf> {
+    if let Some(path) = helpers::existing_notebook_path(name)? {
+        return Ok(path);
+    }
+    helpers::validate_notebook_name(name)?;
+    Err(helpers::notebook_not_found(name))
+}
+
 /// Creates a new notebook directory.
 fn command_notebook_new(name: &str) -> Result<()> {
-    // Basic sanitization to prevent directory traversal or invalid names.
-    if name.contains('/') || name.contains('\\') || name == "." || name == ".." {
-        bail!(
-            "Invalid notebook name: '{}'. Names cannot contain slashes or be dots.",
-            name
-        );
-    }
+    helpers::validate_notebook_name(name)?;
 
     let notebooks_dir = get_notebooks_dir()?;
     let new_notebook_path = notebooks_dir.join(name);
@@ -155,209 +335,252 @@ fn command_notebook_new(name: &str) -> Result<()> {
     Ok(())
 }
 
-/// Lists all available notebooks.
-fn command_notebook_list() -> Result<()> {
-    let notebooks_dir = get_notebooks_dir()?;
-    let active_notebook =
-        env::var("RJOT_ACTIVE_NOTEBOOK").unwrap_or_else(|_| "default".to_string());
+/// Lists all available notebooks, including archived ones if `all` is set.
+///
+/// Each line shows the jot count, followed by the display name and description
+/// from the notebook's `notebook.toml`, if set.
+fn command_notebook_list(all: bool) -> Result<()> {
+    let active_notebook = helpers::active_notebook_name()?;
+
+    let mut rows = Vec::new();
+    for notebook_path in helpers::list_notebooks(all)? {
+        let notebook_name = notebook_path.file_name().unwrap().to_string_lossy();
+        let notebook_config = load_notebook_config(&notebook_path)?;
+        let mut label = notebook_name.to_string();
+        if notebook_config.archived {
+            label.push_str(" (archived)");
+        }
+        let count = list_note_paths(&notebook_path)?.len();
+        let about = match (notebook_config.display_name, notebook_config.description) {
+            (Some(display_name), Some(description)) => format!("{display_name}: {description}"),
+            (Some(text), None) | (None, Some(text)) => text,
+            (None, None) => String::new(),
+        };
+        rows.push((notebook_name == active_notebook, label, count, about));
+    }
+    let width = rows
+        .iter()
+        .map(|(_, label, ..)| label.len())
+        .max()
+        .unwrap_or(0);
 
     println!("Available notebooks (* indicates active):");
-
-    for entry in fs::read_dir(notebooks_dir)?.filter_map(Result::ok) {
-        if entry.path().is_dir() {
-            let notebook_name = entry.file_name().to_string_lossy().to_string();
-            let prefix = if notebook_name == active_notebook {
-                "*"
-            } else {
-                " "
-            };
-            println!("  {prefix} {notebook_name}");
-        }
+    for (active, label, count, about) in rows {
+        let prefix = if active { "*" } else { " " };
+        let jots = format!("{count} {}", if count == 1 { "jot" } else { "jots" });
+        let line = format!("  {prefix} {label:<width$}  {jots:>9}  {about}");
+        println!("{}", line.trim_end());
     }
     Ok(())
 }
 
-/// Prints the shell command to switch the active notebook.
-fn command_notebook_use(name: &str) -> Result<()> {
-    let notebooks_dir = get_notebooks_dir()?;
-    let target_notebook = notebooks_dir.join(name);
+/// Renames a notebook.
+fn command_notebook_rename(old: &str, new: &str) -> Result<()> {
+    let old_path = existing_notebook_dir(old)?;
+    helpers::validate_notebook_name(new)?;
+    let new_path = get_notebooks_dir()?.join(new);
+    if new_path.exists() {
+        bail!(
+            "Notebook '{}' already exists. Use `rjot notebook merge {} {}` to combine them.",
+            new,
+            old,
+            new
+        );
+    }
+
+    fs::rename(&old_path, &new_path)?;
+    println!("Renamed notebook '{old}' to '{new}'.");
+
+    // Keep the saved active notebook pointing at the renamed notebook.
+    let mut config = helpers::load_config()?;
+    if config.active_notebook.as_deref() == Some(old) {
+        config.active_notebook = Some(new.to_string());
+        helpers::save_config(&config)?;
+    }
+    match helpers::resolve_active_notebook(None)? {
+        (active, helpers::NotebookSource::Env) if active == old => {
+            println!("'{old}' is active in this shell. Switch to the new name with:");
+            println!("  eval $(rjot notebook use {new})");
+        }
+        (active, helpers::NotebookSource::Directory(path)) if active == old => {
+            println!("Update {path:?} to use the new name.");
+        }
+        _ => {}
+    }
+    git::auto_commit(&format!("notebook rename {old} to {new}"))
+}
 
-    if !target_notebook.exists() || !target_notebook.is_dir() {
+/// Deletes a notebook, moving it into `.trash` in the rjot root unless `purge` is set.
+fn command_notebook_delete(name: &str, force: bool, purge: bool) -> Result<()> {
+    let notebook_path = existing_notebook_dir(name)?;
+    if helpers::active_notebook_name()? == name {
         bail!(
-            "Notebook '{}' not found. Create it with `rjot notebook new {}`.",
-            name,
+            "Cannot delete '{}' while it is the active notebook. Switch to another notebook first.",
             name
         );
     }
 
-    // This command prints the shell command for the user to evaluate.
-    // It cannot modify the parent shell's environment directly.
-    println!("export RJOT_ACTIVE_NOTEBOOK=\"{name}\"");
-    Ok(())
+    if !force {
+        let jot_count = list_note_paths(&notebook_path)?.len();
+        let action = if purge {
+            "permanently delete"
+        } else {
+            "move to the trash"
+        };
+        print!("Are you sure you want to {action} notebook '{name}' and its {jot_count} jot(s)? [y/N] ");
+        io::stdout().flush()?;
+        let mut confirmation = String::new();
+        io::stdin().read_line(&mut confirmation)?;
+        if confirmation.trim().to_lowercase() != "y" {
+            println!("Deletion aborted.");
+            return Ok(());
+        }
+    }
+
+    if purge {
+        fs::remove_dir_all(&notebook_path)?;
+        println!("Permanently deleted notebook '{name}'.");
+    } else {
+        let trash_dir = get_rjot_dir_root()?.join(".trash");
+        fs::create_dir_all(&trash_dir)?;
+        let trash_path = trash_dir.join(format!("{name}-{}", Local::now().format("%Y%m%d-%H%M%S")));
+        fs::rename(&notebook_path, &trash_path)?;
+        println!("Moved notebook '{name}' to {trash_path:?}.");
+    }
+    git::auto_commit(&format!("notebook delete {name}"))
 }
 
-/// Shows the currently active notebook.
-fn command_notebook_status() -> Result<()> {
-    let active_notebook =
-        env::var("RJOT_ACTIVE_NOTEBOOK").unwrap_or_else(|_| "default".to_string());
-    println!("Active notebook: {active_notebook}");
-    Ok(())
+/// Archives or unarchives a notebook.
+fn command_notebook_set_archived(name: &str, archived: bool) -> Result<()> {
+    let notebook_path = existing_notebook_dir(name)?;
+    let mut config = load_notebook_config(&notebook_path)?;
+    let action = if archived { "archive" } else { "unarchive" };
+    if config.archived == archived {
+        println!("Notebook '{name}' is already {action}d.");
+        return Ok(());
+    }
+
+    config.archived = archived;
+    save_notebook_config(&notebook_path, &config)?;
+    println!("Successfully {action}d notebook '{name}'.");
+    git::auto_commit(&format!("notebook {action} {name}"))
 }
 
-// --- Other Commands ---
+/// Moves every jot from `source` into `destination` and removes `source`.
+///
+/// If `source` was the saved active notebook, `destination` becomes the active one.
+///
+/// Jots are re-encrypted according to the destination's policy. A jot whose ID is
+/// already taken in the destination gets the next free ID.
+fn command_notebook_merge(source: &str, destination: &str) -> Result<()> {
+    if source == destination {
+        bail!("Cannot merge a notebook into itself.");
+    }
+    let source_path = existing_notebook_dir(source)?;
+    let destination_path = existing_notebook_dir(destination)?;
 
-/// Enters the interactive rjot shell.
-pub fn command_shell() -> Result<()> {
-    const VERSION: &str = env!("CARGO_PKG_VERSION");
-    let mut active_notebook =
-        env::var("RJOT_ACTIVE_NOTEBOOK").unwrap_or_else(|_| "default".to_string());
-
-    let entries_dir = helpers::get_active_entries_dir(Some(active_notebook.clone()))?;
-    let (note_count, _, _) = calculate_stats_for_dir(&entries_dir).unwrap_or((
-        0,
-        Default::default(),
-        Default::default(),
-    ));
-
-    let tips = [
-        // Shell Tips
-        "In the shell, type `use <name>` and press Tab to autocomplete notebook names.",
-        "Use the Up/Down arrow keys in the shell to navigate your command history.",
-        "You can exit the shell at any time with `exit`, `quit`, or by pressing Ctrl-D.",
-        // Basic Usage Tips
-        "The `t` command is a fast alias for `task`. Try `t 'My new task'`.",
-        "You can use `rm` as a shorter alias for the `delete` command.",
-        "Tags can be comma-separated (`-t a,b`) or space-separated (`-t a b`).",
-        // Advanced Viewing & Filtering
-        "Filter for a date range like this: `on 2025-01-01..2025-01-31`.",
-        "Compile a full week's notes into a single file with `week --compile > summary.md`.",
-        "Pin important notes with `pin <ID>` and view them with `list --pinned`.",
-        "Find notes with multiple tags, like `tags rust,project`.",
-        // Note Management
-        "You can edit the last jot you created instantly with `edit --last`.",
-        "The `--force` flag on `delete` and `decrypt` will skip confirmation prompts.",
-        "Use a unique prefix of a jot's ID for any command, like `show 2025-07-21`.",
-        // Configuration & Templates
-        "Create custom note structures for `new` by adding files to your templates directory.",
-        "Find your templates folder and other important paths with `info --paths`.",
-        "Pass custom variables to your templates with the `-v` flag, like `new -t bug -v id=123`.",
-        // Notebooks & Syncing
-        "Run a single command in another notebook with the global `--notebook <name>` flag.",
-        "Use `notebook status` to quickly check which notebook is active.",
-        "After setting up a git remote, use `sync` to commit and push all changes.",
-    ];
-    let mut rng = rand::thread_rng();
-    let tip = tips[rng.gen_range(0..tips.len())];
-
-    // Use the new oh-my-logo generated ASCII art
-    let startup_message = format!(
-        "\n\
-        \x1b[38;5;208m ██████╗       ██╗  ██████╗  ████████╗\x1b[0m\n\
-        \x1b[38;5;209m ██╔══██╗      ██║ ██╔═══██╗ ╚══██╔══╝\x1b[0m\n\
-        \x1b[38;5;210m ██████╔╝      ██║ ██║   ██║    ██║   \x1b[0m\n\
-        \x1b[38;5;211m ██╔══██╗ ██   ██║ ██║   ██║    ██║   \x1b[0m\n\
-        \x1b[38;5;212m ██║  ██║ ╚█████╔╝ ╚██████╔╝    ██║   \x1b[0m\n\
-        \x1b[38;5;213m ╚═╝  ╚═╝  ╚════╝   ╚═════╝     ╚═╝   \x1b[0m\n\
-        \n  \x1b[0;1mrjot v{}\x1b[0m | Today: \x1b[32m{}\x1b[0m | Stats: \x1b[33m{} notes in '{}'\x1b[0m\n  \
-        \x1b[2mTip: {}\x1b[0m\n  \
-        \x1b[2mType 'exit' or 'quit' to leave the shell.\x1b[0m\n",
-        VERSION,
-        chrono::Local::now().format("%Y-%m-%d"),
-        note_count,
-        active_notebook,
-        tip
-    );
+    // Read everything first so an undecryptable jot aborts the merge before any changes.
+    let mut jots = Vec::new();
+    for path in list_note_paths(&source_path)? {
+        jots.push((note_id(&path), helpers::read_note_file(&path)?));
+    }
+    jots.sort();
+
+    for (id, content) in &jots {
+        let target = helpers::unique_note_path(&destination_path, id);
+        helpers::write_note_file(&target, content)?;
+        let new_id = note_id(&target);
+        if &new_id != id {
+            println!("  - '{id}' already exists in '{destination}'; saved as '{new_id}'.");
+        }
+    }
+    fs::remove_dir_all(&source_path)?;
 
-    let helper = RjotHelper {};
-    let mut rl = Editor::new()?;
-    rl.set_helper(Some(helper));
-    rl.set_completion_type(CompletionType::List);
+    println!(
+        "Merged {} jot(s) from '{}' into '{}'.",
+        jots.len(),
+        source,
+        destination
+    );
 
-    if rl.load_history("history.txt").is_err() {
-        // Not a critical error.
+    // Like a rename, the saved active notebook follows the jots to the destination.
+    let mut config = helpers::load_config()?;
+    if config.active_notebook.as_deref() == Some(source) {
+        config.active_notebook = Some(destination.to_string());
+        helpers::save_config(&config)?;
+        println!("Active notebook set to '{destination}'.");
     }
+    match helpers::resolve_active_notebook(None)? {
+        (active, helpers::NotebookSource::Env) if active == source => {
+            println!("'{source}' is active in this shell. Switch to '{destination}' with:");
+            println!("  eval $(rjot notebook use {destination})");
+        }
+        (active, helpers::NotebookSource::Directory(path)) if active == source => {
+            println!("Update {path:?} to use '{destination}'.");
+        }
+        _ => {}
+    }
+    git::auto_commit(&format!("notebook merge {source} into {destination}"))
+}
 
-    println!("{startup_message}");
+/// Switches the active notebook, or binds the current directory to it with `here`.
+fn command_notebook_use(name: &str, here: bool) -> Result<()> {
+    existing_notebook_dir(name)?;
 
-    loop {
-        let prompt = format!("\x1b[1m\x1b[35mrjot\x1b[0m(\x1b[33m{active_notebook}\x1b[0m)> ");
-        let readline = rl.readline(&prompt);
-
-        match readline {
-            Ok(line) => {
-                let _ = rl.add_history_entry(line.as_str());
-                let line = line.trim();
-                if line.is_empty() {
-                    continue;
-                }
+    if here {
+        let binding_path = env::current_dir()?.join(helpers::NOTEBOOK_BINDING_FILE);
+        fs::write(&binding_path, format!("{name}\n"))?;
+        println!(
+            "Bound {:?} to notebook '{name}'.",
+            binding_path.parent().unwrap()
+        );
+        return Ok(());
+    }
 
-                let mut parts = line.split_whitespace();
-                let command_name = parts.next().unwrap_or("");
-                match command_name {
-                    "exit" | "quit" => break,
-                    "use" => {
-                        if let Some(name) = parts.next() {
-                            let notebooks_dir = helpers::get_notebooks_dir()?;
-                            if notebooks_dir.join(name).is_dir() {
-                                active_notebook = name.to_string();
-                                println!("Active notebook switched to '{active_notebook}'.");
-                            } else {
-                                eprintln!("Error: Notebook '{name}' not found.");
-                            }
-                        } else {
-                            eprintln!("Usage: use <NOTEBOOK_NAME>");
-                        }
-                        continue;
-                    }
-                    _ => {}
-                }
+    let mut config = helpers::load_config()?;
+    config.active_notebook = Some(name.to_string());
+    helpers::save_config(&config)?;
 
-                let mut args = vec!["rjot"];
-                args.extend(line.split_whitespace());
-
-                match crate::cli::Cli::try_parse_from(args) {
-                    Ok(cli) => {
-                        let notebook_override = cli
-                            .notebook
-                            .clone()
-                            .unwrap_or_else(|| active_notebook.clone());
-                        let entries_dir =
-                            crate::helpers::get_active_entries_dir(Some(notebook_override))?;
-
-                        if let Some(command) = cli.command {
-                            if let Err(e) = crate::run_command(command, entries_dir) {
-                                eprintln!("Error: {e}");
-                            }
-                        } else if !cli.message.is_empty() {
-                            let message = cli.message.join(" ");
-                            if let Err(e) = command_down(&entries_dir, &message, cli.tags) {
-                                eprintln!("Error: {e}");
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        e.print().unwrap_or_default();
-                    }
-                }
-            }
-            Err(rustyline::error::ReadlineError::Interrupted) => {
-                println!("\nInterrupted (Ctrl-C). Type 'exit' or press Ctrl-D to leave.");
-            }
-            Err(rustyline::error::ReadlineError::Eof) => {
-                break;
-            }
-            Err(err) => {
-                println!("Shell Error: {err:?}");
-                break;
-            }
+    if io::stdout().is_terminal() {
+        println!("Active notebook set to '{name}'.");
+    } else {
+        // The output is being captured, most likely by `eval $(rjot notebook use ...)`,
+        // so also switch the calling shell. The name is single-quoted so the shell
+        // doesn't expand anything in it.
+        let quoted = name.replace('\'', r"'\''");
+        println!("export RJOT_ACTIVE_NOTEBOOK='{quoted}'");
+    }
+    match helpers::resolve_active_notebook(None)? {
+        (active, helpers::NotebookSource::Env) if active != name => eprintln!(
+            "Note: RJOT_ACTIVE_NOTEBOOK is set to '{active}' in this shell and takes precedence."
+        ),
+        (active, helpers::NotebookSource::Directory(path)) if active != name => {
+            eprintln!("Note: {path:?} binds this directory to '{active}', which takes precedence.")
         }
+        _ => {}
     }
+    Ok(())
+}
 
-    let _ = rl.save_history("history.txt");
-    println!("Exiting rjot shell.");
+/// Shows the currently active notebook and where the choice came from.
+fn command_notebook_status() -> Result<()> {
+    let (active_notebook, source) = helpers::resolve_active_notebook(None)?;
+    let origin = match source {
+        helpers::NotebookSource::Flag => "the --notebook flag".to_string(),
+        helpers::NotebookSource::Env => "RJOT_ACTIVE_NOTEBOOK".to_string(),
+        helpers::NotebookSource::Directory(path) => format!("{path:?}"),
+        helpers::NotebookSource::Config => "`rjot notebook use`".to_string(),
+        helpers::NotebookSource::Default => {
+            println!("Active notebook: {active_notebook}");
+            return Ok(());
+        }
+    };
+    println!("Active notebook: {active_notebook} (set by {origin})");
     Ok(())
 }
 
+// --- Other Commands ---
+
 /// Initializes the `rjot` directory, optionally with Git and/or encryption.
 pub fn command_init(git: bool, encrypt: bool) -> Result<()> {
     let root_dir = get_rjot_dir_root()?;
@@ -372,7 +595,13 @@ pub fn command_init(git: bool, encrypt: bool) -> Result<()> {
                     println!("Git repository is not empty. Assuming it is already set up.");
                 } else if !gitignore_path.exists() {
                     // Correctly ignore only sensitive files. Notebooks should be tracked.
-                    fs::write(&gitignore_path, "identity.txt\nconfig.toml\n")?;
+                    fs::write(
+                        &gitignore_path,
+                        format!(
+                            "identity.txt\nconfig.toml\n.trash/\n{}\n",
+                            helpers::HISTORY_FILE
+                        ),
+                    )?;
                     println!("Created .gitignore to exclude sensitive files.");
 
                     let mut index = repo.index()?;
@@ -380,7 +609,7 @@ pub fn command_init(git: bool, encrypt: bool) -> Result<()> {
                     index.write()?;
                     let oid = index.write_tree()?;
                     let tree = repo.find_tree(oid)?;
-                    let signature = Signature::now("rjot", "rjot@localhost")?;
+                    let signature = git::signature(&repo)?;
                     repo.commit(
                         Some("HEAD"),
                         &signature,
@@ -411,9 +640,9 @@ pub fn command_init(git: bool, encrypt: bool) -> Result<()> {
             println!("Generated new encryption identity at: {identity_path:?}");
             println!("\nIMPORTANT: Back this file up somewhere safe!");
 
-            let config_path = root_dir.join("config.toml");
-            let config_str = format!("recipient = \"{recipient}\"");
-            fs::write(config_path, config_str)?;
+            let mut config = helpers::load_config()?;
+            config.recipient = Some(recipient.to_string());
+            helpers::save_config(&config)?;
             println!("Saved public key to config.toml.");
             println!("\nYour public key (recipient) is: {recipient}");
         }
@@ -421,12 +650,55 @@ pub fn command_init(git: bool, encrypt: bool) -> Result<()> {
     Ok(())
 }
 
-/// Permanently decrypts all notes in ALL notebooks. It no longer takes
-/// an `entries_dir` argument as it operates globally.
-pub fn command_decrypt(force: bool) -> Result<()> {
+/// Encrypts a single notebook with its own identity.
+///
+/// A new identity is generated inside the notebook directory (unless one already exists),
+/// the notebook's policy is set to always encrypt, and every existing note is rewritten
+/// so that it is encrypted for the notebook's key.
+pub fn command_encrypt(entries_dir: &Path) -> Result<()> {
+    let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
+    let identity_path = entries_dir.join(IDENTITY_FILE);
+    let mut notebook_config = load_notebook_config(entries_dir)?;
+
+    if identity_path.exists() {
+        println!("Notebook '{notebook_name}' already has its own encryption identity.");
+    } else {
+        let identity = x25519::Identity::generate();
+        let recipient = identity.to_public();
+        fs::write(&identity_path, identity.to_string().expose_secret())?;
+        notebook_config.recipient = Some(recipient.to_string());
+        println!("Generated new encryption identity at: {identity_path:?}");
+        println!("\nIMPORTANT: Back this file up somewhere safe!");
+        println!("\nThe notebook's public key (recipient) is: {recipient}");
+    }
+    notebook_config.encrypt = Some(true);
+    save_notebook_config(entries_dir, &notebook_config)?;
+
+    // Reading decrypts with whichever key was used before; writing re-encrypts with
+    // the notebook's own key.
+    let mut count = 0;
+    for path in list_note_paths(entries_dir)? {
+        let content = helpers::read_note_file(&path)?;
+        helpers::write_note_file(&path, &content)?;
+        count += 1;
+    }
+    println!("Encrypted {count} jot(s) in notebook '{notebook_name}'.");
+    Ok(())
+}
+
+/// Permanently decrypts notes.
+///
+/// With `notebook` set, only that notebook is decrypted and it is marked as plaintext,
+/// so new jots are no longer encrypted even if global encryption is enabled. Otherwise,
+/// every notebook that uses the global identity is decrypted and the global keys are removed.
+pub fn command_decrypt(force: bool, notebook: Option<&Path>) -> Result<()> {
+    if let Some(entries_dir) = notebook {
+        return command_decrypt_notebook(force, entries_dir);
+    }
+
     let root_dir = get_rjot_dir_root()?;
     let notebooks_dir = get_notebooks_dir()?;
-    let identity_path = root_dir.join("identity.txt");
+    let identity_path = root_dir.join(IDENTITY_FILE);
 
     if !identity_path.exists() {
         println!("Journal is not encrypted (no identity.txt found). Nothing to do.");
@@ -447,6 +719,7 @@ pub fn command_decrypt(force: bool) -> Result<()> {
     println!("Loading decryption key...");
     let identity_str = fs::read_to_string(&identity_path)?;
     let identity = identity_str
+        .trim()
         .parse::<x25519::Identity>()
         .map_err(|e| anyhow!(e))?;
     let identities: Vec<Box<dyn Identity>> = vec![Box::new(identity)];
@@ -455,263 +728,626 @@ pub fn command_decrypt(force: bool) -> Result<()> {
     for notebook_entry in fs::read_dir(notebooks_dir)?.filter_map(Result::ok) {
         if notebook_entry.path().is_dir() {
             let entries_dir = notebook_entry.path();
+            if entries_dir.join(IDENTITY_FILE).exists() {
+                println!(
+                    "\nSkipping notebook {:?}: it has its own key. Use `rjot decrypt --notebook` instead.",
+                    entries_dir.file_name().unwrap()
+                );
+                continue;
+            }
             println!(
                 "\nDecrypting notebook: {:?}",
                 entries_dir.file_name().unwrap()
             );
-            for entry in fs::read_dir(entries_dir)?.filter_map(Result::ok) {
-                let path = entry.path();
-                if path.is_file() {
-                    let file_bytes = fs::read(&path)?;
-                    if !file_bytes.starts_with(b"age-encryption.org") {
-                        println!(
-                            "  Skipping non-encrypted file: {:?}",
-                            path.file_name().unwrap()
-                        );
-                        continue;
-                    }
-
-                    let decryptor = Decryptor::new(&file_bytes as &[u8])?;
-                    if let Decryptor::Recipients(reader) = decryptor {
-                        let mut decrypted_bytes = vec![];
-                        reader
-                            .decrypt(identities.iter().map(|i| i.as_ref()))?
-                            .read_to_end(&mut decrypted_bytes)?;
-                        fs::write(&path, decrypted_bytes)?;
-                        println!("  - Decrypted {:?}", path.file_name().unwrap());
-                    }
+            for path in list_note_paths(&entries_dir)? {
+                let file_bytes = fs::read(&path)?;
+                if !helpers::is_encrypted(&file_bytes) {
+                    println!(
+                        "  Skipping non-encrypted file: {:?}",
+                        path.file_name().unwrap()
+                    );
+                    continue;
                 }
+
+                let decrypted_bytes = helpers::decrypt_with_identities(&file_bytes, &identities)?;
+                fs::write(&path, decrypted_bytes)?;
+                println!("  - Decrypted {:?}", path.file_name().unwrap());
+            }
+
+            // A notebook that always encrypts with the global key would have nothing to
+            // encrypt for once the key is gone, so it goes back to following the journal.
+            let mut notebook_config = load_notebook_config(&entries_dir)?;
+            if notebook_config.encrypt == Some(true) && notebook_config.recipient.is_none() {
+                notebook_config.encrypt = None;
+                save_notebook_config(&entries_dir, &notebook_config)?;
+                println!("  Turned off `encrypt = true`: the notebook used the global key.");
             }
         }
     }
 
-    let config_path = root_dir.join("config.toml");
     fs::remove_file(&identity_path)?;
-    if config_path.exists() {
-        fs::remove_file(config_path)?;
-    }
+    let mut config = helpers::load_config()?;
+    config.recipient = None;
+    helpers::save_config(&config)?;
     println!("\nSuccessfully decrypted journal and removed encryption keys.");
     Ok(())
 }
 
-/// Commits and pushes all changes in the rjot Git repository to the `origin` remote.
-pub fn command_sync() -> Result<()> {
-    let root_dir = get_rjot_dir_root()?;
-    let repo = Repository::open(&root_dir).map_err(|_| {
-        anyhow!(
-            "rjot directory at {:?} is not a Git repository. Run `rjot init --git` first.",
-            root_dir
-        )
-    })?;
-
-    println!("Staging all changes...");
-    let mut index = repo.index()?;
-    index.add_all(["."].iter(), git2::IndexAddOption::DEFAULT, None)?;
-    index.write()?;
-
-    let oid = index.write_tree()?;
-    let tree = repo.find_tree(oid)?;
-
-    let signature = Signature::now("rjot", "rjot@localhost")?;
-    let commit_message = format!("rjot sync: {}", Local::now().to_rfc2822());
-
-    let head = repo.head();
-    let parent_commits = match head {
-        Ok(head_ref) => vec![head_ref.peel_to_commit()?],
-        Err(ref e) if e.code() == git2::ErrorCode::UnbornBranch => Vec::new(),
-        Err(e) => return Err(e.into()),
-    };
-
-    let parents_ref: Vec<&git2::Commit> = parent_commits.iter().collect();
-
-    repo.commit(
-        Some("HEAD"),
-        &signature,
-        &signature,
-        &commit_message,
-        &tree,
-        &parents_ref,
-    )?;
+/// Permanently decrypts a single notebook and marks it as plaintext.
+fn command_decrypt_notebook(force: bool, entries_dir: &Path) -> Result<()> {
+    let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
 
-    println!("Committed changes with message: '{commit_message}'");
+    if !force {
+        print!("This will permanently decrypt all notes in notebook '{notebook_name}' and remove its identity file. This action cannot be undone. Continue? [y/N] ");
+        io::stdout().flush()?;
+        let mut confirmation = String::new();
+        io::stdin().read_line(&mut confirmation)?;
+        if confirmation.trim().to_lowercase() != "y" {
+            println!("Decryption aborted.");
+            return Ok(());
+        }
+    }
 
-    let head = repo.head()?;
-    let branch_name = head.shorthand().with_context(|| {
-        "Could not get branch name from HEAD. Are you in a detached HEAD state?"
-    })?;
-    let refspec = format!("refs/heads/{branch_name}:refs/heads/{branch_name}");
+    println!("Decrypting notebook: '{notebook_name}'");
+    for path in list_note_paths(entries_dir)? {
+        if helpers::is_encrypted(&fs::read(&path)?) {
+            let content = helpers::read_note_file(&path)?;
+            fs::write(&path, content)?;
+            println!("  - Decrypted {:?}", path.file_name().unwrap());
+        }
+    }
 
-    let mut remote = repo.find_remote("origin").map_err(|_| {
-        anyhow!("Could not find remote 'origin'. Please add a remote to your git repository.")
-    })?;
+    let mut notebook_config = load_notebook_config(entries_dir)?;
+    notebook_config.encrypt = Some(false);
+    notebook_config.recipient = None;
+    save_notebook_config(entries_dir, &notebook_config)?;
 
-    let mut callbacks = RemoteCallbacks::new();
-    callbacks.credentials(|_url, username_from_git, allowed_types| {
-        let username = username_from_git.unwrap_or("git");
+    let identity_path = entries_dir.join(IDENTITY_FILE);
+    if identity_path.exists() {
+        fs::remove_file(identity_path)?;
+    }
+    println!("\nSuccessfully decrypted notebook '{notebook_name}'. New jots will be stored as plaintext.");
+    Ok(())
+}
 
-        if allowed_types.is_user_pass_plaintext() {
-            if let Ok(token) = env::var("GITHUB_TOKEN") {
-                return Cred::userpass_plaintext(username, &token);
+/// Synchronizes the rjot Git repository with the `origin` remote.
+///
+/// Local changes are committed first, then remote changes are fetched and merged
+/// (or fast-forwarded) before pushing. If a merge stops on conflicts that can't be
+/// resolved automatically, nothing is pushed until `rjot sync --resolve` is run.
+pub fn command_sync(resolve: Option<ResolveStrategy>, dry_run: bool) -> Result<()> {
+    let root_dir = get_rjot_dir_root()?;
+    let repo = git::open_repo(&root_dir)?;
+    let config = helpers::load_config()?.git;
+    let remote_name = config.remote();
+    let branch_name = git::current_branch(&repo)?;
+    let remote_branch = config.branch.as_deref().unwrap_or(&branch_name);
+    let template = config
+        .commit_message
+        .as_deref()
+        .unwrap_or(git::DEFAULT_SYNC_MESSAGE);
+
+    if dry_run {
+        let signature = git::signature(&repo)?;
+        println!(
+            "Would sync branch '{branch_name}' with '{remote_branch}' on remote '{remote_name}' as {} <{}>.",
+            signature.name().unwrap_or_default(),
+            signature.email().unwrap_or_default()
+        );
+        if git::is_merging(&repo) {
+            println!("A previous sync stopped with conflicts. Run `rjot sync --resolve` first.");
+        }
+        let changes = git::pending_changes(&repo)?;
+        if changes.is_empty() {
+            println!("No local changes to commit.");
+        } else {
+            println!("\nWould commit with message:\n");
+            for line in git::sync_commit_message(template, &changes).lines() {
+                println!("    {line}");
             }
         }
+        println!("\nDry run: nothing was committed, fetched, or pushed.");
+        return Ok(());
+    }
 
-        if allowed_types.is_ssh_key() {
-            if let Ok(cred) = Cred::ssh_key_from_agent(username) {
-                return Ok(cred);
-            }
+    if git::is_merging(&repo) {
+        let Some(strategy) = resolve else {
+            bail!("A previous sync stopped with conflicts. Run `rjot sync --resolve` to resolve them.");
+        };
+        if !resolve_sync_conflicts(&repo, strategy)? {
+            bail!(
+                "Some conflicts are still unresolved. Run `rjot sync --resolve` again when ready."
+            );
         }
+        git::commit_merge(&repo)?;
+        println!("All conflicts resolved. Created merge commit.");
+    } else if resolve.is_some() {
+        println!("No conflicts to resolve.");
+    }
 
-        if allowed_types.is_ssh_key() {
-            if let Some(home_dir) = dirs::home_dir() {
-                if let Ok(cred) =
-                    Cred::ssh_key(username, None, &home_dir.join(".ssh").join("id_rsa"), None)
-                {
-                    return Ok(cred);
+    println!("Staging all changes...");
+    let commit_message = git::sync_commit_message(template, &git::pending_changes(&repo)?);
+    let mut index = git::stage_all(&repo)?;
+    match git::commit_index(&repo, &mut index, &commit_message)? {
+        Some(_) => println!(
+            "Committed changes: {}",
+            commit_message.lines().next().unwrap_or_default()
+        ),
+        None => println!("No local changes to commit."),
+    }
+
+    println!("Fetching from remote '{remote_name}'...");
+    if let Some(remote_commit) = git::fetch(&repo, remote_name, remote_branch)? {
+        match git::integrate(&repo, &branch_name, &remote_commit)? {
+            MergeOutcome::UpToDate => println!("Already up to date with '{remote_name}'."),
+            MergeOutcome::FastForward => println!("Fast-forwarded to the remote changes."),
+            MergeOutcome::Merged { auto_resolved } => {
+                for path in &auto_resolved {
+                    println!("  Automatically merged appended content in {path}");
                 }
+                println!("Merged remote changes.");
             }
-        }
-
-        if allowed_types.is_user_pass_plaintext() {
-            if let Ok(cred) = Cred::credential_helper(&repo.config()?, _url, Some(username)) {
-                return Ok(cred);
+            MergeOutcome::Conflicted { conflicts } => {
+                println!("\nSync stopped: these jots were changed on both sides and could not be merged automatically:");
+                for path in conflicts {
+                    println!("  - {path}");
+                }
+                println!();
+                bail!("Sync stopped with conflicts. Run `rjot sync --resolve` to choose which version to keep.");
             }
         }
+    }
 
-        Err(git2::Error::new(
-            git2::ErrorCode::Auth,
-            git2::ErrorClass::Ssh,
-            "failed to acquire credentials",
-        ))
-    });
-
-    let mut push_options = PushOptions::new();
-    push_options.remote_callbacks(callbacks);
-
-    println!("Pushing to remote 'origin' on branch '{branch_name}'...");
-    remote.push(&[&refspec], Some(&mut push_options))?;
+    println!("Pushing to remote '{remote_name}' on branch '{remote_branch}'...");
+    git::push(&repo, remote_name, &branch_name, remote_branch)?;
 
     println!("Sync complete.");
 
     Ok(())
 }
 
-/// Creates a new jot instantly from command-line arguments.
-pub fn command_down(entries_dir: &Path, message: &str, tags: Option<Vec<String>>) -> Result<()> {
-    let mut content = String::new();
-    if let Some(tags) = tags {
-        if !tags.is_empty() {
-            // `..Default::default()` handles the `pinned` field, setting it to false.
-            let frontmatter = Frontmatter {
-                tags,
-                ..Default::default()
-            };
-            let fm_str = serde_yaml::to_string(&frontmatter)?;
-            content.push_str("---\n");
-            content.push_str(&fm_str);
-            content.push_str("---\n\n");
+/// Keeps both versions of a jot: the union of their tags, pinned if either is, and our
+/// content followed by theirs.
+fn combine_jot_versions(ours: &str, theirs: &str) -> Result<String> {
+    let (mut frontmatter, our_content) = helpers::split_frontmatter(ours)?;
+    let (their_frontmatter, their_content) = helpers::split_frontmatter(theirs)?;
+    frontmatter.tags.extend(their_frontmatter.tags);
+    frontmatter.tags.sort();
+    frontmatter.tags.dedup();
+    frontmatter.pinned |= their_frontmatter.pinned;
+    let content = format!(
+        "{}\n\n{}",
+        our_content.trim_end(),
+        their_content.trim_start()
+    );
+    helpers::format_note(&frontmatter, &content)
+}
+
+/// Resolves the conflicts left by a merge, returning `true` once none remain.
+fn resolve_sync_conflicts(repo: &git2::Repository, strategy: ResolveStrategy) -> Result<bool> {
+    let conflicts = git::read_conflicts(repo)?;
+    if conflicts.is_empty() {
+        return Ok(true);
+    }
+
+    let mut all_resolved = true;
+    for conflict in conflicts {
+        let path_str = conflict.path.to_string_lossy().to_string();
+        let choice = match strategy {
+            ResolveStrategy::Ask => prompt_conflict_choice(&conflict)?,
+            other => other,
+        };
+        let resolution = match choice {
+            ResolveStrategy::Both if !conflict.is_jot() => {
+                println!(
+                    "Skipped {path_str}: only jots can keep both versions. Choose ours or theirs."
+                );
+                all_resolved = false;
+                continue;
+            }
+            ResolveStrategy::Ours => conflict.ours.clone(),
+            ResolveStrategy::Theirs => conflict.theirs.clone(),
+            ResolveStrategy::Both => Some(match (&conflict.ours, &conflict.theirs) {
+                (Some(ours), Some(theirs)) => combine_jot_versions(ours, theirs)
+                    .with_context(|| format!("Failed to combine the versions of {path_str}"))?,
+                (ours, theirs) => ours.clone().or(theirs.clone()).unwrap_or_default(),
+            }),
+            ResolveStrategy::Ask => {
+                println!("Skipped {path_str}.");
+                all_resolved = false;
+                continue;
+            }
+        };
+        git::write_resolution(repo, &conflict.path, resolution.as_deref())?;
+        println!("Resolved {path_str}.");
+    }
+    Ok(all_resolved)
+}
+
+/// Shows both sides of a conflicting file and asks how to resolve it.
+///
+/// Returns `ResolveStrategy::Ask` if the user chooses to skip the jot for now.
+fn prompt_conflict_choice(conflict: &git::JotConflict) -> Result<ResolveStrategy> {
+    let show_side = |label: &str, side: &Option<String>| {
+        println!("\n--- {label} ---");
+        match side {
+            Some(content) => println!("{content}"),
+            None => println!("(deleted)"),
+        }
+    };
+    println!("\n=== Conflict in {} ===", conflict.path.to_string_lossy());
+    show_side("ours (this machine)", &conflict.ours);
+    show_side("theirs (remote)", &conflict.theirs);
+
+    // Only jots can be combined; other files keep one side or the other.
+    let can_combine = conflict.is_jot();
+    loop {
+        if can_combine {
+            print!("\nKeep [o]urs, [t]heirs, [b]oth, or [s]kip? ");
+        } else {
+            print!("\nKeep [o]urs, [t]heirs, or [s]kip? ");
+        }
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            return Ok(ResolveStrategy::Ask);
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "o" | "ours" => return Ok(ResolveStrategy::Ours),
+            "t" | "theirs" => return Ok(ResolveStrategy::Theirs),
+            "b" | "both" if can_combine => return Ok(ResolveStrategy::Both),
+            "s" | "skip" => return Ok(ResolveStrategy::Ask),
+            _ if can_combine => println!("Please answer 'o', 't', 'b', or 's'."),
+            _ => println!("Please answer 'o', 't', or 's'."),
         }
     }
-    content.push_str(message);
+}
+
+/// Adds the notebook's `default_tags` to a list of tags, skipping duplicates.
+fn with_default_tags(entries_dir: &Path, mut tags: Vec<String>) -> Result<Vec<String>> {
+    for tag in load_notebook_config(entries_dir)?.default_tags {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    Ok(tags)
+}
+
+/// Builds the text of a new jot, adding a frontmatter block if there are tags.
+fn jot_with_tags(tags: Vec<String>, body: &str) -> Result<String> {
+    let mut content = String::new();
+    if !tags.is_empty() {
+        // `..Default::default()` handles the `pinned` field, setting it to false.
+        let frontmatter = Frontmatter {
+            tags,
+            ..Default::default()
+        };
+        let fm_str = serde_yaml::to_string(&frontmatter)?;
+        content.push_str("---\n");
+        content.push_str(&fm_str);
+        content.push_str("---\n\n");
+    }
+    content.push_str(body);
+    Ok(content)
+}
+
+/// Creates a new jot instantly from command-line arguments.
+pub fn command_down(entries_dir: &Path, message: &str, tags: Option<Vec<String>>) -> Result<()> {
+    let tags = with_default_tags(entries_dir, tags.unwrap_or_default())?;
+    let content = jot_with_tags(tags, message)?;
     println!("Jotting down: \"{message}\"");
-    let now = Local::now();
-    let filename = now.format("%Y-%m-%d-%H%M%S.md").to_string();
-    let file_path = entries_dir.join(filename);
+    let now = helpers::load_preferences(Some(entries_dir))?.now()?;
+    let id = now.format("%Y-%m-%d-%H%M%S").to_string();
+    let file_path = entries_dir.join(format!("{id}.md"));
     helpers::write_note_file(&file_path, &content)?;
+    helpers::remember_created(&file_path);
     println!("Successfully saved to {file_path:?}");
-    Ok(())
+    git::auto_commit(&format!("down {id}"))
+}
+
+/// Creates a new jot from text written in the shell's compose mode.
+///
+/// Inline `#tags` in the text are added to the jot's frontmatter.
+pub fn command_compose(entries_dir: &Path, text: &str) -> Result<()> {
+    if text.trim().is_empty() {
+        println!("Empty jot discarded.");
+        return Ok(());
+    }
+    let tags = with_default_tags(entries_dir, helpers::extract_hashtags(text))?;
+    let content = jot_with_tags(tags, text)?;
+    let now = helpers::load_preferences(Some(entries_dir))?.now()?;
+    let id = now.format("%Y-%m-%d-%H%M%S").to_string();
+    let file_path = entries_dir.join(format!("{id}.md"));
+    helpers::write_note_file(&file_path, &content)?;
+    helpers::remember_created(&file_path);
+    println!("Successfully saved to {file_path:?}");
+    git::auto_commit(&format!("compose {id}"))
 }
 
 /// Creates a new jot formatted as a Markdown task.
 pub fn command_task(entries_dir: &Path, message: &str) -> Result<()> {
-    let task_content = format!("- [ ] {message}");
+    let tags = with_default_tags(entries_dir, Vec::new())?;
+    let task_content = jot_with_tags(tags, &format!("- [ ] {message}"))?;
     println!("Jotting down task: \"{message}\"");
-    let now = Local::now();
-    let filename = now.format("%Y-%m-%d-%H%M%S.md").to_string();
-    let file_path = entries_dir.join(filename);
+    let now = helpers::load_preferences(Some(entries_dir))?.now()?;
+    let id = now.format("%Y-%m-%d-%H%M%S").to_string();
+    let file_path = entries_dir.join(format!("{id}.md"));
     helpers::write_note_file(&file_path, &task_content)?;
+    helpers::remember_created(&file_path);
     println!("Successfully saved to {file_path:?}");
-    Ok(())
+    git::auto_commit(&format!("task {id}"))
+}
+
+/// Loads included templates from the templates directory, prompts for missing variables,
+/// and runs the commands a template allows.
+struct TemplateEnvironment {
+    templates_dir: PathBuf,
+    /// Descriptions of the variables the template declares, used in prompts.
+    descriptions: BTreeMap<String, String>,
+    /// The commands the template's `commands` list allows `{{ cmd:... }}` to run.
+    commands: Vec<String>,
+}
+
+impl template::Environment for TemplateEnvironment {
+    fn load(&self, name: &str) -> Result<String> {
+        let path = template_path(&self.templates_dir, name)?;
+        fs::read_to_string(&path).with_context(|| format!("Could not read template {path:?}"))
+    }
+
+    fn missing(&mut self, name: &str) -> Result<template::Value> {
+        match self.descriptions.get(name) {
+            Some(description) => eprint!("{description} ({name}): "),
+            None => eprint!("Value for '{name}': "),
+        }
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        if io::stdin().read_line(&mut answer)? == 0 {
+            bail!("The template variable '{name}' has no value. Pass it with `-v {name}=<value>`.");
+        }
+        Ok(answer.trim_end_matches(['\r', '\n']).into())
+    }
+
+    fn command(&mut self, command: &str) -> Result<String> {
+        if !self
+            .commands
+            .iter()
+            .any(|allowed| allowed.trim() == command)
+        {
+            bail!(
+                "The template isn't allowed to run '{command}'. Add it to the `commands` list in its `template:` block."
+            );
+        }
+        // Split like a shell line, so quoted arguments reach the program whole.
+        let words = crate::shell::split_words(command)?;
+        let Some((program, args)) = words.split_first() else {
+            bail!("The template has an empty command.");
+        };
+        let output = process::Command::new(program)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run '{command}'"))?;
+        if !output.status.success() {
+            bail!(
+                "'{command}' failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string())
+    }
+}
+
+/// Returns the path of a template in the templates directory, adding `.md` if needed.
+fn template_path(templates_dir: &Path, name: &str) -> Result<PathBuf> {
+    let is_relative = Path::new(name)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)));
+    if name.is_empty() || !is_relative {
+        bail!("Invalid template name '{name}'.");
+    }
+    let mut file_name = name.to_string();
+    if !file_name.ends_with(".md") {
+        file_name.push_str(".md");
+    }
+    Ok(templates_dir.join(file_name))
+}
+
+/// How many commits `{{ commits }}` lists when the template doesn't say.
+const DEFAULT_TEMPLATE_COMMITS: usize = 5;
+
+/// Builds the variables available to templates: the built-ins plus any `-v key=value` pairs.
+fn template_variables(
+    now: DateTime<FixedOffset>,
+    commit_count: usize,
+    variables: Vec<(String, String)>,
+) -> Result<template::Variables> {
+    let mut context = template::Variables::new();
+    context.insert("date".into(), template::Value::Date(now));
+    context.insert("uuid".into(), Uuid::new_v4().to_string().into());
+    context.insert("hostname".into(), hostname().into());
+    context.insert("user".into(), username().into());
+
+    let project_dir = env::current_dir()?
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    context.insert("project_dir".into(), project_dir.into());
+
+    let (branch, commits, dirty_files) = match Repository::discover(".") {
+        Ok(repo) => {
+            let head = repo.head()?;
+            let branch = head.shorthand().unwrap_or("detached-head").to_string();
+            (
+                branch,
+                recent_commits(&repo, commit_count)?,
+                dirty_files(&repo)?,
+            )
+        }
+        Err(_) => ("not-a-repo".to_string(), Vec::new(), Vec::new()),
+    };
+    context.insert("branch".into(), branch.into());
+    context.insert("commits".into(), template::Value::List(commits));
+    context.insert("dirty_files".into(), template::Value::List(dirty_files));
+
+    for (key, value) in variables {
+        context.insert(key, value.into());
+    }
+    Ok(context)
+}
+
+/// Returns the last `count` commits on HEAD as `<short id> <summary>`, newest first.
+fn recent_commits(repo: &Repository, count: usize) -> Result<Vec<template::Value>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    let mut commits = Vec::new();
+    for oid in revwalk.take(count) {
+        let commit = repo.find_commit(oid?)?;
+        let id = commit.id().to_string();
+        commits.push(format!("{} {}", &id[..7], commit.summary().unwrap_or_default()).into());
+    }
+    Ok(commits)
+}
+
+/// Returns the paths of modified, staged, and untracked files in the working tree.
+fn dirty_files(repo: &Repository) -> Result<Vec<template::Value>> {
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    let statuses = repo.statuses(Some(&mut options))?;
+    Ok(statuses
+        .iter()
+        .filter(|entry| !entry.status().is_ignored())
+        .filter_map(|entry| entry.path().map(template::Value::from))
+        .collect())
+}
+
+/// Returns this machine's host name, or an empty string if it can't be found.
+fn hostname() -> String {
+    if let Some(name) = env::var("HOSTNAME")
+        .or_else(|_| env::var("COMPUTERNAME"))
+        .ok()
+        .filter(|name| !name.is_empty())
+    {
+        return name;
+    }
+    if let Ok(name) = fs::read_to_string("/etc/hostname") {
+        return name.trim().to_string();
+    }
+    process::Command::new("hostname")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Returns the name of the current user, or an empty string if it isn't set.
+fn username() -> String {
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or_default()
 }
 
 /// Creates a new jot by opening the default editor.
+///
+/// Without `--template`, the notebook's `default_template` is used, then `default.md`.
+/// A template's `notebook` setting picks the notebook unless `--notebook` is given.
 pub fn command_new(
-    entries_dir: &Path,
+    notebook: Option<String>,
     template_name: Option<String>,
     variables: Vec<(String, String)>,
 ) -> Result<()> {
-    let editor = helpers::get_editor()?;
-    let now = Local::now();
-    let filename = now.format("%Y-%m-%d-%H%M%S.md").to_string();
-    let file_path = entries_dir.join(filename);
-    let mut tpl_name = template_name.unwrap_or_else(|| "default".to_string());
-    if !tpl_name.ends_with(".md") {
-        tpl_name.push_str(".md");
-    }
+    let active_dir = helpers::get_active_entries_dir(notebook.clone())?;
+    let default_template = load_notebook_config(&active_dir)?.default_template;
+    let explicit_template = template_name.is_some() || default_template.is_some();
+    let tpl_name = template_name
+        .or(default_template)
+        .unwrap_or_else(|| "default".to_string());
     let templates_dir = get_templates_dir()?;
-    let tpl_path = templates_dir.join(tpl_name);
-    let mut initial_content = String::new();
-    if tpl_path.exists() {
-        initial_content = fs::read_to_string(tpl_path)?;
-        // {{date}}
-        initial_content = initial_content.replace("{{date}}", &now.to_rfc3339());
-
-        // {{uuid}}
-        let uuid = Uuid::new_v4().to_string();
-        initial_content = initial_content.replace("{{uuid}}", &uuid);
+    let tpl_path = template_path(&templates_dir, &tpl_name)?;
+    let tpl_exists = tpl_path.exists();
+    let (meta, source) = if tpl_exists {
+        template::split_meta(&fs::read_to_string(&tpl_path)?)
+            .with_context(|| format!("Invalid template '{tpl_name}'"))?
+    } else if explicit_template {
+        return Err(template_not_found(&tpl_name));
+    } else {
+        Default::default()
+    };
 
-        // {{project_dir}}
-        let project_dir = env::current_dir()?
-            .file_name()
-            .unwrap_or_default()
-            .to_string_lossy()
-            .to_string();
-        initial_content = initial_content.replace("{{project_dir}}", &project_dir);
+    let entries_dir = match (&meta.notebook, notebook) {
+        (Some(target), None) => helpers::get_active_entries_dir(Some(target.clone()))?,
+        _ => active_dir,
+    };
+    let preferences = helpers::load_preferences(Some(&entries_dir))?;
+    let editor = helpers::get_editor(&preferences)?;
+    let notebook_config = load_notebook_config(&entries_dir)?;
+    let now = preferences.now()?;
+    let id = now.format("%Y-%m-%d-%H%M%S").to_string();
+    let file_path = entries_dir.join(format!("{id}.md"));
 
-        // {{branch}}
-        let branch = match Repository::discover(".") {
-            Ok(repo) => {
-                let head = repo.head()?;
-                head.shorthand().unwrap_or("detached-head").to_string()
-            }
-            Err(_) => "not-a-repo".to_string(),
+    let mut initial_content = String::new();
+    if tpl_exists {
+        let commit_count = meta.commits.unwrap_or(DEFAULT_TEMPLATE_COMMITS);
+        let mut context = template_variables(now, commit_count, variables)?;
+        let mut template_env = TemplateEnvironment {
+            templates_dir,
+            descriptions: meta.variables.clone(),
+            commands: meta.commands.clone(),
         };
-        initial_content = initial_content.replace("{{branch}}", &branch);
+        // Declared variables are required, even if only a condition uses them.
+        for name in meta.variables.keys() {
+            if !context.contains_key(name) {
+                let value = template::Environment::missing(&mut template_env, name)?;
+                context.insert(name.clone(), value);
+            }
+        }
+        initial_content = template::render(&source, &mut context, &mut template_env)
+            .with_context(|| format!("Failed to render template '{tpl_name}'"))?;
+    }
 
-        // Handle custom variables
-        for (key, value) in variables {
-            initial_content = initial_content.replace(&format!("{{{{{key}}}}}"), &value);
+    let mut extra_tags = meta.tags;
+    extra_tags.extend(notebook_config.default_tags);
+    if !extra_tags.is_empty() || meta.pinned {
+        let (mut frontmatter, body) = helpers::split_frontmatter(&initial_content)
+            .context("Failed to parse the template's frontmatter")?;
+        for tag in extra_tags {
+            if !frontmatter.tags.contains(&tag) {
+                frontmatter.tags.push(tag);
+            }
         }
+        frontmatter.pinned |= meta.pinned;
+        initial_content = format!(
+            "---\n{}---\n\n{}",
+            serde_yaml::to_string(&frontmatter)?,
+            body
+        );
     }
     helpers::write_note_file(&file_path, &initial_content)?;
-    let status = Command::new(&editor).arg(&file_path).status()?;
-    if !status.success() {
-        bail!("Editor exited with a non-zero status.");
-    }
+    helpers::open_in_editor(&editor, &file_path)?;
     let final_content = helpers::read_note_file(&file_path)?;
-    if final_content.trim().is_empty() {
+    // A jot left untouched with only the default tags counts as empty too.
+    let untouched_tags_only = !tpl_exists && final_content == initial_content;
+    if final_content.trim().is_empty() || untouched_tags_only {
         fs::remove_file(&file_path)?;
         println!("Empty jot discarded.");
-    } else {
-        println!("Successfully saved to {file_path:?}");
+        return Ok(());
     }
-    Ok(())
+    helpers::remember_created(&file_path);
+    println!("Successfully saved to {file_path:?}");
+    git::auto_commit(&format!("new {id}"))
 }
 
 /// Opens an existing jot in the default editor.
-/// No changes needed.
 pub fn command_edit(note_path: PathBuf) -> Result<()> {
-    let editor = helpers::get_editor()?;
+    let editor = helpers::get_editor(&helpers::load_preferences(note_path.parent())?)?;
     println!(
         "Opening {:?} in {}...",
         &note_path.file_name().unwrap(),
-        &editor
+        &editor[0]
     );
-    let status = Command::new(&editor).arg(&note_path).status()?;
-    if !status.success() {
-        bail!("Editor exited with a non-zero status.");
-    }
+    helpers::open_in_editor(&editor, &note_path)?;
     println!("Finished editing {:?}.", &note_path.file_name().unwrap());
-    Ok(())
+    git::auto_commit(&format!("edit {}", note_id(&note_path)))
 }
 
 /// Manages tags on an existing jot.
@@ -732,30 +1368,33 @@ pub fn command_tag(entries_dir: &Path, args: TagArgs) -> Result<()> {
     let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
     let mut note = parse_note_from_file(&note_path, &notebook_name)?;
 
-    match args.action {
+    let commit_message = match args.action {
         TagAction::Add { tags, .. } => {
+            let message = format!("tag add {} to {}", tags.join(", "), note.id);
             for tag in tags {
                 if !note.frontmatter.tags.contains(&tag) {
                     note.frontmatter.tags.push(tag);
                 }
             }
             println!("Added tags to '{}'.", note.id);
+            message
         }
         TagAction::Remove { tags, .. } => {
             note.frontmatter.tags.retain(|t| !tags.contains(t));
             println!("Removed tags from '{}'.", note.id);
+            format!("tag remove {} from {}", tags.join(", "), note.id)
         }
         TagAction::Set { tags, .. } => {
+            let message = format!("tag set {} on {}", tags.join(", "), note.id);
             note.frontmatter.tags = tags;
             println!("Set tags for '{}'.", note.id);
+            message
         }
-    }
+    };
     note.frontmatter.tags.sort();
     note.frontmatter.tags.dedup();
-    let new_frontmatter_str = serde_yaml::to_string(&note.frontmatter)?;
-    let new_content = format!("---\n{}---\n\n{}", new_frontmatter_str, note.content);
-    helpers::write_note_file(&note.path, &new_content)?;
-    Ok(())
+    helpers::save_note(&note)?;
+    git::auto_commit(&commit_message)
 }
 
 // A private helper function to toggle the pinned status of a note.
@@ -770,7 +1409,7 @@ fn command_toggle_pin_status(
     let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
     let mut note = parse_note_from_file(&note_path, &notebook_name)?;
 
-    if note.frontmatter.pinned == pin {
+    if !helpers::set_pinned(&mut note, pin)? {
         println!(
             "Jot '{}' is already {}.",
             note.id,
@@ -779,19 +1418,14 @@ fn command_toggle_pin_status(
         return Ok(());
     }
 
-    note.frontmatter.pinned = pin;
-
-    let new_frontmatter_str = serde_yaml::to_string(&note.frontmatter)?;
-    let new_content = format!("---\n{}---\n\n{}", new_frontmatter_str, note.content);
-    helpers::write_note_file(&note.path, &new_content)?;
-
     println!(
         "Successfully {} jot '{}'.",
         if pin { "pinned" } else { "unpinned" },
         note.id
     );
 
-    Ok(())
+    let action = if pin { "pin" } else { "unpin" };
+    git::auto_commit(&format!("{action} {}", note.id))
 }
 
 // Public command function to pin a note.
@@ -814,18 +1448,21 @@ pub fn command_unpin(
 
 /// Lists the most recent jots.
 pub fn command_list(
-    entries_dir: &PathBuf,
+    entries_dir: &Path,
     count: Option<usize>,
     pinned: bool,
     tasks: bool,
+    tags: &[String],
 ) -> Result<()> {
-    let num_to_list = count.unwrap_or(10);
-    let entries = fs::read_dir(entries_dir)?;
+    let num_to_list = match count {
+        Some(count) => count,
+        None => helpers::load_preferences(Some(entries_dir))?.list_count(),
+    };
     let mut notes = Vec::new();
     let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
 
-    for entry in entries.filter_map(Result::ok) {
-        notes.push(parse_note_from_file(&entry.path(), &notebook_name)?);
+    for path in list_note_paths(entries_dir)? {
+        notes.push(parse_note_from_file(&path, &notebook_name)?);
     }
 
     if pinned {
@@ -838,42 +1475,120 @@ pub fn command_list(
         println!("Showing jots with incomplete tasks:");
     }
 
+    if !tags.is_empty() {
+        notes.retain(|note| tags.iter().all(|tag| note.frontmatter.tags.contains(tag)));
+        println!("Showing jots tagged {}:", tags.join(", "));
+    }
+
     notes.sort_by(|a, b| b.id.cmp(&a.id));
     notes.truncate(num_to_list);
 
-    display_note_list(notes);
+    display_note_list(notes)?;
     Ok(())
 }
 
-/// Interactively selects a jot using a fuzzy finder.
+// Keys that accept the selection with a specific action instead of the `--action` one.
 #[cfg(not(windows))]
-pub fn command_select(entries_dir: &PathBuf) -> Result<()> {
+const SELECT_KEYS: [(&str, SelectAction); 8] = [
+    ("alt-e", SelectAction::Edit),
+    ("alt-s", SelectAction::Show),
+    ("alt-d", SelectAction::Delete),
+    ("alt-p", SelectAction::Pin),
+    ("alt-y", SelectAction::CopyId),
+    ("alt-o", SelectAction::PrintPath),
+    ("alt-t", SelectAction::Tag),
+    ("alt-m", SelectAction::Move),
+];
+
+/// Interactively selects jots using a fuzzy finder and acts on them.
+///
+/// Enter applies the `--action`, while the keys in [`SELECT_KEYS`] pick another one. Tab
+/// marks several jots so the action applies to all of them. Matching covers each jot's
+/// tags and full content, and the preview pane shows the jot under the cursor.
+#[cfg(not(windows))]
+pub fn command_select(entries_dir: &Path, args: SelectArgs) -> Result<()> {
     struct NoteItem {
-        id: String,
+        path: PathBuf,
         display_text: String,
+        search_text: String,
+        preview: String,
     }
 
     impl SkimItem for NoteItem {
         fn text(&self) -> Cow<'_, str> {
-            Cow::Borrowed(&self.display_text)
+            Cow::Borrowed(&self.search_text)
+        }
+
+        // Only the summary line is shown, so highlight just the matches that fall inside it.
+        fn display<'a>(&'a self, context: DisplayContext<'a>) -> AnsiString<'a> {
+            let shown = self.display_text.chars().count();
+            let range = |start: usize, end: usize| {
+                (start < shown).then(|| {
+                    (
+                        context.highlight_attr,
+                        (start as u32, end.min(shown) as u32),
+                    )
+                })
+            };
+            let highlights = match context.matches {
+                Matches::CharIndices(indices) => {
+                    indices.iter().filter_map(|&i| range(i, i + 1)).collect()
+                }
+                Matches::CharRange(start, end) => range(start, end).into_iter().collect(),
+                Matches::ByteRange(start, end) => {
+                    let start_char = context.text[..start].chars().count();
+                    let end_char = start_char + context.text[start..end].chars().count();
+                    range(start_char, end_char).into_iter().collect()
+                }
+                Matches::None => vec![],
+            };
+            AnsiString::new_str(&self.display_text, highlights)
+        }
+
+        fn preview(&self, _context: PreviewContext) -> ItemPreview {
+            ItemPreview::AnsiText(self.preview.clone())
         }
 
         fn output(&self) -> Cow<'_, str> {
-            Cow::Borrowed(&self.id)
+            Cow::Owned(note_id(&self.path))
         }
     }
 
-    let entries = fs::read_dir(entries_dir)?;
+    // Check the destination up front, so a typo doesn't throw away the selection.
+    if let Some(to) = &args.to {
+        existing_notebook_dir(to)?;
+    }
+
+    let notebook_dirs = if args.all {
+        helpers::list_notebooks(args.include_archived)?
+    } else {
+        vec![entries_dir.to_path_buf()]
+    };
     let mut notes = vec![];
-    let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
-    for entry in entries.filter_map(Result::ok) {
-        notes.push(parse_note_from_file(&entry.path(), &notebook_name)?);
+    for notebook_dir in &notebook_dirs {
+        let notebook_name = notebook_dir.file_name().unwrap().to_string_lossy();
+        for path in list_note_paths(notebook_dir)? {
+            notes.push(parse_note_from_file(&path, &notebook_name)?);
+        }
     }
     notes.sort_by(|a, b| b.id.cmp(&a.id));
 
+    let color = helpers::load_preferences(Some(entries_dir))?.color();
+    let expect = SELECT_KEYS.map(|(key, _)| key).join(",");
+    let header = format!(
+        "enter: {} | tab: mark | {}",
+        select_action_name(args.action),
+        SELECT_KEYS
+            .map(|(key, action)| format!("{key}: {}", select_action_name(action)))
+            .join(" | ")
+    );
     let options = SkimOptionsBuilder::default()
-        .multi(false)
+        .multi(true)
         .reverse(true)
+        .expect(Some(expect))
+        .header(Some(&header))
+        .preview(Some(""))
+        .preview_window(Some("right:50%:wrap"))
         .build()?;
 
     type SkimChannel = (Sender<Arc<dyn SkimItem>>, Receiver<Arc<dyn SkimItem>>);
@@ -881,182 +1596,618 @@ pub fn command_select(entries_dir: &PathBuf) -> Result<()> {
     let (tx, rx): SkimChannel = unbounded();
 
     for note in notes {
-        let display_text = format!(
+        let mut display_text = format!(
             "{} | {}",
             note.id,
             note.content.lines().next().unwrap_or("").trim()
         );
+        if args.all {
+            display_text = format!("{} | {display_text}", note.notebook);
+        }
+        let tags: Vec<String> = note
+            .frontmatter
+            .tags
+            .iter()
+            .map(|t| format!("#{t}"))
+            .collect();
+        if !tags.is_empty() {
+            display_text = format!("{display_text}  {}", tags.join(" "));
+        }
+        let search_text = format!(
+            "{display_text} {}",
+            note.content
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
         let item = NoteItem {
-            id: note.id,
+            preview: select_preview(&note, color),
+            path: note.path,
             display_text,
+            search_text,
         };
         let _ = tx.send(Arc::new(item));
     }
     drop(tx);
 
-    let skim_output = Skim::run_with(&options, Some(rx));
+    let Some(output) = Skim::run_with(&options, Some(rx)) else {
+        return Ok(());
+    };
+    if output.is_abort {
+        return Ok(());
+    }
 
-    if let Some(output) = skim_output {
-        if output.is_abort {
-            return Ok(());
-        }
+    let action = match &output.final_event {
+        Event::EvActAccept(Some(key)) => SELECT_KEYS
+            .iter()
+            .find(|(k, _)| k == key)
+            .map_or(args.action, |(_, action)| *action),
+        _ => args.action,
+    };
+    let paths: Vec<PathBuf> = output
+        .selected_items
+        .iter()
+        .filter_map(|item| item.as_ref().as_any().downcast_ref::<NoteItem>())
+        .map(|item| item.path.clone())
+        .collect();
+    if paths.is_empty() {
+        return Ok(());
+    }
+    apply_select_action(action, paths, args.tag, args.to, args.force)
+}
+
+// Renders a jot for the `select` preview pane: a heading line with its ID, notebook, tags,
+// and pinned state, followed by the (decrypted) content.
+#[cfg(not(windows))]
+fn select_preview(note: &helpers::Note, color: bool) -> String {
+    let mut heading = format!("{} in '{}'", note.id, note.notebook);
+    if note.frontmatter.pinned {
+        heading.push_str(" (pinned)");
+    }
+    let tags = if note.frontmatter.tags.is_empty() {
+        String::new()
+    } else {
+        format!("Tags: {}\n", note.frontmatter.tags.join(", "))
+    };
+    if color {
+        format!(
+            "\x1b[1m{heading}\x1b[0m\n\x1b[36m{tags}\x1b[0m\n{}",
+            note.content
+        )
+    } else {
+        format!("{heading}\n{tags}\n{}", note.content)
     }
+}
+
+#[cfg(not(windows))]
+fn select_action_name(action: SelectAction) -> String {
+    action.to_possible_value().unwrap().get_name().to_string()
+}
 
+// Applies a `select` action to the chosen jots. Actions that change jots record one commit.
+#[cfg(not(windows))]
+fn apply_select_action(
+    action: SelectAction,
+    paths: Vec<PathBuf>,
+    mut tags: Vec<String>,
+    to: Option<String>,
+    force: bool,
+) -> Result<()> {
+    // With `select --all` the jots can come from several notebooks.
+    let notebook_of = |path: &Path| {
+        path.parent()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+    match action {
+        SelectAction::Edit => {
+            for path in paths {
+                command_edit(path)?;
+            }
+        }
+        SelectAction::Show => {
+            for path in paths {
+                command_show(path, false)?;
+            }
+        }
+        SelectAction::PrintPath => {
+            for path in &paths {
+                println!("{}", path.display());
+            }
+        }
+        SelectAction::CopyId => {
+            let ids: Vec<String> = paths.iter().map(|path| note_id(path)).collect();
+            if helpers::copy_to_clipboard(&ids.join("\n"))? {
+                println!("Copied {} to the clipboard.", ids.join(", "));
+            } else {
+                eprintln!("No clipboard tool found, so here are the IDs instead.");
+                for id in ids {
+                    println!("{id}");
+                }
+            }
+        }
+        SelectAction::Delete => {
+            if let [path] = paths.as_slice() {
+                return command_delete(path.clone(), force);
+            }
+            if !force {
+                print!(
+                    "Are you sure you want to delete {} jots? [y/N] ",
+                    paths.len()
+                );
+                io::stdout().flush()?;
+                let mut confirmation = String::new();
+                io::stdin().read_line(&mut confirmation)?;
+                if confirmation.trim().to_lowercase() != "y" {
+                    println!("Deletion aborted.");
+                    return Ok(());
+                }
+            }
+            for path in &paths {
+                fs::remove_file(path)?;
+                println!("Successfully deleted '{}'.", note_id(path));
+            }
+            git::auto_commit(&format!("delete {}", jots_subject(&paths)))?;
+        }
+        SelectAction::Pin => {
+            let mut pinned = Vec::new();
+            for path in paths {
+                let mut note = parse_note_from_file(&path, &notebook_of(&path))?;
+                if !helpers::set_pinned(&mut note, true)? {
+                    println!("Jot '{}' is already pinned.", note.id);
+                    continue;
+                }
+                println!("Successfully pinned jot '{}'.", note.id);
+                pinned.push(path);
+            }
+            if !pinned.is_empty() {
+                git::auto_commit(&format!("pin {}", jots_subject(&pinned)))?;
+            }
+        }
+        SelectAction::Tag => {
+            if tags.is_empty() {
+                let answer = ask("Tags to add (comma-separated): ")?;
+                tags = answer
+                    .split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect();
+            }
+            if tags.is_empty() {
+                bail!("No tags given, so nothing was tagged.");
+            }
+            for path in &paths {
+                let mut note = parse_note_from_file(path, &notebook_of(path))?;
+                note.frontmatter.tags.extend(tags.iter().cloned());
+                note.frontmatter.tags.sort();
+                note.frontmatter.tags.dedup();
+                helpers::save_note(&note)?;
+                println!("Added tags to '{}'.", note.id);
+            }
+            git::auto_commit(&format!(
+                "tag add {} to {}",
+                tags.join(", "),
+                jots_subject(&paths)
+            ))?;
+        }
+        SelectAction::Move => {
+            let to = match to {
+                Some(to) => to,
+                None => ask("Notebook to move to: ")?,
+            };
+            let destination = existing_notebook_dir(&to)?;
+            let (already_there, paths): (Vec<_>, Vec<_>) = paths
+                .into_iter()
+                .partition(|path| path.parent() == Some(destination.as_path()));
+            if paths.is_empty() {
+                bail!("The jots are already in notebook '{to}'.");
+            }
+            for path in already_there {
+                println!("Skipped '{}', which is already in '{to}'.", note_id(&path));
+            }
+            transfer_paths(&destination, &to, paths, false)?;
+        }
+    }
     Ok(())
 }
 
+// Reads one line of input after printing a prompt.
+#[cfg(not(windows))]
+fn ask(prompt: &str) -> Result<String> {
+    print!("{prompt}");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
 /// Performs a full-text search of all jots.
-pub fn command_find(entries_dir: &PathBuf, query: &str, all: bool) -> Result<()> {
+pub fn command_find(
+    entries_dir: &Path,
+    query: &str,
+    all: bool,
+    include_archived: bool,
+) -> Result<()> {
     println!("Searching for \"{query}\" in your jots...");
     let mut matches = Vec::new();
 
     if all {
         // --- GLOBAL SEARCH LOGIC ---
-        let notebooks_dir = get_notebooks_dir()?;
-        for notebook_entry in fs::read_dir(notebooks_dir)?.filter_map(Result::ok) {
-            if notebook_entry.path().is_dir() {
-                let notebook_path = notebook_entry.path();
-                let notebook_name = notebook_path.file_name().unwrap().to_string_lossy();
-                // Pass a reference to `read_dir` to avoid moving the value
-                for entry in fs::read_dir(&notebook_path)?.filter_map(Result::ok) {
-                    let note = parse_note_from_file(&entry.path(), &notebook_name)?;
-                    if note.content.to_lowercase().contains(&query.to_lowercase()) {
-                        matches.push(note);
-                    }
+        for notebook_path in helpers::list_notebooks(include_archived)? {
+            let notebook_name = notebook_path.file_name().unwrap().to_string_lossy();
+            for path in list_note_paths(&notebook_path)? {
+                let note = parse_note_from_file(&path, &notebook_name)?;
+                if note.content.to_lowercase().contains(&query.to_lowercase()) {
+                    matches.push(note);
                 }
             }
         }
-        display_global_find_list(matches);
+        display_global_find_list(matches)?;
     } else {
         // --- LOCAL SEARCH LOGIC ---
         let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
-        for entry in fs::read_dir(entries_dir)?.filter_map(Result::ok) {
-            let note = parse_note_from_file(&entry.path(), &notebook_name)?;
+        for path in list_note_paths(entries_dir)? {
+            let note = parse_note_from_file(&path, &notebook_name)?;
             if note.content.to_lowercase().contains(&query.to_lowercase()) {
                 matches.push(note);
             }
         }
-        display_note_list(matches);
+        display_note_list(matches)?;
     }
 
     Ok(())
 }
 
 /// Filters jots by one or more tags.
-pub fn command_tags_filter(entries_dir: &PathBuf, tags: &[String]) -> Result<()> {
+pub fn command_tags_filter(entries_dir: &Path, tags: &[String]) -> Result<()> {
     println!("Filtering by tags: {tags:?}");
-    let entries = fs::read_dir(entries_dir)?;
     let mut matches = Vec::new();
     let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
 
-    for entry in entries.filter_map(Result::ok) {
-        let note = parse_note_from_file(&entry.path(), &notebook_name)?;
+    for path in list_note_paths(entries_dir)? {
+        let note = parse_note_from_file(&path, &notebook_name)?;
         if note.frontmatter.tags.iter().any(|t| tags.contains(t)) {
             matches.push(note);
         }
     }
-    display_note_list(matches);
+    display_note_list(matches)?;
     Ok(())
 }
 
 /// A helper function for all date-based filtering.
-pub fn command_by_date_filter(entries_dir: &PathBuf, date: NaiveDate, compile: bool) -> Result<()> {
+pub fn command_by_date_filter(
+    entries_dir: &Path,
+    date: NaiveDate,
+    compile: bool,
+    raw: bool,
+) -> Result<()> {
     let date_prefix = date.format("%Y-%m-%d").to_string();
-    println!("Finding jots from {date_prefix}...");
+    let preferences = helpers::load_preferences(Some(entries_dir))?;
+    println!(
+        "Finding jots from {}...",
+        date.format(preferences.date_format())
+    );
     let mut matches = Vec::new();
     let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
 
-    for entry in fs::read_dir(entries_dir)?.filter_map(Result::ok) {
-        if entry
+    for path in list_note_paths(entries_dir)? {
+        if path
             .file_name()
+            .unwrap()
             .to_string_lossy()
             .starts_with(&date_prefix)
         {
-            matches.push(parse_note_from_file(&entry.path(), &notebook_name)?);
+            matches.push(parse_note_from_file(&path, &notebook_name)?);
         }
     }
     matches.sort_by(|a, b| a.id.cmp(&b.id));
     if compile {
-        helpers::compile_notes(matches)?
+        helpers::compile_notes(matches, raw)?
     } else {
-        display_note_list(matches)
+        display_note_list(matches)?
     }
     Ok(())
 }
 
 /// Lists jots created today.
-pub fn command_today(entries_dir: &PathBuf, compile: bool) -> Result<()> {
-    command_by_date_filter(entries_dir, Local::now().date_naive(), compile)
+pub fn command_today(entries_dir: &Path, compile: bool, raw: bool) -> Result<()> {
+    let today = helpers::load_preferences(Some(entries_dir))?
+        .now()?
+        .date_naive();
+    command_by_date_filter(entries_dir, today, compile, raw)
 }
 
 /// Lists jots created yesterday.
-pub fn command_yesterday(entries_dir: &PathBuf, compile: bool) -> Result<()> {
-    let yesterday = Local::now().date_naive() - chrono::Duration::days(1);
-    command_by_date_filter(entries_dir, yesterday, compile)
+pub fn command_yesterday(entries_dir: &Path, compile: bool, raw: bool) -> Result<()> {
+    let today = helpers::load_preferences(Some(entries_dir))?
+        .now()?
+        .date_naive();
+    let yesterday = today - chrono::Duration::days(1);
+    command_by_date_filter(entries_dir, yesterday, compile, raw)
 }
 
 /// Lists jots created in the current week.
-pub fn command_by_week(entries_dir: &PathBuf, compile: bool) -> Result<()> {
-    let today = Local::now().date_naive();
+pub fn command_by_week(entries_dir: &Path, compile: bool, raw: bool) -> Result<()> {
+    let preferences = helpers::load_preferences(Some(entries_dir))?;
+    let today = preferences.now()?.date_naive();
     let week_start = today - chrono::Duration::days(today.weekday().num_days_from_sunday() as i64);
-    println!("Finding jots from this week (starting {week_start})...");
+    println!(
+        "Finding jots from this week (starting {})...",
+        week_start.format(preferences.date_format())
+    );
     let mut matches = Vec::new();
     let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
 
-    for entry in fs::read_dir(entries_dir)?.filter_map(Result::ok) {
-        let filename = entry.file_name().to_string_lossy().to_string();
-        if let Ok(date) = NaiveDate::parse_from_str(&filename[0..10], "%Y-%m-%d") {
+    for path in list_note_paths(entries_dir)? {
+        let filename = path.file_name().unwrap().to_string_lossy().to_string();
+        if let Some(Ok(date)) = filename
+            .get(0..10)
+            .map(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d"))
+        {
             if date >= week_start && date <= today {
-                matches.push(parse_note_from_file(&entry.path(), &notebook_name)?);
+                matches.push(parse_note_from_file(&path, &notebook_name)?);
             }
         }
     }
     matches.sort_by(|a, b| a.id.cmp(&b.id));
     if compile {
-        helpers::compile_notes(matches)?
+        helpers::compile_notes(matches, raw)?
     } else {
-        display_note_list(matches)
+        display_note_list(matches)?
     }
     Ok(())
 }
 
 /// Lists jots from a specific date or date range.
-pub fn command_on(entries_dir: &PathBuf, date_spec: &str, compile: bool) -> Result<()> {
+pub fn command_on(entries_dir: &Path, date_spec: &str, compile: bool, raw: bool) -> Result<()> {
     let mut matches = Vec::new();
     let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
 
     if let Some((start_str, end_str)) = date_spec.split_once("..") {
         let start_date = NaiveDate::parse_from_str(start_str, "%Y-%m-%d")?;
         let end_date = NaiveDate::parse_from_str(end_str, "%Y-%m-%d")?;
-        println!("Finding jots from {start_date} to {end_date}...");
-        for entry in fs::read_dir(entries_dir)?.filter_map(Result::ok) {
-            let filename = entry.file_name().to_string_lossy().to_string();
-            if let Ok(date) = NaiveDate::parse_from_str(&filename[0..10], "%Y-%m-%d") {
+        let preferences = helpers::load_preferences(Some(entries_dir))?;
+        println!(
+            "Finding jots from {} to {}...",
+            start_date.format(preferences.date_format()),
+            end_date.format(preferences.date_format())
+        );
+        for path in list_note_paths(entries_dir)? {
+            let filename = path.file_name().unwrap().to_string_lossy().to_string();
+            if let Some(Ok(date)) = filename
+                .get(0..10)
+                .map(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d"))
+            {
                 if date >= start_date && date <= end_date {
-                    matches.push(parse_note_from_file(&entry.path(), &notebook_name)?);
+                    matches.push(parse_note_from_file(&path, &notebook_name)?);
                 }
             }
         }
     } else {
         let date = NaiveDate::parse_from_str(date_spec, "%Y-%m-%d")?;
-        return command_by_date_filter(entries_dir, date, compile);
+        return command_by_date_filter(entries_dir, date, compile, raw);
     }
     matches.sort_by(|a, b| a.id.cmp(&b.id));
     if compile {
-        helpers::compile_notes(matches)?
+        helpers::compile_notes(matches, raw)?
     } else {
-        display_note_list(matches)
+        display_note_list(matches)?
     }
     Ok(())
 }
 
 /// Displays the full content of a specific jot.
-pub fn command_show(note_path: PathBuf) -> Result<()> {
+pub fn command_show(note_path: PathBuf, raw: bool) -> Result<()> {
     let content = helpers::read_note_file(&note_path)?;
-    println!("{content}");
+    show_jot(&note_id(&note_path), &content, raw, note_path.parent())
+}
+
+// Prints a jot file, rendered in a terminal: its ID and tags as a heading, then the
+// markdown body. `raw` prints the file as it is stored.
+fn show_jot(id: &str, file_content: &str, raw: bool, notebook_dir: Option<&Path>) -> Result<()> {
+    if raw || !io::stdout().is_terminal() {
+        println!("{file_content}");
+        return Ok(());
+    }
+    let (frontmatter, content) = helpers::split_frontmatter(file_content)?;
+    let mut markdown = format!("# {id}\n\n");
+    if frontmatter.pinned {
+        markdown.push_str("**Pinned** ");
+    }
+    if !frontmatter.tags.is_empty() {
+        let tags: Vec<String> = frontmatter.tags.iter().map(|t| format!("`#{t}`")).collect();
+        markdown.push_str(&tags.join(" "));
+    }
+    markdown.push_str(&format!("\n\n{content}"));
+    crate::render::print_markdown(&markdown, false, notebook_dir)
+}
+
+/// Displays a jot as it was at an earlier revision or date.
+pub fn command_show_at(
+    entries_dir: &Path,
+    id_prefix: Option<String>,
+    last: Option<usize>,
+    at: &str,
+    raw: bool,
+) -> Result<()> {
+    let (repo, jot_path) = locate_jot_in_repo(entries_dir, id_prefix, last)?;
+    let preferences = helpers::load_preferences(Some(entries_dir))?;
+    let commit = git::resolve_revision(&repo, at, &preferences)?;
+    match read_jot_at(&repo, entries_dir, &commit, &jot_path)? {
+        Some(content) => show_jot(&note_id(&jot_path), &content, raw, Some(entries_dir))?,
+        None => bail!("Jot '{}' did not exist at {}.", note_id(&jot_path), at),
+    }
+    Ok(())
+}
+
+/// Lists the commits that changed a jot.
+pub fn command_history(
+    entries_dir: &Path,
+    id_prefix: Option<String>,
+    last: Option<usize>,
+) -> Result<()> {
+    let (repo, jot_path) = locate_jot_in_repo(entries_dir, id_prefix, last)?;
+    let id = note_id(&jot_path);
+    let revisions = git::jot_history(&repo, &jot_path)?;
+    if revisions.is_empty() {
+        println!("No committed history for '{id}'.");
+        return Ok(());
+    }
+
+    let preferences = helpers::load_preferences(Some(entries_dir))?;
+    let datetime_format = preferences.datetime_format();
+    let mut rows = Vec::new();
+    for revision in revisions {
+        let change = match revision.change {
+            git::JotChange::Added => "added",
+            git::JotChange::Modified => "modified",
+            git::JotChange::Deleted => "deleted",
+        };
+        let time = preferences.local_time(&revision.time)?;
+        let date = time.format(&datetime_format).to_string();
+        rows.push((short_oid(revision.id), date, change, revision.summary));
+    }
+    let date_width = rows.iter().map(|row| row.1.len()).max().unwrap_or(0).max(4);
+
+    println!("History of '{id}':");
+    println!(
+        "\n{:<9} {:<date_width$} {:<9} MESSAGE",
+        "COMMIT", "DATE", "CHANGE"
+    );
+    println!("{:-<9} {:-<date_width$} {:-<9} {:-<40}", "", "", "", "");
+    for (commit, date, change, summary) in rows {
+        println!("{commit:<9} {date:<date_width$} {change:<9} {summary}");
+    }
+    Ok(())
+}
+
+/// Shows the changes to a jot since a revision, defaulting to the last commit.
+pub fn command_diff(
+    entries_dir: &Path,
+    id_prefix: Option<String>,
+    last: Option<usize>,
+    at: Option<String>,
+) -> Result<()> {
+    let (repo, jot_path) = locate_jot_in_repo(entries_dir, id_prefix, last)?;
+    let at = at.unwrap_or_else(|| "HEAD".to_string());
+    let preferences = helpers::load_preferences(Some(entries_dir))?;
+    let commit = git::resolve_revision(&repo, &at, &preferences)?;
+    let old = read_jot_at(&repo, entries_dir, &commit, &jot_path)?.unwrap_or_default();
+    let working_path = get_rjot_dir_root()?.join(&jot_path);
+    let new = if working_path.exists() {
+        helpers::read_note_file(&working_path)?
+    } else {
+        String::new()
+    };
+
+    if old == new {
+        println!("No changes to '{}' since {}.", note_id(&jot_path), at);
+        return Ok(());
+    }
+    let mut patch = git2::Patch::from_buffers(
+        old.as_bytes(),
+        Some(&jot_path),
+        new.as_bytes(),
+        Some(&jot_path),
+        None,
+    )?;
+    print!("{}", patch.to_buf()?.as_str().unwrap_or_default());
     Ok(())
 }
 
+/// Restores a jot from history, bringing it back if it was deleted.
+///
+/// Without `--at`, an existing jot is restored to its last committed version and a
+/// deleted jot to the last version before it was deleted.
+pub fn command_restore(
+    entries_dir: &Path,
+    id_prefix: Option<String>,
+    last: Option<usize>,
+    at: Option<String>,
+) -> Result<()> {
+    let (repo, jot_path) = locate_jot_in_repo(entries_dir, id_prefix, last)?;
+    let id = note_id(&jot_path);
+    let working_path = get_rjot_dir_root()?.join(&jot_path);
+    let preferences = helpers::load_preferences(Some(entries_dir))?;
+    let commit = match &at {
+        Some(spec) => git::resolve_revision(&repo, spec, &preferences)?,
+        None if working_path.exists() => git::resolve_revision(&repo, "HEAD", &preferences)?,
+        None => {
+            let revision = git::jot_history(&repo, &jot_path)?
+                .into_iter()
+                .find(|r| r.change != git::JotChange::Deleted)
+                .with_context(|| format!("No committed version of '{id}' was found."))?;
+            repo.find_commit(revision.id)?
+        }
+    };
+
+    let Some(content) = read_jot_at(&repo, entries_dir, &commit, &jot_path)? else {
+        bail!(
+            "Jot '{}' did not exist at commit {}.",
+            id,
+            short_oid(commit.id())
+        );
+    };
+    helpers::write_note_file(&working_path, &content)?;
+    println!("Restored '{}' from commit {}.", id, short_oid(commit.id()));
+    git::auto_commit(&format!("restore {id}"))
+}
+
+/// Opens the journal's repository and finds a jot's path relative to the rjot root.
+///
+/// Jots that no longer exist on disk are looked up by prefix in the history.
+fn locate_jot_in_repo(
+    entries_dir: &Path,
+    id_prefix: Option<String>,
+    last: Option<usize>,
+) -> Result<(Repository, PathBuf)> {
+    let root_dir = get_rjot_dir_root()?;
+    let repo = git::open_repo(&root_dir)?;
+    let note_path = match get_note_path_for_action(entries_dir, id_prefix.clone(), last) {
+        Ok(path) => path,
+        Err(e) => {
+            let Some(prefix) = id_prefix else {
+                return Err(e);
+            };
+            let notebook_path = entries_dir.strip_prefix(&root_dir)?;
+            match git::find_jot_in_history(&repo, notebook_path, &prefix)? {
+                Some(path) => root_dir.join(path),
+                None => return Err(e),
+            }
+        }
+    };
+    let jot_path = note_path.strip_prefix(&root_dir)?.to_path_buf();
+    Ok((repo, jot_path))
+}
+
+/// Reads and decrypts a jot as it was in `commit`, or `None` if it didn't exist then.
+fn read_jot_at(
+    repo: &Repository,
+    entries_dir: &Path,
+    commit: &git2::Commit,
+    jot_path: &Path,
+) -> Result<Option<String>> {
+    match git::blob_at(commit, jot_path)? {
+        Some(oid) => Ok(Some(git::blob_to_text(repo, oid, entries_dir)?)),
+        None => Ok(None),
+    }
+}
+
+/// Abbreviates a commit ID for display.
+fn short_oid(oid: git2::Oid) -> String {
+    oid.to_string()[..7].to_string()
+}
+
+/// Returns a jot's ID, which is its file name without the `.md` extension.
+fn note_id(note_path: &Path) -> String {
+    note_path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
 /// Deletes a specific jot with user confirmation.
 pub fn command_delete(note_path: PathBuf, force: bool) -> Result<()> {
     let filename = note_path.file_name().unwrap().to_string_lossy();
@@ -1072,12 +2223,98 @@ pub fn command_delete(note_path: PathBuf, force: bool) -> Result<()> {
     }
     fs::remove_file(&note_path)?;
     println!("Successfully deleted '{filename}'.");
-    Ok(())
+    git::auto_commit(&format!("delete {}", note_id(&note_path)))
+}
+
+/// Moves or copies jots from the active notebook into another notebook.
+///
+/// Jots are re-encrypted according to the destination's policy. A jot whose ID is
+/// already taken in the destination gets the next free ID.
+pub fn command_transfer(entries_dir: &Path, args: TransferArgs, copy: bool) -> Result<()> {
+    let destination = existing_notebook_dir(&args.to)?;
+    if destination == entries_dir {
+        bail!("The jots are already in notebook '{}'.", args.to);
+    }
+
+    let paths = if let Some(tags) = &args.tag {
+        let notebook_name = entries_dir.file_name().unwrap().to_string_lossy();
+        let mut paths = Vec::new();
+        for path in list_note_paths(entries_dir)? {
+            let note = parse_note_from_file(&path, &notebook_name)?;
+            if note.frontmatter.tags.iter().any(|t| tags.contains(t)) {
+                paths.push(path);
+            }
+        }
+        paths
+    } else if let Some(date_spec) = &args.on {
+        let (start, end) = match date_spec.split_once("..") {
+            Some((start, end)) => (start, end),
+            None => (date_spec.as_str(), date_spec.as_str()),
+        };
+        let start = NaiveDate::parse_from_str(start, "%Y-%m-%d")?;
+        let end = NaiveDate::parse_from_str(end, "%Y-%m-%d")?;
+        list_note_paths(entries_dir)?
+            .into_iter()
+            .filter(|path| {
+                let filename = path.file_name().unwrap().to_string_lossy();
+                filename
+                    .get(0..10)
+                    .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+                    .is_some_and(|date| date >= start && date <= end)
+            })
+            .collect()
+    } else {
+        vec![get_note_path_for_action(
+            entries_dir,
+            args.id_prefix.clone(),
+            args.last,
+        )?]
+    };
+    if paths.is_empty() {
+        println!("No matching jots found.");
+        return Ok(());
+    }
+    transfer_paths(&destination, &args.to, paths, copy)
+}
+
+// Moves or copies the given jot files into `destination` and records a single commit.
+fn transfer_paths(destination: &Path, to: &str, mut paths: Vec<PathBuf>, copy: bool) -> Result<()> {
+    let (verb, past) = if copy {
+        ("copy", "Copied")
+    } else {
+        ("move", "Moved")
+    };
+    paths.sort();
+    for path in &paths {
+        let id = note_id(path);
+        let content = helpers::read_note_file(path)?;
+        let target = helpers::unique_note_path(destination, &id);
+        helpers::write_note_file(&target, &content)?;
+        if !copy {
+            fs::remove_file(path)?;
+        }
+        let new_id = note_id(&target);
+        if new_id == id {
+            println!("{past} '{id}' to '{to}'.");
+        } else {
+            println!("{past} '{id}' to '{to}' as '{new_id}' because the ID was taken.");
+        }
+    }
+
+    git::auto_commit(&format!("{verb} {} to {to}", jots_subject(&paths)))
+}
+
+// Names the jots in a commit message: the ID of a single jot, otherwise their count.
+fn jots_subject(paths: &[PathBuf]) -> String {
+    match paths {
+        [path] => note_id(path),
+        _ => format!("{} jots", paths.len()),
+    }
 }
 
 /// Displays information and statistics about the journal.
 /// This command is notebook-aware.
-pub fn command_info(entries_dir: &PathBuf, args: InfoArgs) -> Result<()> {
+pub fn command_info(entries_dir: &Path, args: InfoArgs) -> Result<()> {
     if !args.paths && !args.stats {
         println!(
             "Please provide a flag to the info command, e.g., `rjot info --paths` or `rjot info --stats`"
@@ -1087,8 +2324,7 @@ pub fn command_info(entries_dir: &PathBuf, args: InfoArgs) -> Result<()> {
     }
     if args.paths {
         println!("--- rjot paths ---");
-        let active_notebook =
-            env::var("RJOT_ACTIVE_NOTEBOOK").unwrap_or_else(|_| "default".to_string());
+        let active_notebook = entries_dir.file_name().unwrap().to_string_lossy();
         println!("Root Directory:   {:?}", helpers::get_rjot_dir_root()?);
         println!("Notebooks Root:   {:?}", helpers::get_notebooks_dir()?);
         println!("Active Notebook:  {active_notebook}");
@@ -1100,23 +2336,18 @@ pub fn command_info(entries_dir: &PathBuf, args: InfoArgs) -> Result<()> {
 
         if args.all {
             // Stats for all notebooks
-            let notebooks_dir = get_notebooks_dir()?;
             let mut total_notes = 0;
             let mut all_tags: HashMap<String, usize> = HashMap::new();
             let mut total_task_stats = TaskStats::default();
 
-            for entry in fs::read_dir(notebooks_dir)?.filter_map(Result::ok) {
-                if entry.path().is_dir() {
-                    let notebook_path = entry.path();
-                    let (note_count, tag_counts, task_stats) =
-                        calculate_stats_for_dir(&notebook_path)?;
-                    total_notes += note_count;
-                    for (tag, count) in tag_counts {
-                        *all_tags.entry(tag).or_insert(0) += count;
-                    }
-                    total_task_stats.completed += task_stats.completed;
-                    total_task_stats.pending += task_stats.pending;
+            for notebook_path in helpers::list_notebooks(args.include_archived)? {
+                let (note_count, tag_counts, task_stats) = calculate_stats_for_dir(&notebook_path)?;
+                total_notes += note_count;
+                for (tag, count) in tag_counts {
+                    *all_tags.entry(tag).or_insert(0) += count;
                 }
+                total_task_stats.completed += task_stats.completed;
+                total_task_stats.pending += task_stats.pending;
             }
             println!("Stats for all notebooks combined:");
             print_stats(total_notes, all_tags, total_task_stats);
@@ -1135,40 +2366,33 @@ pub fn command_info(entries_dir: &PathBuf, args: InfoArgs) -> Result<()> {
 }
 
 /// Formats and prints a list of notes from a global search.
-pub fn display_global_find_list(notes: Vec<helpers::Note>) {
+pub fn display_global_find_list(notes: Vec<helpers::Note>) -> Result<()> {
+    helpers::remember_listing(&notes);
     if notes.is_empty() {
         println!("\nNo jots found.");
-        return;
-    }
-    // Adjust spacing as needed for your desired output
-    println!("\n{:<22} {:<18} FIRST LINE OF CONTENT", "ID", "NOTEBOOK");
-    println!("{:-<22} {:-<18} {:-<50}", "", "", "");
-    for note in notes {
-        let first_line = note.content.lines().next().unwrap_or("").trim();
-        println!("{:<22} {:<18} {}", note.id, note.notebook, first_line);
+        return Ok(());
     }
+    crate::render::print_note_table(&notes, Some("notebook"))
 }
 
 /// Helper function to calculate stats for a given directory.
-fn calculate_stats_for_dir(dir: &Path) -> Result<(usize, HashMap<String, usize>, TaskStats)> {
+pub fn calculate_stats_for_dir(dir: &Path) -> Result<(usize, HashMap<String, usize>, TaskStats)> {
     let mut note_count = 0;
     let mut tag_counts: HashMap<String, usize> = HashMap::new();
     let mut task_stats = TaskStats::default();
     let notebook_name = dir.file_name().unwrap().to_string_lossy();
 
-    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
-        if entry.path().is_file() {
-            note_count += 1;
-            let note = parse_note_from_file(&entry.path(), &notebook_name)?;
-            for tag in note.frontmatter.tags {
-                *tag_counts.entry(tag).or_insert(0) += 1;
-            }
-            for task in note.tasks {
-                if task.completed {
-                    task_stats.completed += 1;
-                } else {
-                    task_stats.pending += 1;
-                }
+    for path in list_note_paths(dir)? {
+        note_count += 1;
+        let note = parse_note_from_file(&path, &notebook_name)?;
+        for tag in note.frontmatter.tags {
+            *tag_counts.entry(tag).or_insert(0) += 1;
+        }
+        for task in note.tasks {
+            if task.completed {
+                task_stats.completed += 1;
+            } else {
+                task_stats.pending += 1;
             }
         }
     }
@@ -1180,7 +2404,7 @@ fn print_stats(note_count: usize, tag_counts: HashMap<String, usize>, task_stats
     println!("Total jots: {note_count}");
     if !tag_counts.is_empty() {
         let mut sorted_tags: Vec<_> = tag_counts.into_iter().collect();
-        sorted_tags.sort_by(|a, b| b.1.cmp(&a.1));
+        sorted_tags.sort_by_key(|b| std::cmp::Reverse(b.1));
         sorted_tags.truncate(5);
         println!("\nMost common tags:");
         for (tag, count) in sorted_tags {
@@ -1196,57 +2420,113 @@ fn print_stats(note_count: usize, tag_counts: HashMap<String, usize>, task_stats
 
 /// Exports a notebook to a specified file format.
 pub fn command_export(args: ExportArgs) -> Result<()> {
-    let notebooks_dir = get_notebooks_dir()?;
-    let notebook_path = notebooks_dir.join(&args.notebook_name);
-
-    if !notebook_path.is_dir() {
-        bail!("Notebook '{}' not found.", args.notebook_name);
+    let notebook_path = existing_notebook_dir(&args.notebook_name)?;
+    if args.raw && args.format != "zip" {
+        bail!("The --raw option is only supported for zip exports.");
     }
 
-    match args.format.as_str() {
-        "zip" => export_to_zip(&notebook_path, &args.output)?,
-        "json" => export_to_json(&notebook_path, &args.notebook_name, &args.output)?,
+    let archive_bytes = match args.format.as_str() {
+        "zip" => export_to_zip(&notebook_path, args.raw)?,
+        "json" => export_to_json(&notebook_path, &args.notebook_name)?,
         _ => bail!(
             "Unsupported format: '{}'. Please use 'zip' or 'json'.",
             args.format
         ),
-    }
+    };
+
+    let output_bytes = if args.encrypt {
+        encrypt_archive(&archive_bytes, &notebook_path, &args)?
+    } else {
+        archive_bytes
+    };
+    fs::write(&args.output, output_bytes)?;
 
     println!(
-        "Successfully exported notebook '{}' to {:?}",
-        args.notebook_name, args.output
+        "Successfully exported notebook '{}' to {:?}{}",
+        args.notebook_name,
+        args.output,
+        if args.encrypt { " (encrypted)" } else { "" }
     );
     Ok(())
 }
 
-fn export_to_zip(notebook_path: &Path, output_path: &Path) -> Result<()> {
-    let file = File::create(output_path)?;
-    let mut zip = ZipWriter::new(file);
+/// Wraps an export archive in `age` encryption.
+///
+/// A passphrase or explicit recipient takes precedence; otherwise the archive is
+/// encrypted for the same key the notebook's jots are.
+fn encrypt_archive(
+    archive_bytes: &[u8],
+    notebook_path: &Path,
+    args: &ExportArgs,
+) -> Result<Vec<u8>> {
+    if args.passphrase {
+        let passphrase = helpers::read_passphrase(true)?;
+        let encryptor = age::Encryptor::with_user_passphrase(passphrase);
+        let mut encrypted = vec![];
+        let mut writer = encryptor.wrap_output(&mut encrypted)?;
+        writer.write_all(archive_bytes)?;
+        writer.finish()?;
+        return Ok(encrypted);
+    }
+
+    let recipient = match &args.recipient {
+        Some(recipient) => recipient.clone(),
+        None => helpers::resolve_recipient(notebook_path)?.with_context(|| {
+            "This notebook has no encryption key. Use --recipient <KEY> or --passphrase."
+        })?,
+    };
+    helpers::encrypt_for_recipient(&recipient, archive_bytes)
+}
+
+/// The archive comment that marks a zip made by `export --raw`.
+const RAW_EXPORT_COMMENT: &str = "rjot export --raw";
+
+fn export_to_zip(notebook_path: &Path, raw: bool) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(io::Cursor::new(Vec::new()));
     let options = FileOptions::<()>::default().compression_method(zip::CompressionMethod::Zstd);
 
-    for entry in fs::read_dir(notebook_path)?.filter_map(Result::ok) {
-        let path = entry.path();
-        if path.is_file() {
-            let filename = path.file_name().unwrap().to_str().unwrap();
-            zip.start_file(filename, options)?;
+    for path in list_note_paths(notebook_path)? {
+        let filename = path.file_name().unwrap().to_str().unwrap();
+        zip.start_file(filename, options)?;
+        if raw {
+            zip.write_all(&fs::read(&path)?)?;
+        } else {
             let content = helpers::read_note_file(&path)?;
             zip.write_all(content.as_bytes())?;
         }
     }
-    zip.finish()?;
-    Ok(())
+
+    // Raw exports keep the notebook's policy so a restored notebook stays encrypted.
+    if raw {
+        zip.set_comment(RAW_EXPORT_COMMENT);
+        if notebook_path.join(helpers::NOTEBOOK_CONFIG_FILE).exists() {
+            let policy = encryption_policy(helpers::load_notebook_config(notebook_path)?);
+            zip.start_file(helpers::NOTEBOOK_CONFIG_FILE, options)?;
+            zip.write_all(toml::to_string_pretty(&policy)?.as_bytes())?;
+        }
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+/// Keeps only the parts of a notebook config that describe how its jots are stored.
+///
+/// Other settings, such as the editor, are never carried between machines in an archive.
+fn encryption_policy(config: helpers::NotebookConfig) -> helpers::NotebookConfig {
+    helpers::NotebookConfig {
+        encrypt: config.encrypt,
+        recipient: config.recipient,
+        archived: config.archived,
+        ..Default::default()
+    }
 }
 
-fn export_to_json(notebook_path: &Path, notebook_name: &str, output_path: &Path) -> Result<()> {
+fn export_to_json(notebook_path: &Path, notebook_name: &str) -> Result<Vec<u8>> {
     let mut jots = Vec::new();
-    for entry in fs::read_dir(notebook_path)?.filter_map(Result::ok) {
-        let path = entry.path();
-        if path.is_file() {
-            jots.push(JsonJot {
-                filename: path.file_name().unwrap().to_string_lossy().to_string(),
-                content: helpers::read_note_file(&path)?,
-            });
-        }
+    for path in list_note_paths(notebook_path)? {
+        jots.push(JsonJot {
+            filename: path.file_name().unwrap().to_string_lossy().to_string(),
+            content: helpers::read_note_file(&path)?,
+        });
     }
 
     let export_data = JsonExport {
@@ -1254,22 +2534,34 @@ fn export_to_json(notebook_path: &Path, notebook_name: &str, output_path: &Path)
         jots,
     };
 
-    let json_string = serde_json::to_string_pretty(&export_data)?;
-    fs::write(output_path, json_string)?;
-    Ok(())
+    Ok(serde_json::to_string_pretty(&export_data)?.into_bytes())
 }
 
 /// Imports a notebook from a specified file.
 pub fn command_import(args: ImportArgs) -> Result<()> {
-    let extension = args
-        .file_path
+    let mut bytes = fs::read(&args.file_path)?;
+
+    // An `.age` suffix is dropped so `work.zip.age` imports like `work.zip`.
+    let mut inner_path = args.file_path.clone();
+    if inner_path.extension().is_some_and(|ext| ext == "age") {
+        inner_path.set_extension("");
+    }
+
+    if helpers::is_encrypted(&bytes) {
+        bytes = decrypt_archive(&bytes)?;
+        println!("Decrypted archive {:?}.", args.file_path);
+    }
+
+    let extension = inner_path
         .extension()
         .and_then(|s| s.to_str())
         .unwrap_or("");
+    let is_zip = bytes.starts_with(b"PK\x03\x04");
 
     match extension {
-        "zip" => import_from_zip(&args.file_path)?,
-        "json" => import_from_json(&args.file_path)?,
+        "zip" => import_from_zip(bytes, &inner_path)?,
+        "json" => import_from_json(&bytes, &args.file_path)?,
+        _ if is_zip => import_from_zip(bytes, &inner_path)?,
         _ => bail!(
             "Unsupported file type: '{:?}'. Please use a '.zip' or '.json' file.",
             args.file_path
@@ -1278,10 +2570,30 @@ pub fn command_import(args: ImportArgs) -> Result<()> {
     Ok(())
 }
 
-fn import_from_zip(file_path: &Path) -> Result<()> {
-    let file = File::open(file_path)?;
-    let mut archive = ZipArchive::new(file)?;
+/// Decrypts an archive produced by `export --encrypt`.
+fn decrypt_archive(bytes: &[u8]) -> Result<Vec<u8>> {
+    match age::Decryptor::new(bytes)? {
+        age::Decryptor::Passphrase(decryptor) => {
+            let passphrase = helpers::read_passphrase(false)?;
+            let mut decrypted = vec![];
+            decryptor
+                .decrypt(&passphrase, None)
+                .map_err(|e| anyhow!("Failed to decrypt archive: {}", e))?
+                .read_to_end(&mut decrypted)?;
+            Ok(decrypted)
+        }
+        age::Decryptor::Recipients(_) => {
+            helpers::decrypt_with_identities(bytes, &helpers::load_all_identities()?)
+                .with_context(|| "Failed to decrypt archive with any known identity.")
+        }
+    }
+}
+
+fn import_from_zip(bytes: Vec<u8>, file_path: &Path) -> Result<()> {
+    let mut archive = ZipArchive::new(io::Cursor::new(bytes))?;
     let notebook_name = file_path.file_stem().unwrap().to_string_lossy().to_string();
+    helpers::validate_notebook_name(&notebook_name)
+        .context("Rename the archive to import it under a valid notebook name")?;
     let notebooks_dir = get_notebooks_dir()?;
     let new_notebook_path = notebooks_dir.join(&notebook_name);
 
@@ -1290,20 +2602,56 @@ fn import_from_zip(file_path: &Path) -> Result<()> {
     }
     fs::create_dir_all(&new_notebook_path)?;
 
+    // The notebook's policy must be in place before any plaintext jot is written. Only
+    // raw exports carry one; a config in any other archive is ignored.
+    let is_raw_export = archive.comment() == RAW_EXPORT_COMMENT.as_bytes();
+    if is_raw_export {
+        if let Ok(mut config_file) = archive.by_name(helpers::NOTEBOOK_CONFIG_FILE) {
+            let mut config_text = String::new();
+            config_file.read_to_string(&mut config_text)?;
+            let config: helpers::NotebookConfig = toml::from_str(&config_text)
+                .context("The archive's notebook.toml could not be read")?;
+            helpers::save_notebook_config(&new_notebook_path, &encryption_policy(config))?;
+        }
+    }
+
+    let mut still_encrypted = 0;
     for i in 0..archive.len() {
         let mut file = archive.by_index(i)?;
-        let outpath = new_notebook_path.join(file.name());
-        let mut outfile = File::create(&outpath)?;
-        io::copy(&mut file, &mut outfile)?;
+        let Some(name) = file.enclosed_name() else {
+            bail!("Archive contains an unsafe path: '{}'", file.name());
+        };
+        if name == Path::new(helpers::NOTEBOOK_CONFIG_FILE) {
+            continue;
+        }
+        let outpath = new_notebook_path.join(name);
+        let mut contents = vec![];
+        file.read_to_end(&mut contents)?;
+
+        // Plaintext jots go through the normal write path so they pick up the
+        // destination's encryption policy. Raw encrypted jots are kept as-is.
+        if helpers::is_encrypted(&contents) {
+            still_encrypted += 1;
+            fs::write(&outpath, contents)?;
+        } else if outpath.extension().is_some_and(|ext| ext == "md") {
+            helpers::write_note_file(&outpath, &String::from_utf8(contents)?)?;
+        } else {
+            fs::write(&outpath, contents)?;
+        }
     }
 
     println!("Successfully imported notebook '{notebook_name}' from {file_path:?}");
+    if still_encrypted > 0 {
+        println!(
+            "Note: {still_encrypted} jot(s) are still encrypted. If they used a notebook key, copy that notebook's identity.txt into {new_notebook_path:?}."
+        );
+    }
     Ok(())
 }
 
-fn import_from_json(file_path: &Path) -> Result<()> {
-    let json_string = fs::read_to_string(file_path)?;
-    let export_data: JsonExport = serde_json::from_str(&json_string)?;
+fn import_from_json(bytes: &[u8], file_path: &Path) -> Result<()> {
+    let export_data: JsonExport = serde_json::from_slice(bytes)?;
+    helpers::validate_notebook_name(&export_data.notebook_name)?;
     let notebooks_dir = get_notebooks_dir()?;
     let new_notebook_path = notebooks_dir.join(&export_data.notebook_name);
 
@@ -1316,7 +2664,13 @@ fn import_from_json(file_path: &Path) -> Result<()> {
     fs::create_dir_all(&new_notebook_path)?;
 
     for jot in export_data.jots {
-        let jot_path = new_notebook_path.join(jot.filename);
+        let Some(filename) = Path::new(&jot.filename).file_name() else {
+            bail!(
+                "Archive contains an invalid jot filename: '{}'",
+                jot.filename
+            );
+        };
+        let jot_path = new_notebook_path.join(filename);
         helpers::write_note_file(&jot_path, &jot.content)?;
     }
 
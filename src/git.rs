@@ -0,0 +1,731 @@
+//! This module contains the Git plumbing behind `rjot sync`, automatic commits, and
+//! per-jot history.
+//!
+//! It wraps the `git2` crate to stage and commit the rjot directory, fetch and
+//! integrate remote changes, resolve conflicts between jots, and read old versions
+//! of a jot. Blobs are decrypted before they are compared, so encrypted notebooks
+//! can be merged and diffed too.
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use git2::{
+    build::CheckoutBuilder, AnnotatedCommit, Commit, Cred, FetchOptions, IndexConflict, Oid,
+    PushOptions, RemoteCallbacks, Repository, Signature, Sort,
+};
+
+use crate::helpers;
+
+/// The outcome of integrating remote changes into the local branch.
+pub enum MergeOutcome {
+    /// The local branch already contains the remote changes.
+    UpToDate,
+    /// The local branch was moved forward to the remote commit.
+    FastForward,
+    /// A merge commit was created, possibly after resolving conflicts automatically.
+    Merged { auto_resolved: Vec<String> },
+    /// The merge stopped with conflicts that need `rjot sync --resolve`.
+    Conflicted { conflicts: Vec<String> },
+}
+
+/// A conflicting file with each side as text; jots are decrypted.
+///
+/// A side is `None` when the file was deleted (or never existed) on that side.
+pub struct JotConflict {
+    pub path: PathBuf,
+    pub ancestor: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+impl JotConflict {
+    /// Returns `true` if the conflict is on a jot rather than on another tracked file.
+    pub fn is_jot(&self) -> bool {
+        is_jot_path(&self.path)
+    }
+}
+
+/// Returns `true` for paths of the form `notebooks/<notebook>/<jot>.md`.
+///
+/// Only these files are encrypted by rjot or can be merged as jots; notebook
+/// configuration, templates, and `.gitignore` are kept as they are.
+pub fn is_jot_path(path: &Path) -> bool {
+    let components: Vec<_> = path.components().collect();
+    matches!(
+        components.as_slice(),
+        [Component::Normal(root), Component::Normal(_), Component::Normal(_)]
+            if *root == "notebooks" && path.extension().is_some_and(|ext| ext == "md")
+    )
+}
+
+/// How a commit changed a jot.
+#[derive(Debug, PartialEq, Eq)]
+pub enum JotChange {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/// A commit that changed a jot.
+pub struct JotRevision {
+    pub id: Oid,
+    pub time: DateTime<Local>,
+    pub summary: String,
+    pub change: JotChange,
+}
+
+/// Opens the rjot directory as a Git repository.
+pub fn open_repo(root_dir: &Path) -> Result<Repository> {
+    Repository::open(root_dir).map_err(|_| {
+        anyhow!(
+            "rjot directory at {:?} is not a Git repository. Run `rjot init --git` first.",
+            root_dir
+        )
+    })
+}
+
+/// Returns the signature used for commits made by rjot.
+///
+/// The author comes from the `[git]` section of `config.toml`, then from Git's own
+/// `user.name` and `user.email`, and finally falls back to a generic rjot identity.
+pub fn signature(repo: &Repository) -> Result<Signature<'static>> {
+    let config = helpers::load_config()?.git;
+    let git_config = repo.config().ok();
+    let from_git = |key: &str| git_config.as_ref().and_then(|c| c.get_string(key).ok());
+
+    let name = config
+        .name
+        .or_else(|| from_git("user.name"))
+        .unwrap_or_else(|| "rjot".to_string());
+    let email = config
+        .email
+        .or_else(|| from_git("user.email"))
+        .unwrap_or_else(|| "rjot@localhost".to_string());
+    Ok(Signature::now(&name, &email)?)
+}
+
+/// Returns the SSH private keys to try, from `[git] ssh_keys` or the usual defaults.
+///
+/// A leading `~` in a configured path is expanded to the home directory.
+fn ssh_key_paths() -> Vec<PathBuf> {
+    let home_dir = dirs::home_dir();
+    let configured = helpers::load_config()
+        .map(|config| config.git.ssh_keys)
+        .unwrap_or_default();
+    if configured.is_empty() {
+        let Some(home_dir) = home_dir else {
+            return Vec::new();
+        };
+        return ["id_ed25519", "id_ecdsa", "id_rsa"]
+            .iter()
+            .map(|name| home_dir.join(".ssh").join(name))
+            .collect();
+    }
+    configured
+        .into_iter()
+        .map(|path| match (path.strip_prefix("~"), &home_dir) {
+            (Ok(rest), Some(home_dir)) => home_dir.join(rest),
+            _ => path,
+        })
+        .collect()
+}
+
+/// Builds the callbacks used to authenticate against a remote.
+///
+/// Credentials are tried in order: `$GITHUB_TOKEN`, the SSH agent, the SSH keys from
+/// [`ssh_key_paths`], and finally the configured Git credential helper.
+pub fn remote_callbacks(repo: &Repository) -> RemoteCallbacks<'_> {
+    let ssh_keys = ssh_key_paths();
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(move |url, username_from_git, allowed_types| {
+        let username = username_from_git.unwrap_or("git");
+
+        if allowed_types.is_user_pass_plaintext() {
+            if let Ok(token) = env::var("GITHUB_TOKEN") {
+                return Cred::userpass_plaintext(username, &token);
+            }
+        }
+
+        if allowed_types.is_ssh_key() {
+            if let Ok(cred) = Cred::ssh_key_from_agent(username) {
+                return Ok(cred);
+            }
+        }
+
+        if allowed_types.is_ssh_key() {
+            for key in ssh_keys.iter().filter(|key| key.exists()) {
+                if let Ok(cred) = Cred::ssh_key(username, None, key, None) {
+                    return Ok(cred);
+                }
+            }
+        }
+
+        if allowed_types.is_user_pass_plaintext() {
+            if let Ok(cred) = Cred::credential_helper(&repo.config()?, url, Some(username)) {
+                return Ok(cred);
+            }
+        }
+
+        Err(git2::Error::new(
+            git2::ErrorCode::Auth,
+            git2::ErrorClass::Ssh,
+            "failed to acquire credentials",
+        ))
+    });
+    callbacks
+}
+
+/// Gets the name of the currently checked-out branch.
+pub fn current_branch(repo: &Repository) -> Result<String> {
+    let head = repo.find_reference("HEAD")?;
+    let target = head.symbolic_target().with_context(|| {
+        "Could not get branch name from HEAD. Are you in a detached HEAD state?"
+    })?;
+    Ok(target.trim_start_matches("refs/heads/").to_string())
+}
+
+/// Lists the uncommitted changes in the working directory, sorted by path.
+pub fn pending_changes(repo: &Repository) -> Result<Vec<(JotChange, String)>> {
+    let mut options = git2::StatusOptions::new();
+    options.include_untracked(true).recurse_untracked_dirs(true);
+    let mut changes = Vec::new();
+    for entry in repo.statuses(Some(&mut options))?.iter() {
+        let status = entry.status();
+        let change = if status.intersects(git2::Status::WT_NEW | git2::Status::INDEX_NEW) {
+            JotChange::Added
+        } else if status.intersects(git2::Status::WT_DELETED | git2::Status::INDEX_DELETED) {
+            JotChange::Deleted
+        } else if status.is_ignored() || status.is_conflicted() || status.is_empty() {
+            continue;
+        } else {
+            JotChange::Modified
+        };
+        if let Some(path) = entry.path().filter(|path| *path != helpers::HISTORY_FILE) {
+            changes.push((change, path.to_string()));
+        }
+    }
+    changes.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(changes)
+}
+
+/// The default template for `rjot sync` commit messages.
+pub const DEFAULT_SYNC_MESSAGE: &str = "rjot sync: {summary}\n\n{jots}";
+
+/// Builds a `rjot sync` commit message from a template and the pending changes.
+///
+/// `{date}` is replaced with the current time, `{summary}` with counts such as
+/// "2 jots added, 1 jot modified", and `{jots}` with one line per changed jot.
+pub fn sync_commit_message(template: &str, changes: &[(JotChange, String)]) -> String {
+    let mut jots = Vec::new();
+    let mut counts = [0usize; 3];
+    let mut other_files = 0;
+    for (change, path) in changes {
+        let Some(jot) = path
+            .strip_prefix("notebooks/")
+            .and_then(|p| p.strip_suffix(".md"))
+        else {
+            other_files += 1;
+            continue;
+        };
+        let (slot, label) = match change {
+            JotChange::Added => (0, "added"),
+            JotChange::Modified => (1, "modified"),
+            JotChange::Deleted => (2, "deleted"),
+        };
+        counts[slot] += 1;
+        jots.push(format!("- {label}: {jot}"));
+    }
+
+    let mut parts: Vec<String> = counts
+        .iter()
+        .zip(["added", "modified", "deleted"])
+        .filter(|(count, _)| **count > 0)
+        .map(|(count, label)| {
+            let noun = if *count == 1 { "jot" } else { "jots" };
+            format!("{count} {noun} {label}")
+        })
+        .collect();
+    if other_files > 0 {
+        let noun = if other_files == 1 { "file" } else { "files" };
+        parts.push(format!("{other_files} other {noun} changed"));
+    }
+    let summary = if parts.is_empty() {
+        "no changes".to_string()
+    } else {
+        parts.join(", ")
+    };
+
+    template
+        .replace("{date}", &Local::now().to_rfc2822())
+        .replace("{summary}", &summary)
+        .replace("{jots}", &jots.join("\n"))
+        .trim()
+        .to_string()
+}
+
+/// Stages every change in the working directory, including deletions.
+///
+/// The shell history is machine-local, so it is never staged, even in journals whose
+/// `.gitignore` predates it.
+pub fn stage_all(repo: &Repository) -> Result<git2::Index> {
+    let mut index = repo.index()?;
+    index.add_all(
+        ["."].iter(),
+        git2::IndexAddOption::DEFAULT,
+        Some(&mut |path: &Path, _: &[u8]| i32::from(path == Path::new(helpers::HISTORY_FILE))),
+    )?;
+    index.update_all(["."].iter(), None)?;
+    index.write()?;
+    Ok(index)
+}
+
+/// Commits the staged tree on top of `HEAD`.
+///
+/// Returns `None` without committing if the tree is identical to `HEAD`'s.
+pub fn commit_index(
+    repo: &Repository,
+    index: &mut git2::Index,
+    message: &str,
+) -> Result<Option<Oid>> {
+    let tree = repo.find_tree(index.write_tree()?)?;
+
+    let parent_commits = match repo.head() {
+        Ok(head_ref) => vec![head_ref.peel_to_commit()?],
+        Err(ref e) if e.code() == git2::ErrorCode::UnbornBranch => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(parent) = parent_commits.first() {
+        if parent.tree_id() == tree.id() {
+            return Ok(None);
+        }
+    }
+
+    let parents_ref: Vec<&git2::Commit> = parent_commits.iter().collect();
+    let signature = signature(repo)?;
+    let oid = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents_ref,
+    )?;
+    Ok(Some(oid))
+}
+
+/// Fetches `branch` from `remote_name` and returns the remote-tracking commit, if any.
+pub fn fetch<'r>(
+    repo: &'r Repository,
+    remote_name: &str,
+    branch: &str,
+) -> Result<Option<AnnotatedCommit<'r>>> {
+    let mut remote = repo.find_remote(remote_name).map_err(|_| {
+        anyhow!(
+            "Could not find remote '{}'. Please add a remote to your git repository.",
+            remote_name
+        )
+    })?;
+
+    let mut fetch_options = FetchOptions::new();
+    fetch_options.remote_callbacks(remote_callbacks(repo));
+    let refspec = format!("+refs/heads/{branch}:refs/remotes/{remote_name}/{branch}");
+    remote.fetch(&[&refspec], Some(&mut fetch_options), None)?;
+
+    match repo.find_reference(&format!("refs/remotes/{remote_name}/{branch}")) {
+        Ok(reference) => Ok(Some(repo.reference_to_annotated_commit(&reference)?)),
+        // The branch doesn't exist on the remote yet, e.g. before the first push.
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Pushes the local `branch` to `remote_branch` on `remote_name`.
+pub fn push(repo: &Repository, remote_name: &str, branch: &str, remote_branch: &str) -> Result<()> {
+    let mut remote = repo.find_remote(remote_name).map_err(|_| {
+        anyhow!(
+            "Could not find remote '{}'. Please add a remote to your git repository.",
+            remote_name
+        )
+    })?;
+    let refspec = format!("refs/heads/{branch}:refs/heads/{remote_branch}");
+    let mut callbacks = remote_callbacks(repo);
+    // The remote reports rejected updates here rather than failing the push itself.
+    callbacks.push_update_reference(|refname, status| match status {
+        Some(message) => Err(git2::Error::from_str(&format!(
+            "remote rejected {refname}: {message}"
+        ))),
+        None => Ok(()),
+    });
+    let mut push_options = PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+    remote.push(&[&refspec], Some(&mut push_options))?;
+    Ok(())
+}
+
+/// Commits all changes with `message` if `auto_commit` is enabled in `config.toml`.
+///
+/// Does nothing if the rjot directory is not a Git repository or a sync is waiting
+/// for conflicts to be resolved. With `auto_push`, the commit is also pushed to the
+/// configured remote; a failed push only prints a warning, since the jot itself was saved.
+pub fn auto_commit(message: &str) -> Result<()> {
+    let config = helpers::load_config()?.git;
+    if !config.auto_commit {
+        return Ok(());
+    }
+    let Ok(repo) = Repository::open(helpers::get_rjot_dir_root()?) else {
+        return Ok(());
+    };
+    if is_merging(&repo) {
+        eprintln!("Warning: skipping auto-commit until `rjot sync --resolve` finishes.");
+        return Ok(());
+    }
+
+    let mut index = stage_all(&repo)?;
+    if commit_index(&repo, &mut index, message)?.is_none() || !config.auto_push {
+        return Ok(());
+    }
+    let pushed = current_branch(&repo).and_then(|branch| {
+        let remote_branch = config.branch.as_deref().unwrap_or(&branch);
+        push(&repo, config.remote(), &branch, remote_branch)
+    });
+    if let Err(e) = pushed {
+        eprintln!("Warning: auto-push failed: {e:#}. Run `rjot sync` to catch up.");
+    }
+    Ok(())
+}
+
+/// Integrates a fetched remote commit into the current branch.
+///
+/// Fast-forwards when possible. Otherwise the branches are merged; conflicting jots
+/// that were only appended to on both sides are resolved automatically, and any
+/// remaining conflicts are left in the index for `rjot sync --resolve`.
+pub fn integrate(
+    repo: &Repository,
+    branch: &str,
+    remote_commit: &AnnotatedCommit,
+) -> Result<MergeOutcome> {
+    let (analysis, _) = repo.merge_analysis(&[remote_commit])?;
+
+    if analysis.is_up_to_date() {
+        return Ok(MergeOutcome::UpToDate);
+    }
+
+    if analysis.is_fast_forward() || analysis.is_unborn() {
+        let target = repo.find_object(remote_commit.id(), None)?;
+        repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))?;
+        let refname = format!("refs/heads/{branch}");
+        match repo.find_reference(&refname) {
+            Ok(mut reference) => {
+                reference.set_target(remote_commit.id(), "rjot sync: fast-forward")?;
+            }
+            Err(_) => {
+                repo.reference(&refname, remote_commit.id(), true, "rjot sync")?;
+            }
+        }
+        repo.set_head(&refname)?;
+        return Ok(MergeOutcome::FastForward);
+    }
+
+    repo.merge(&[remote_commit], None, Some(CheckoutBuilder::new().safe()))?;
+
+    let mut auto_resolved = Vec::new();
+    for conflict in read_conflicts(repo)?
+        .into_iter()
+        .filter(JotConflict::is_jot)
+    {
+        if let Some(merged) = merge_append_only(&conflict) {
+            write_resolution(repo, &conflict.path, Some(&merged))?;
+            auto_resolved.push(conflict.path.to_string_lossy().to_string());
+        }
+    }
+
+    let remaining = read_conflicts(repo)?;
+    if !remaining.is_empty() {
+        return Ok(MergeOutcome::Conflicted {
+            conflicts: remaining
+                .iter()
+                .map(|c| c.path.to_string_lossy().to_string())
+                .collect(),
+        });
+    }
+
+    commit_merge(repo)?;
+    Ok(MergeOutcome::Merged { auto_resolved })
+}
+
+/// Returns `true` if a previous sync stopped in the middle of a merge.
+pub fn is_merging(repo: &Repository) -> bool {
+    repo.state() == git2::RepositoryState::Merge
+}
+
+/// Reads every conflicting file from the index, decrypting each side of a jot.
+pub fn read_conflicts(repo: &Repository) -> Result<Vec<JotConflict>> {
+    let index = repo.index()?;
+    let workdir = workdir(repo)?;
+    let mut conflicts = Vec::new();
+    for conflict in index.conflicts()? {
+        let conflict: IndexConflict = conflict?;
+        let path = conflict
+            .our
+            .as_ref()
+            .or(conflict.their.as_ref())
+            .or(conflict.ancestor.as_ref())
+            .map(|entry| PathBuf::from(String::from_utf8_lossy(&entry.path).to_string()))
+            .with_context(|| "Found a conflict without any index entries.")?;
+        let notebook_dir = workdir.join(&path).parent().unwrap().to_path_buf();
+        let is_jot = is_jot_path(&path);
+        let read_side = |entry: &Option<git2::IndexEntry>| -> Result<Option<String>> {
+            match entry {
+                Some(entry) if is_jot => Ok(Some(blob_to_text(repo, entry.id, &notebook_dir)?)),
+                Some(entry) => Ok(Some(
+                    String::from_utf8_lossy(repo.find_blob(entry.id)?.content()).to_string(),
+                )),
+                None => Ok(None),
+            }
+        };
+        conflicts.push(JotConflict {
+            ancestor: read_side(&conflict.ancestor)?,
+            ours: read_side(&conflict.our)?,
+            theirs: read_side(&conflict.their)?,
+            path,
+        });
+    }
+    Ok(conflicts)
+}
+
+/// Reads a blob as text, decrypting it with the identities of its notebook if needed.
+pub fn blob_to_text(repo: &Repository, oid: Oid, notebook_dir: &Path) -> Result<String> {
+    let blob = repo.find_blob(oid)?;
+    let bytes = blob.content();
+    if helpers::is_encrypted(bytes) {
+        let identities = helpers::load_identities(notebook_dir)?;
+        Ok(String::from_utf8(helpers::decrypt_with_identities(
+            bytes,
+            &identities,
+        )?)?)
+    } else {
+        Ok(String::from_utf8_lossy(bytes).to_string())
+    }
+}
+
+/// Attempts to merge a conflicting jot that was only appended to.
+///
+/// This succeeds when one side is unchanged, or when both sides extend the common
+/// ancestor, in which case both additions are kept. Any other edit, such as one side
+/// cutting text the other side kept, is left for conflict resolution.
+pub fn merge_append_only(conflict: &JotConflict) -> Option<String> {
+    let ours = conflict.ours.as_deref()?;
+    let theirs = conflict.theirs.as_deref()?;
+    if ours == theirs {
+        return Some(ours.to_string());
+    }
+    let ancestor = conflict.ancestor.as_deref()?;
+
+    if theirs == ancestor {
+        Some(ours.to_string())
+    } else if ours == ancestor {
+        Some(theirs.to_string())
+    } else if !ours.starts_with(ancestor) || !theirs.starts_with(ancestor) {
+        None
+    } else if ours.starts_with(theirs) {
+        Some(ours.to_string())
+    } else if theirs.starts_with(ours) {
+        Some(theirs.to_string())
+    } else {
+        let their_addition = theirs[ancestor.len()..].trim_start_matches('\n');
+        Some(format!(
+            "{}\n{}",
+            ours.trim_end_matches('\n'),
+            their_addition
+        ))
+    }
+}
+
+/// Writes the resolution for a conflicting file and marks it as resolved.
+///
+/// `None` resolves the conflict by deleting the file. Jots are written through the
+/// normal note writer so they are re-encrypted according to the notebook's policy;
+/// any other file is written as it is.
+pub fn write_resolution(repo: &Repository, path: &Path, content: Option<&str>) -> Result<()> {
+    let full_path = workdir(repo)?.join(path);
+    let mut index = repo.index()?;
+    match content {
+        Some(content) => {
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if is_jot_path(path) {
+                helpers::write_note_file(&full_path, content)?;
+            } else {
+                fs::write(&full_path, content)?;
+            }
+            index.add_path(path)?;
+        }
+        None => {
+            if full_path.exists() {
+                fs::remove_file(&full_path)?;
+            }
+            index.remove_path(path)?;
+        }
+    }
+    index.write()?;
+    Ok(())
+}
+
+/// Creates the merge commit once all conflicts are resolved and clears the merge state.
+pub fn commit_merge(repo: &Repository) -> Result<Oid> {
+    let mut index = repo.index()?;
+    if index.has_conflicts() {
+        bail!("Cannot commit the merge while conflicts remain.");
+    }
+    let tree = repo.find_tree(index.write_tree()?)?;
+    let head_commit = repo.head()?.peel_to_commit()?;
+    let merge_head = repo
+        .revparse_single("MERGE_HEAD")
+        .and_then(|object| object.peel_to_commit())
+        .with_context(|| "Could not find the commit being merged.")?;
+    let signature = signature(repo)?;
+    let oid = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        "rjot sync: merge remote changes",
+        &tree,
+        &[&head_commit, &merge_head],
+    )?;
+    repo.cleanup_state()?;
+    Ok(oid)
+}
+
+/// Resolves a revision to a commit.
+///
+/// `spec` is either anything Git understands (`HEAD~2`, a commit hash, a branch) or a
+/// `YYYY-MM-DD` date, which selects the last commit made on or before that day in the
+/// configured time zone.
+pub fn resolve_revision<'r>(
+    repo: &'r Repository,
+    spec: &str,
+    preferences: &helpers::Preferences,
+) -> Result<Commit<'r>> {
+    if let Ok(date) = NaiveDate::parse_from_str(spec, "%Y-%m-%d") {
+        let end_of_day = date
+            .succ_opt()
+            .unwrap_or(date)
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let cutoff = preferences
+            .wall_clock(&end_of_day)?
+            .with_context(|| format!("Invalid date '{spec}'"))?
+            .timestamp();
+        for oid in walk_head(repo)? {
+            let commit = repo.find_commit(oid)?;
+            if commit.time().seconds() < cutoff {
+                return Ok(commit);
+            }
+        }
+        bail!("No commits found on or before {}.", date);
+    }
+    repo.revparse_single(spec)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| anyhow!("Unknown revision '{}'.", spec))
+}
+
+/// Returns the blob for `path` in a commit's tree, or `None` if it doesn't exist there.
+pub fn blob_at(commit: &Commit, path: &Path) -> Result<Option<Oid>> {
+    match commit.tree()?.get_path(path) {
+        Ok(entry) => Ok(Some(entry.id())),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Lists the commits reachable from `HEAD` that changed the jot at `path`, newest first.
+pub fn jot_history(repo: &Repository, path: &Path) -> Result<Vec<JotRevision>> {
+    let mut revisions = Vec::new();
+    for oid in walk_head(repo)? {
+        let commit = repo.find_commit(oid)?;
+        let current = blob_at(&commit, path)?;
+        let previous = match commit.parent(0) {
+            Ok(parent) => blob_at(&parent, path)?,
+            Err(_) => None,
+        };
+        let change = match (previous, current) {
+            (None, Some(_)) => JotChange::Added,
+            (Some(_), None) => JotChange::Deleted,
+            (Some(old), Some(new)) if old != new => JotChange::Modified,
+            _ => continue,
+        };
+        revisions.push(JotRevision {
+            id: oid,
+            time: Local
+                .timestamp_opt(commit.time().seconds(), 0)
+                .single()
+                .unwrap_or_default(),
+            summary: commit.summary().unwrap_or_default().to_string(),
+            change,
+        });
+    }
+    Ok(revisions)
+}
+
+/// Searches the history for a jot in `notebook_path` whose ID starts with `prefix`.
+///
+/// This finds jots that have since been deleted. Returns `None` if no commit ever
+/// contained a match, and an error if the prefix matches more than one jot.
+pub fn find_jot_in_history(
+    repo: &Repository,
+    notebook_path: &Path,
+    prefix: &str,
+) -> Result<Option<PathBuf>> {
+    let mut matches = BTreeSet::new();
+    for oid in walk_head(repo)? {
+        let tree = repo.find_commit(oid)?.tree()?;
+        let Ok(entry) = tree.get_path(notebook_path) else {
+            continue;
+        };
+        let Ok(notebook_tree) = repo.find_tree(entry.id()) else {
+            continue;
+        };
+        for jot in notebook_tree.iter() {
+            let name = jot.name().unwrap_or_default();
+            if name.starts_with(prefix) && name.ends_with(".md") {
+                matches.insert(name.to_string());
+            }
+        }
+    }
+    if matches.len() > 1 {
+        bail!(
+            "Prefix '{}' is not unique. Multiple jots found in history:\n{}",
+            prefix,
+            matches.into_iter().collect::<Vec<_>>().join("\n")
+        );
+    }
+    Ok(matches
+        .into_iter()
+        .next()
+        .map(|name| notebook_path.join(name)))
+}
+
+/// Returns the commits reachable from `HEAD`, newest first.
+fn walk_head(repo: &Repository) -> Result<Vec<Oid>> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+    if let Err(e) = revwalk.push_head() {
+        if e.code() == git2::ErrorCode::UnbornBranch {
+            return Ok(Vec::new());
+        }
+        return Err(e.into());
+    }
+    Ok(revwalk.collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Gets the working directory of the repository.
+fn workdir(repo: &Repository) -> Result<PathBuf> {
+    repo.workdir()
+        .map(Path::to_path_buf)
+        .with_context(|| "The rjot Git repository has no working directory.")
+}
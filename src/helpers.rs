@@ -3,16 +3,23 @@
 //! It handles tasks like file system interactions, configuration management, note parsing,
 //! and encryption/decryption logic, centralizing common functionality.
 
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
-use std::io::{Read, Write};
+use std::io::{IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, PoisonError};
 
 use age::{
+    secrecy::SecretString,
     x25519::{Identity, Recipient},
     Encryptor,
 };
 use anyhow::{anyhow, bail, Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use which::which;
 
@@ -58,13 +65,515 @@ pub struct Note {
     pub tasks: Vec<Task>,
 }
 
-/// Represents the `config.toml` file used for encryption settings.
+/// Represents the global `config.toml` file in the rjot root directory.
 #[derive(Serialize, Deserialize, Debug, Default)]
-struct Config {
+pub struct Config {
     /// The public key (`age` recipient) used for encrypting notes.
-    recipient: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    /// The notebook chosen with `rjot notebook use`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_notebook: Option<String>,
+    /// Create unknown notebooks on first use instead of reporting an error.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub auto_create_notebooks: bool,
+    /// User preferences such as the editor and date formats.
+    #[serde(flatten)]
+    pub preferences: Preferences,
+    /// Settings for the Git integration.
+    #[serde(default, skip_serializing_if = "GitConfig::is_default")]
+    pub git: GitConfig,
+    /// Shortcuts defined with `alias` in the interactive shell.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// User preferences, which can be set at several levels.
+///
+/// Each level overrides the ones before it: the system-wide config file, the user's
+/// `config.toml`, the active notebook's `notebook.toml`, and `RJOT_*` environment
+/// variables. Command-line flags such as `--no-color` and `list <COUNT>` win over all of them.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Preferences {
+    /// The editor command, including any arguments (e.g., `code --wait`).
+    /// Defaults to `$EDITOR`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub editor: Option<String>,
+    /// The number of jots `rjot list` shows when no count is given. Defaults to 10.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_count: Option<usize>,
+    /// The `strftime` format for dates. Defaults to `%Y-%m-%d`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// The `strftime` format for times. Defaults to `%H:%M`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_format: Option<String>,
+    /// Whether to use color. Defaults to color only when writing to a terminal.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<bool>,
+    /// The notebook used when no other notebook is selected. Defaults to `default`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_notebook: Option<String>,
+    /// An IANA time zone (e.g., `Europe/Berlin`) for jot IDs and dates.
+    /// Defaults to the system time zone.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    /// How many commands the interactive shell remembers. Defaults to 1000.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_size: Option<usize>,
+    /// Which commands the shell's history recalls: `all`, or only those run in the
+    /// active `notebook`. Defaults to `all`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub history_scope: Option<String>,
+    /// The shell prompt, with `{notebook}`, `{pending}` (incomplete tasks), and `{today}`
+    /// (jots created today) filled in. Defaults to `rjot({notebook})> `.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// The comma-separated columns of jot listings, chosen from [`LIST_COLUMNS`].
+    /// Defaults to `pinned,id,age,tasks,title,tags`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub list_columns: Option<String>,
+}
+
+/// The names of all settings in [`Preferences`], as used by `rjot config`.
+pub const PREFERENCE_KEYS: [&str; 11] = [
+    "editor",
+    "list_count",
+    "date_format",
+    "time_format",
+    "color",
+    "default_notebook",
+    "timezone",
+    "history_size",
+    "history_scope",
+    "prompt",
+    "list_columns",
+];
+
+/// Describes the value a setting has when no level sets it.
+pub fn preference_default(key: &str) -> &'static str {
+    match key {
+        "editor" => "$EDITOR",
+        "list_count" => "10",
+        "date_format" => DEFAULT_DATE_FORMAT,
+        "time_format" => DEFAULT_TIME_FORMAT,
+        "color" => "auto",
+        "default_notebook" => "default",
+        "timezone" => "local",
+        "history_size" => "1000",
+        "history_scope" => "all",
+        "prompt" => "rjot({notebook})> ",
+        "list_columns" => DEFAULT_LIST_COLUMNS,
+        _ => "",
+    }
+}
+
+/// The placeholders the `prompt` setting may use.
+pub const PROMPT_PLACEHOLDERS: [&str; 3] = ["notebook", "pending", "today"];
+
+/// The columns jot listings can show, as named by the `list_columns` setting and `--columns`.
+pub const LIST_COLUMNS: [&str; 9] = [
+    "pinned", "id", "date", "age", "title", "tags", "tasks", "notebook", "words",
+];
+
+const DEFAULT_LIST_COLUMNS: &str = "pinned,id,age,tasks,title,tags";
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+
+impl Preferences {
+    /// Overrides these preferences with every value that is set in `other`.
+    fn merge(&mut self, other: Preferences) {
+        let Preferences {
+            editor,
+            list_count,
+            date_format,
+            time_format,
+            color,
+            default_notebook,
+            timezone,
+            history_size,
+            history_scope,
+            prompt,
+            list_columns,
+        } = other;
+        self.editor = editor.or(self.editor.take());
+        self.list_count = list_count.or(self.list_count);
+        self.date_format = date_format.or(self.date_format.take());
+        self.time_format = time_format.or(self.time_format.take());
+        self.color = color.or(self.color);
+        self.default_notebook = default_notebook.or(self.default_notebook.take());
+        self.timezone = timezone.or(self.timezone.take());
+        self.history_size = history_size.or(self.history_size);
+        self.history_scope = history_scope.or(self.history_scope.take());
+        self.prompt = prompt.or(self.prompt.take());
+        self.list_columns = list_columns.or(self.list_columns.take());
+    }
+
+    /// Returns a setting's value as text, or `None` if it isn't set.
+    pub fn get(&self, key: &str) -> Result<Option<String>> {
+        Ok(match key {
+            "editor" => self.editor.clone(),
+            "list_count" => self.list_count.map(|count| count.to_string()),
+            "date_format" => self.date_format.clone(),
+            "time_format" => self.time_format.clone(),
+            "color" => self.color.map(|color| color.to_string()),
+            "default_notebook" => self.default_notebook.clone(),
+            "timezone" => self.timezone.clone(),
+            "history_size" => self.history_size.map(|size| size.to_string()),
+            "history_scope" => self.history_scope.clone(),
+            "prompt" => self.prompt.clone(),
+            "list_columns" => self.list_columns.clone(),
+            _ => bail!(unknown_preference(key)),
+        })
+    }
+
+    /// Validates and sets a setting from text, or clears it if `value` is `None`.
+    pub fn set(&mut self, key: &str, value: Option<&str>) -> Result<()> {
+        let Some(value) = value else {
+            match key {
+                "editor" => self.editor = None,
+                "list_count" => self.list_count = None,
+                "date_format" => self.date_format = None,
+                "time_format" => self.time_format = None,
+                "color" => self.color = None,
+                "default_notebook" => self.default_notebook = None,
+                "timezone" => self.timezone = None,
+                "history_size" => self.history_size = None,
+                "history_scope" => self.history_scope = None,
+                "prompt" => self.prompt = None,
+                "list_columns" => self.list_columns = None,
+                _ => bail!(unknown_preference(key)),
+            }
+            return Ok(());
+        };
+        match key {
+            "editor" => {
+                if value.trim().is_empty() {
+                    bail!("The editor command can't be empty.");
+                }
+                self.editor = Some(value.to_string());
+            }
+            "list_count" => {
+                let count: usize =
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|count| *count > 0)
+                        .with_context(|| {
+                            format!("Invalid list_count '{value}'. Use a number above 0.")
+                        })?;
+                self.list_count = Some(count);
+            }
+            "date_format" | "time_format" => {
+                if StrftimeItems::new(value).any(|item| item == Item::Error) {
+                    bail!("Invalid {key} '{value}'. Use strftime syntax, like '%Y-%m-%d'.");
+                }
+                if key == "date_format" {
+                    self.date_format = Some(value.to_string());
+                } else {
+                    self.time_format = Some(value.to_string());
+                }
+            }
+            "color" => {
+                self.color = Some(match value.to_lowercase().as_str() {
+                    "true" | "on" | "yes" | "always" => true,
+                    "false" | "off" | "no" | "never" => false,
+                    _ => bail!("Invalid color '{value}'. Use 'true' or 'false'."),
+                });
+            }
+            "default_notebook" => {
+                validate_notebook_name(value)?;
+                self.default_notebook = Some(value.to_string());
+            }
+            "timezone" => {
+                parse_timezone(value)?;
+                self.timezone = Some(value.to_string());
+            }
+            "history_size" => {
+                let size: usize = value.parse().with_context(|| {
+                    format!(
+                        "Invalid history_size '{value}'. Use a number, or 0 to keep no history."
+                    )
+                })?;
+                self.history_size = Some(size);
+            }
+            "history_scope" => {
+                if !matches!(value, "all" | "notebook") {
+                    bail!("Invalid history_scope '{value}'. Use 'all' or 'notebook'.");
+                }
+                self.history_scope = Some(value.to_string());
+            }
+            "prompt" => {
+                for placeholder in value.split('{').skip(1) {
+                    let name = placeholder.split('}').next().unwrap_or_default();
+                    if !PROMPT_PLACEHOLDERS.contains(&name) {
+                        bail!(
+                            "Unknown placeholder '{{{name}}}' in prompt. Use {}.",
+                            PROMPT_PLACEHOLDERS.map(|p| format!("{{{p}}}")).join(", ")
+                        );
+                    }
+                }
+                self.prompt = Some(value.to_string());
+            }
+            "list_columns" => {
+                let columns = parse_list_columns(value)?;
+                self.list_columns = Some(columns.join(","));
+            }
+            _ => bail!(unknown_preference(key)),
+        }
+        Ok(())
+    }
+
+    /// Reads preferences from `RJOT_*` environment variables, plus `NO_COLOR`.
+    fn from_env() -> Result<Preferences> {
+        let mut preferences = Preferences::default();
+        for key in PREFERENCE_KEYS {
+            let var = format!("RJOT_{}", key.to_uppercase());
+            if let Ok(value) = env::var(&var) {
+                if !value.is_empty() {
+                    preferences
+                        .set(key, Some(&value))
+                        .with_context(|| format!("Invalid value in ${var}"))?;
+                }
+            }
+        }
+        // See https://no-color.org. An explicit `RJOT_COLOR` still wins.
+        if preferences.color.is_none() && env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            preferences.color = Some(false);
+        }
+        Ok(preferences)
+    }
+
+    /// The number of jots to list when no count is given.
+    pub fn list_count(&self) -> usize {
+        self.list_count.unwrap_or(10)
+    }
+
+    /// The `strftime` format for dates.
+    pub fn date_format(&self) -> &str {
+        self.date_format.as_deref().unwrap_or(DEFAULT_DATE_FORMAT)
+    }
+
+    /// The `strftime` format for a date followed by a time.
+    pub fn datetime_format(&self) -> String {
+        format!(
+            "{} {}",
+            self.date_format(),
+            self.time_format.as_deref().unwrap_or(DEFAULT_TIME_FORMAT)
+        )
+    }
+
+    /// Whether output should be colored.
+    pub fn color(&self) -> bool {
+        self.color
+            .unwrap_or_else(|| std::io::stdout().is_terminal())
+    }
+
+    /// How many commands the interactive shell remembers.
+    pub fn history_size(&self) -> usize {
+        self.history_size.unwrap_or(1000)
+    }
+
+    /// The shell prompt template, if one is set.
+    pub fn prompt(&self) -> Option<&str> {
+        self.prompt.as_deref()
+    }
+
+    /// Whether the shell's history only recalls commands run in the active notebook.
+    pub fn history_per_notebook(&self) -> bool {
+        self.history_scope.as_deref() == Some("notebook")
+    }
+
+    /// The columns jot listings show, in order.
+    pub fn list_columns(&self) -> Vec<&str> {
+        self.list_columns
+            .as_deref()
+            .unwrap_or(DEFAULT_LIST_COLUMNS)
+            .split(',')
+            .collect()
+    }
+
+    /// The notebook to use when no other notebook is selected.
+    pub fn default_notebook(&self) -> &str {
+        self.default_notebook.as_deref().unwrap_or("default")
+    }
+
+    /// Converts a point in time to the configured time zone.
+    pub fn local_time<T: TimeZone>(&self, time: &DateTime<T>) -> Result<DateTime<FixedOffset>> {
+        Ok(match &self.timezone {
+            Some(name) => time.with_timezone(&parse_timezone(name)?).fixed_offset(),
+            None => time.with_timezone(&Local).fixed_offset(),
+        })
+    }
+
+    /// The current time in the configured time zone.
+    pub fn now(&self) -> Result<DateTime<FixedOffset>> {
+        self.local_time(&Utc::now())
+    }
+
+    /// Reads a wall-clock time in the configured time zone, taking the earlier time if
+    /// a clock change makes it ambiguous. Returns `None` for times a clock change skips.
+    pub fn wall_clock(&self, time: &NaiveDateTime) -> Result<Option<DateTime<FixedOffset>>> {
+        Ok(match &self.timezone {
+            Some(name) => parse_timezone(name)?
+                .from_local_datetime(time)
+                .earliest()
+                .map(|time| time.fixed_offset()),
+            None => Local
+                .from_local_datetime(time)
+                .earliest()
+                .map(|time| time.fixed_offset()),
+        })
+    }
+}
+
+/// Builds the error for a setting name that doesn't exist.
+fn unknown_preference(key: &str) -> anyhow::Error {
+    anyhow!(
+        "Unknown setting '{key}'. Available settings: {}.",
+        PREFERENCE_KEYS.join(", ")
+    )
+}
+
+/// Splits a comma-separated list of column names, checking each against [`LIST_COLUMNS`].
+pub fn parse_list_columns(value: &str) -> Result<Vec<&str>> {
+    let columns: Vec<&str> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|column| !column.is_empty())
+        .collect();
+    if columns.is_empty() {
+        bail!(
+            "Choose at least one column from {}.",
+            LIST_COLUMNS.join(", ")
+        );
+    }
+    for column in &columns {
+        if !LIST_COLUMNS.contains(column) {
+            bail!(
+                "Unknown column '{column}'. Available columns: {}.",
+                LIST_COLUMNS.join(", ")
+            );
+        }
+    }
+    Ok(columns)
+}
+
+/// Parses an IANA time zone name such as `America/New_York`.
+fn parse_timezone(name: &str) -> Result<Tz> {
+    name.parse::<Tz>().map_err(|_| {
+        anyhow!("Unknown time zone '{name}'. Use an IANA name like 'Europe/Berlin' or 'UTC'.")
+    })
+}
+
+/// Represents the `[git]` section of `config.toml`.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Commit after every command that changes a jot.
+    pub auto_commit: bool,
+    /// Push after every automatic commit. Has no effect without `auto_commit`.
+    pub auto_push: bool,
+    /// The remote to sync with. Defaults to `origin`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remote: Option<String>,
+    /// The remote branch to sync with. Defaults to the checked-out branch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// The commit author's name. Defaults to Git's `user.name`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The commit author's email. Defaults to Git's `user.email`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// The message template for `rjot sync` commits. Supports `{date}`, `{summary}`,
+    /// and `{jots}` placeholders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_message: Option<String>,
+    /// SSH private keys to try, in order. Defaults to the usual keys in `~/.ssh`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ssh_keys: Vec<PathBuf>,
+}
+
+impl GitConfig {
+    fn is_default(&self) -> bool {
+        self == &GitConfig::default()
+    }
+
+    /// Returns the name of the remote to sync with.
+    pub fn remote(&self) -> &str {
+        self.remote.as_deref().unwrap_or("origin")
+    }
+}
+
+/// Represents the `notebook.toml` file stored inside a notebook directory.
+///
+/// Settings here apply to a single notebook and take precedence over the
+/// global `config.toml`.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct NotebookConfig {
+    /// A friendlier name shown next to the directory name in `notebook list`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
+    /// A short description shown in `notebook list`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The template `rjot new` uses when `--template` is not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_template: Option<String>,
+
+    /// Tags added to every new jot in this notebook.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_tags: Vec<String>,
+
+    /// The encryption policy for this notebook. `Some(true)` always encrypts,
+    /// `Some(false)` always writes plaintext, and `None` follows the global setting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypt: Option<bool>,
+
+    /// The notebook's own `age` recipient. Falls back to the global recipient if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+
+    /// Archived notebooks are hidden from `notebook list`, `find --all`, and `info --stats --all`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+
+    /// Preferences that override the user's `config.toml` inside this notebook.
+    #[serde(flatten)]
+    pub preferences: Preferences,
 }
 
+/// Where the name of the active notebook came from.
+#[derive(Debug, PartialEq, Eq)]
+pub enum NotebookSource {
+    /// The global `--notebook` flag.
+    Flag,
+    /// The `RJOT_ACTIVE_NOTEBOOK` environment variable.
+    Env,
+    /// A `.rjot-notebook` file in the current directory or one of its parents.
+    Directory(PathBuf),
+    /// The `active_notebook` setting saved by `rjot notebook use`.
+    Config,
+    /// Nothing was set, so the `default` notebook is used.
+    Default,
+}
+
+/// The name of the file that binds a directory tree to a notebook.
+pub const NOTEBOOK_BINDING_FILE: &str = ".rjot-notebook";
+
+/// The name of the per-notebook configuration file.
+pub const NOTEBOOK_CONFIG_FILE: &str = "notebook.toml";
+
+/// The name of the file holding an `age` identity, both at the root and inside notebooks.
+pub const IDENTITY_FILE: &str = "identity.txt";
+
+/// The name of the file in the rjot directory that keeps the shell's command history.
+pub const HISTORY_FILE: &str = "shell_history.jsonl";
+
 // --- Path and Editor Helpers ---
 
 /// Gets the root directory for all `rjot` data, creating it if it doesn't exist.
@@ -123,28 +632,85 @@ pub fn get_notebooks_dir() -> Result<PathBuf> {
     Ok(notebooks_dir)
 }
 
-/// Gets the `entries` directory for the currently active notebook.
+/// Resolves the name of the active notebook and where it came from.
 ///
-/// This is the core of the multi-notebook feature. It resolves the path based on this priority:
+/// This is the core of the multi-notebook feature. The name is chosen by this priority:
 /// 1. The `--notebook` command-line flag (passed in as `notebook_override`).
 /// 2. The `RJOT_ACTIVE_NOTEBOOK` environment variable.
-/// 3. The "default" notebook if neither is set.
+/// 3. A `.rjot-notebook` file in the current directory or the nearest parent that has one.
+/// 4. The `active_notebook` saved in `config.toml` by `rjot notebook use`.
+/// 5. The `default_notebook` preference, or "default" if none of these are set.
+pub fn resolve_active_notebook(
+    notebook_override: Option<String>,
+) -> Result<(String, NotebookSource)> {
+    if let Some(name) = notebook_override {
+        return Ok((name, NotebookSource::Flag));
+    }
+    if let Ok(name) = env::var("RJOT_ACTIVE_NOTEBOOK") {
+        return Ok((name, NotebookSource::Env));
+    }
+    if let Some((name, path)) = find_notebook_binding()? {
+        return Ok((name, NotebookSource::Directory(path)));
+    }
+    if let Some(name) = load_config()?.active_notebook {
+        return Ok((name, NotebookSource::Config));
+    }
+    let default_notebook = load_preferences(None)?.default_notebook().to_string();
+    Ok((default_notebook, NotebookSource::Default))
+}
+
+/// Returns the name of the active notebook, ignoring any `--notebook` flag.
+pub fn active_notebook_name() -> Result<String> {
+    Ok(resolve_active_notebook(None)?.0)
+}
+
+/// Looks for a `.rjot-notebook` file by walking up from the current directory.
 ///
-/// It will automatically create the notebook directory if it doesn't exist.
+/// Returns the notebook name on the file's first line and the file's path.
+fn find_notebook_binding() -> Result<Option<(String, PathBuf)>> {
+    let Ok(current_dir) = env::current_dir() else {
+        return Ok(None);
+    };
+    for dir in current_dir.ancestors() {
+        let binding_path = dir.join(NOTEBOOK_BINDING_FILE);
+        if binding_path.is_file() {
+            let content = fs::read_to_string(&binding_path)
+                .with_context(|| format!("Failed to read {binding_path:?}"))?;
+            let name = content.lines().next().unwrap_or("").trim();
+            if name.is_empty() {
+                bail!("{:?} does not name a notebook.", binding_path);
+            }
+            return Ok(Some((name.to_string(), binding_path)));
+        }
+    }
+    Ok(None)
+}
+
+/// Gets the `entries` directory for the currently active notebook.
+///
+/// The notebook is chosen by [`resolve_active_notebook`]. A missing notebook is an error,
+/// except for `default` or when `auto_create_notebooks` is enabled in `config.toml`.
 pub fn get_active_entries_dir(notebook_override: Option<String>) -> Result<PathBuf> {
     let notebooks_root = get_notebooks_dir()?;
+    let (notebook_name, source) = resolve_active_notebook(notebook_override)?;
+    if let Some(entries_dir) = existing_notebook_path(&notebook_name)? {
+        return Ok(entries_dir);
+    }
+    validate_notebook_name(&notebook_name).map_err(|e| match &source {
+        NotebookSource::Directory(path) => e.context(format!("Check the name in {path:?}")),
+        _ => e,
+    })?;
 
-    let notebook_name = if let Some(name) = notebook_override {
-        name
-    } else if let Ok(name) = env::var("RJOT_ACTIVE_NOTEBOOK") {
-        name
-    } else {
-        "default".to_string()
-    };
-
-    let entries_dir = notebooks_root.join(notebook_name);
-    if !entries_dir.exists() {
-        fs::create_dir_all(&entries_dir)?;
+    let entries_dir = notebooks_root.join(&notebook_name);
+    if !entries_dir.is_dir() {
+        if notebook_name == "default" {
+            fs::create_dir_all(&entries_dir)?;
+        } else if load_config()?.auto_create_notebooks {
+            fs::create_dir_all(&entries_dir)?;
+            eprintln!("Created new notebook '{notebook_name}'.");
+        } else {
+            return Err(notebook_not_found(&notebook_name));
+        }
     }
     Ok(entries_dir)
 }
@@ -159,18 +725,28 @@ pub fn get_templates_dir() -> Result<PathBuf> {
     Ok(templates_dir)
 }
 
-/// Determines which command-line editor to use.
+/// Determines which command-line editor to use, as a program followed by its arguments.
 ///
-/// It prioritizes the `$EDITOR` environment variable, then falls back to a list
-/// of common editors (`vim`, `nvim`, `nano`, `notepad.exe`).
+/// It prioritizes the `editor` preference, then the `$EDITOR` environment variable, either
+/// of which may quote a program path or argument that has spaces. Then it falls back to a
+/// list of common editors (`vim`, `nvim`, `nano`, `notepad.exe`).
 ///
 /// # Errors
 /// Returns an error if no suitable editor can be found.
-pub fn get_editor() -> Result<String> {
-    if let Ok(editor) = env::var("EDITOR") {
-        if !editor.is_empty() {
-            return Ok(editor);
+pub fn get_editor(preferences: &Preferences) -> Result<Vec<String>> {
+    let configured = preferences
+        .editor
+        .clone()
+        .or_else(|| env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty());
+    if let Some(editor) = configured {
+        // A path with spaces, like `C:\\Program Files\\...`, is one program. Anything else is
+        // split like a shell line, so quoted arguments and paths stay whole.
+        if Path::new(editor.trim()).is_file() {
+            return Ok(vec![editor.trim().to_string()]);
         }
+        return crate::shell::split_words(&editor)
+            .with_context(|| format!("Invalid editor command '{editor}'"));
     }
     #[cfg(unix)]
     let fallbacks = ["vim", "nvim", "nano"];
@@ -181,37 +757,388 @@ pub fn get_editor() -> Result<String> {
 
     for editor in fallbacks {
         if which(editor).is_ok() {
-            return Ok(editor.to_string());
+            return Ok(vec![editor.to_string()]);
         }
     }
     bail!("Could not find a default editor. Please set the $EDITOR environment variable.")
 }
 
-// --- Core File I/O Logic ---
+/// Opens a file in the editor returned by [`get_editor`] and waits for it to exit.
+pub fn open_in_editor(editor: &[String], path: &Path) -> Result<()> {
+    let status = Command::new(&editor[0])
+        .args(&editor[1..])
+        .arg(path)
+        .status()
+        .with_context(|| format!("Failed to start the editor '{}'", editor[0]))?;
+    if !status.success() {
+        bail!("Editor exited with a non-zero status.");
+    }
+    Ok(())
+}
 
-/// Writes content to a note file, encrypting it if encryption is enabled.
-pub fn write_note_file(path: &Path, content: &str) -> Result<()> {
-    let root_dir = get_rjot_dir_root()?;
+/// Copies text to the system clipboard with the first clipboard tool found on `PATH`.
+/// Returns `false` if there is no clipboard tool.
+pub fn copy_to_clipboard(text: &str) -> Result<bool> {
+    let tools: [(&str, &[&str]); 5] = [
+        ("pbcopy", &[]),
+        ("wl-copy", &[]),
+        ("xclip", &["-selection", "clipboard"]),
+        ("xsel", &["--clipboard", "--input"]),
+        ("clip.exe", &[]),
+    ];
+    let Some((tool, args)) = tools.into_iter().find(|(tool, _)| which(tool).is_ok()) else {
+        return Ok(false);
+    };
+    let mut child = Command::new(tool)
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start '{tool}'"))?;
+    child.stdin.take().unwrap().write_all(text.as_bytes())?;
+    if !child.wait()?.success() {
+        bail!("'{tool}' exited with a non-zero status.");
+    }
+    Ok(true)
+}
+
+// --- Configuration Helpers ---
+
+/// Reads the global `config.toml`, returning defaults if it doesn't exist.
+pub fn load_config() -> Result<Config> {
+    load_config_in(&get_rjot_dir_root()?)
+}
+
+/// Loads the `config.toml` of the given rjot directory.
+pub fn load_config_in(root_dir: &Path) -> Result<Config> {
     let config_path = root_dir.join("config.toml");
-    let config: Config = if config_path.exists() {
-        toml::from_str(&fs::read_to_string(config_path)?)?
+    if config_path.exists() {
+        toml::from_str(&fs::read_to_string(&config_path)?)
+            .with_context(|| format!("Failed to parse {config_path:?}"))
     } else {
-        Config::default()
-    };
+        Ok(Config::default())
+    }
+}
 
-    if let Some(recipient_str) = config.recipient {
-        let recipient: Recipient = recipient_str
-            .parse()
-            .map_err(|e| anyhow!("Failed to parse recipient from config: {}", e))?;
-        let encrypted_bytes = {
-            let encryptor = Encryptor::with_recipients(vec![Box::new(recipient)]);
-            let mut encrypted = vec![];
-            let mut writer = encryptor.expect("REASON").wrap_output(&mut encrypted)?;
-            writer.write_all(content.as_bytes())?;
-            writer.finish()?;
-            encrypted
-        };
-        fs::write(path, encrypted_bytes)?;
+/// Writes the global `config.toml`, removing the file if no settings remain.
+pub fn save_config(config: &Config) -> Result<()> {
+    let config_path = get_rjot_dir_root()?.join("config.toml");
+    let config_str = toml::to_string(config)?;
+    if config_str.trim().is_empty() {
+        if config_path.exists() {
+            fs::remove_file(config_path)?;
+        }
+    } else {
+        fs::write(config_path, config_str)?;
+    }
+    Ok(())
+}
+
+/// Returns the path of the system-wide config file.
+///
+/// This is `/etc/rjot/config.toml` (or `%ProgramData%\\rjot\\config.toml` on Windows),
+/// unless `RJOT_SYSTEM_CONFIG` points elsewhere.
+pub fn system_config_path() -> PathBuf {
+    if let Ok(path) = env::var("RJOT_SYSTEM_CONFIG") {
+        return PathBuf::from(path);
+    }
+    #[cfg(windows)]
+    let dir = PathBuf::from(env::var("ProgramData").unwrap_or_else(|_| "C:\\ProgramData".into()));
+    #[cfg(not(windows))]
+    let dir = PathBuf::from("/etc");
+    dir.join("rjot").join("config.toml")
+}
+
+/// Reads the preferences from the system-wide config file, if there is one.
+pub fn load_system_preferences() -> Result<Preferences> {
+    let config_path = system_config_path();
+    if config_path.exists() {
+        toml::from_str(&fs::read_to_string(&config_path)?)
+            .with_context(|| format!("Failed to parse {config_path:?}"))
+    } else {
+        Ok(Preferences::default())
+    }
+}
+
+/// Writes preferences to the system-wide config file.
+pub fn save_system_preferences(preferences: &Preferences) -> Result<()> {
+    let config_path = system_config_path();
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&config_path, toml::to_string(preferences)?)
+        .with_context(|| format!("Failed to write {config_path:?}"))
+}
+
+/// Returns each layer of preferences, from lowest to highest precedence, with its name.
+///
+/// The notebook layer is only included if `notebook_dir` is given, and the flags layer
+/// only while [`DisplayFlags`] are applied.
+pub fn preference_layers(notebook_dir: Option<&Path>) -> Result<Vec<(&'static str, Preferences)>> {
+    let mut layers = vec![
+        ("system", load_system_preferences()?),
+        ("user", load_config()?.preferences),
+    ];
+    if let Some(notebook_dir) = notebook_dir {
+        let mut notebook_preferences = load_notebook_config(notebook_dir)?.preferences;
+        // The default notebook can't depend on the notebook that was chosen.
+        notebook_preferences.default_notebook = None;
+        layers.push(("notebook", notebook_preferences));
+    }
+    layers.push(("env", Preferences::from_env()?));
+    let flags = FLAG_PREFERENCES
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone();
+    if let Some(flags) = flags {
+        layers.push(("flags", flags));
+    }
+    Ok(layers)
+}
+
+/// The preferences set by command-line flags for the command that is running.
+static FLAG_PREFERENCES: Mutex<Option<Preferences>> = Mutex::new(None);
+
+/// The global `--no-color` and `--columns` flags, applied for the length of one command.
+///
+/// They form the `flags` layer of [`preference_layers`], above the environment. Dropping
+/// this clears the layer again, which lets the shell apply flags to a single line.
+pub struct DisplayFlags;
+
+impl DisplayFlags {
+    /// Sets the flags layer from the given flags, after checking the column names.
+    pub fn apply(no_color: bool, columns: Option<&[String]>) -> Result<DisplayFlags> {
+        let mut preferences = Preferences::default();
+        if no_color {
+            preferences.color = Some(false);
+        }
+        if let Some(columns) = columns {
+            preferences.set("list_columns", Some(&columns.join(",")))?;
+        }
+        *FLAG_PREFERENCES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(preferences);
+        Ok(DisplayFlags)
+    }
+}
+
+impl Drop for DisplayFlags {
+    fn drop(&mut self) {
+        *FLAG_PREFERENCES
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+/// Loads the effective preferences by merging every layer from [`preference_layers`].
+pub fn load_preferences(notebook_dir: Option<&Path>) -> Result<Preferences> {
+    let mut preferences = Preferences::default();
+    for (_, layer) in preference_layers(notebook_dir)? {
+        preferences.merge(layer);
+    }
+    Ok(preferences)
+}
+
+/// Removes ANSI color codes from text.
+pub fn strip_ansi(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip everything up to and including the final letter of the sequence.
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// Finds the `#tags` written inline in a jot, in order of first appearance.
+///
+/// A tag starts with a letter and may contain letters, digits, `-`, `_`, and `/`. It must
+/// follow whitespace or start a line, so Markdown headings, `#123` issue numbers, and URL
+/// fragments are left alone, as is anything inside a fenced code block.
+pub fn extract_hashtags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let mut in_code_block = false;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        for word in line.split_whitespace() {
+            let Some(rest) = word.strip_prefix('#') else {
+                continue;
+            };
+            let tag: String = rest
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/'))
+                .collect();
+            let tag = tag.trim_end_matches(['-', '_', '/']);
+            if tag.starts_with(char::is_alphabetic) && !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+    }
+    tags
+}
+
+/// Reads a notebook's `notebook.toml`, returning defaults if it doesn't exist.
+pub fn load_notebook_config(notebook_dir: &Path) -> Result<NotebookConfig> {
+    let config_path = notebook_dir.join(NOTEBOOK_CONFIG_FILE);
+    if config_path.exists() {
+        toml::from_str(&fs::read_to_string(&config_path)?)
+            .with_context(|| format!("Failed to parse {config_path:?}"))
+    } else {
+        Ok(NotebookConfig::default())
+    }
+}
+
+/// Writes a notebook's `notebook.toml`.
+pub fn save_notebook_config(notebook_dir: &Path, config: &NotebookConfig) -> Result<()> {
+    fs::write(
+        notebook_dir.join(NOTEBOOK_CONFIG_FILE),
+        toml::to_string_pretty(config)?,
+    )?;
+    Ok(())
+}
+
+/// Resolves the `age` recipient a note should be encrypted for, if any.
+///
+/// The notebook's own policy wins: an explicit `encrypt = false` forces plaintext,
+/// and `encrypt = true` uses the notebook's recipient (or the global one). Notebooks
+/// without a policy follow the global `config.toml`.
+pub fn resolve_recipient(notebook_dir: &Path) -> Result<Option<String>> {
+    let notebook_config = load_notebook_config(notebook_dir)?;
+    let global_recipient = load_config()?.recipient;
+    match notebook_config.encrypt {
+        Some(false) => Ok(None),
+        Some(true) => notebook_config
+            .recipient
+            .or(global_recipient)
+            .map(Some)
+            .with_context(|| {
+                format!("Notebook at {notebook_dir:?} requires encryption but has no recipient.")
+            }),
+        None => Ok(global_recipient),
+    }
+}
+
+/// Parses an `age` identity from a file.
+fn read_identity_file(identity_path: &Path) -> Result<Identity> {
+    fs::read_to_string(identity_path)?
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Failed to parse identity file {:?}.", identity_path))
+}
+
+/// Loads every identity that may decrypt notes in the given notebook.
+///
+/// The notebook's own identity is tried first, followed by the global one.
+pub fn load_identities(notebook_dir: &Path) -> Result<Vec<Box<dyn age::Identity>>> {
+    let root_dir = get_rjot_dir_root()?;
+    let mut identities: Vec<Box<dyn age::Identity>> = Vec::new();
+    for identity_path in [
+        notebook_dir.join(IDENTITY_FILE),
+        root_dir.join(IDENTITY_FILE),
+    ] {
+        if identity_path.exists() {
+            identities.push(Box::new(read_identity_file(&identity_path)?));
+        }
+    }
+    Ok(identities)
+}
+
+/// Loads every identity known to rjot: the global one and those of all notebooks.
+pub fn load_all_identities() -> Result<Vec<Box<dyn age::Identity>>> {
+    let mut identities: Vec<Box<dyn age::Identity>> = Vec::new();
+    let global_identity_path = get_rjot_dir_root()?.join(IDENTITY_FILE);
+    let notebook_identity_paths = fs::read_dir(get_notebooks_dir()?)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path().join(IDENTITY_FILE));
+    for identity_path in std::iter::once(global_identity_path).chain(notebook_identity_paths) {
+        if identity_path.exists() {
+            identities.push(Box::new(read_identity_file(&identity_path)?));
+        }
+    }
+    Ok(identities)
+}
+
+/// Reads a passphrase from the `$RJOT_PASSPHRASE` environment variable or the terminal.
+///
+/// When `confirm` is set and the passphrase is typed interactively, it must be entered twice.
+pub fn read_passphrase(confirm: bool) -> Result<SecretString> {
+    if let Ok(passphrase) = env::var("RJOT_PASSPHRASE") {
+        if !passphrase.is_empty() {
+            return Ok(SecretString::new(passphrase));
+        }
+    }
+    let passphrase = rpassword::prompt_password("Passphrase: ")?;
+    if passphrase.is_empty() {
+        bail!("Passphrase cannot be empty.");
+    }
+    if confirm && rpassword::prompt_password("Confirm passphrase: ")? != passphrase {
+        bail!("Passphrases do not match.");
+    }
+    Ok(SecretString::new(passphrase))
+}
+
+/// Returns `true` if the bytes look like an `age`-encrypted file.
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"age-encryption.org")
+}
+
+/// Encrypts bytes for a single `age` recipient given as a string.
+pub fn encrypt_for_recipient(recipient_str: &str, plaintext: &[u8]) -> Result<Vec<u8>> {
+    let recipient: Recipient = recipient_str
+        .parse()
+        .map_err(|e| anyhow!("Failed to parse recipient: {}", e))?;
+    let encryptor = Encryptor::with_recipients(vec![Box::new(recipient)])
+        .with_context(|| "Failed to create encryptor.")?;
+    let mut encrypted = vec![];
+    let mut writer = encryptor.wrap_output(&mut encrypted)?;
+    writer.write_all(plaintext)?;
+    writer.finish()?;
+    Ok(encrypted)
+}
+
+/// Decrypts recipient-encrypted bytes with any of the given identities.
+pub fn decrypt_with_identities(
+    ciphertext: &[u8],
+    identities: &[Box<dyn age::Identity>],
+) -> Result<Vec<u8>> {
+    if identities.is_empty() {
+        bail!("This file is encrypted but no identity was found to decrypt it.");
+    }
+    let decryptor = age::Decryptor::new(ciphertext)?;
+    let mut decrypted_bytes = vec![];
+    if let age::Decryptor::Recipients(reader) = decryptor {
+        reader
+            .decrypt(identities.iter().map(|i| i.as_ref()))?
+            .read_to_end(&mut decrypted_bytes)?;
+    } else {
+        bail!("Expected recipients-based encryption");
+    }
+    Ok(decrypted_bytes)
+}
+
+// --- Core File I/O Logic ---
+
+/// Writes content to a note file, encrypting it if its notebook's policy requires it.
+pub fn write_note_file(path: &Path, content: &str) -> Result<()> {
+    let notebook_dir = path.parent().unwrap_or(Path::new("."));
+    if let Some(recipient_str) = resolve_recipient(notebook_dir)? {
+        fs::write(
+            path,
+            encrypt_for_recipient(&recipient_str, content.as_bytes())?,
+        )?;
     } else {
         fs::write(path, content)?;
     }
@@ -220,33 +1147,202 @@ pub fn write_note_file(path: &Path, content: &str) -> Result<()> {
 
 /// Reads content from a note file, decrypting it if necessary.
 pub fn read_note_file(path: &Path) -> Result<String> {
-    let root_dir = get_rjot_dir_root()?;
-    let identity_path = root_dir.join("identity.txt");
     let file_bytes = fs::read(path)?;
 
-    if identity_path.exists() && file_bytes.starts_with(b"age-encryption.org") {
-        let identity_str = fs::read_to_string(identity_path)?;
-        let identity: Identity = identity_str
-            .parse()
-            .map_err(|_| anyhow!("Failed to parse identity file."))?;
-        let decryptor = age::Decryptor::new(&file_bytes as &[u8])?;
-        let mut decrypted_bytes = vec![];
-        if let age::Decryptor::Recipients(reader) = decryptor {
-            let identities: Vec<Box<dyn age::Identity>> = vec![Box::new(identity)];
-            reader
-                .decrypt(identities.iter().map(|i| i.as_ref()))?
-                .read_to_end(&mut decrypted_bytes)?;
-        } else {
-            bail!("Expected recipients-based encryption");
-        }
+    if is_encrypted(&file_bytes) {
+        let notebook_dir = path.parent().unwrap_or(Path::new("."));
+        let identities = load_identities(notebook_dir)?;
+        let decrypted_bytes = decrypt_with_identities(&file_bytes, &identities)?;
         Ok(String::from_utf8(decrypted_bytes)?)
     } else {
         Ok(String::from_utf8(file_bytes)?)
     }
 }
 
+/// Lists the note files (`*.md`) in a notebook directory.
+///
+/// Other files, such as `notebook.toml` or a notebook's `identity.txt`, are skipped.
+pub fn list_note_paths(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "md") {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// Returns a path in `dir` for a jot with `id` that doesn't overwrite an existing jot.
+///
+/// If the ID is taken, timestamp IDs are moved forward one second at a time so they
+/// keep sorting by date; other IDs get a numeric suffix.
+pub fn unique_note_path(dir: &Path, id: &str) -> PathBuf {
+    let path = dir.join(format!("{id}.md"));
+    if !path.exists() {
+        return path;
+    }
+    if let Ok(mut timestamp) = NaiveDateTime::parse_from_str(id, "%Y-%m-%d-%H%M%S") {
+        loop {
+            timestamp += chrono::Duration::seconds(1);
+            let path = dir.join(format!("{}.md", timestamp.format("%Y-%m-%d-%H%M%S")));
+            if !path.exists() {
+                return path;
+            }
+        }
+    }
+    (1..)
+        .map(|n| dir.join(format!("{id}-{n}.md")))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+/// Lists the notebook directories, sorted by name.
+///
+/// Archived notebooks are skipped unless `include_archived` is set.
+pub fn list_notebooks(include_archived: bool) -> Result<Vec<PathBuf>> {
+    list_notebooks_in(&get_notebooks_dir()?, include_archived)
+}
+
+/// Lists the notebook directories inside the given notebooks directory.
+pub fn list_notebooks_in(notebooks_dir: &Path, include_archived: bool) -> Result<Vec<PathBuf>> {
+    let mut notebooks = Vec::new();
+    for entry in fs::read_dir(notebooks_dir)?.filter_map(Result::ok) {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        if include_archived || !load_notebook_config(&path)?.archived {
+            notebooks.push(path);
+        }
+    }
+    notebooks.sort();
+    Ok(notebooks)
+}
+
+/// Checks that a notebook name is usable as a directory name.
+///
+/// Names may only contain letters, numbers, `-`, `_`, and `.`, and cannot start with a
+/// dot. This keeps every notebook inside the notebooks directory and out of hidden files.
+pub fn validate_notebook_name(name: &str) -> Result<()> {
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if name.is_empty() || name.chars().count() > 64 || name.starts_with('.') || !valid_chars {
+        bail!(
+            "Invalid notebook name: '{}'. Names may only contain letters, numbers, '-', '_', \
+             and '.', cannot start with '.', and are limited to 64 characters.",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Returns the directory of a notebook that already exists, or `None` if there is none.
+///
+/// Names are only checked with [`validate_notebook_name`] when a notebook is created, so
+/// notebooks made by hand, like `my notes`, still resolve here and can be renamed. The
+/// name must still be a single path component inside the notebooks directory.
+pub fn existing_notebook_path(name: &str) -> Result<Option<PathBuf>> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+        return Ok(None);
+    }
+    let path = get_notebooks_dir()?.join(name);
+    Ok(path.is_dir().then_some(path))
+}
+
+/// Builds the error for a notebook that doesn't exist, suggesting similar names.
+pub fn notebook_not_found(name: &str) -> anyhow::Error {
+    let max_distance = (name.chars().count() / 3).max(1);
+    let mut suggestions: Vec<(usize, String)> = list_notebooks(true)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|path| {
+            let candidate = path.file_name()?.to_string_lossy().to_string();
+            let distance = edit_distance(&name.to_lowercase(), &candidate.to_lowercase());
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .collect();
+    suggestions.sort();
+
+    let mut message = format!("Notebook '{name}' not found.");
+    if !suggestions.is_empty() {
+        let names: Vec<String> = suggestions
+            .into_iter()
+            .take(3)
+            .map(|(_, candidate)| format!("'{candidate}'"))
+            .collect();
+        message.push_str(&format!(" Did you mean {}?", names.join(" or ")));
+    }
+    message.push_str(&format!(" Create it with `rjot notebook new {name}`."));
+    anyhow!(message)
+}
+
+/// Computes the edit distance between two strings, counting a swap of two adjacent
+/// characters as a single edit so typos like "wrok" match "work".
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
 // --- Other Helpers ---
 
+/// Splits a jot's text into its parsed frontmatter and the remaining content.
+pub fn split_frontmatter(file_content: &str) -> Result<(Frontmatter, String)> {
+    if file_content.starts_with("---") {
+        if let Some(end_frontmatter) = file_content.get(3..).and_then(|s| s.find("---")) {
+            let frontmatter_str = &file_content[3..(3 + end_frontmatter)];
+            let content_part = file_content[(3 + end_frontmatter + 3)..].trim().to_string();
+            let fm: Frontmatter = serde_yaml::from_str(frontmatter_str)?;
+            return Ok((fm, content_part));
+        }
+    }
+    Ok((Frontmatter::default(), file_content.to_string()))
+}
+
+/// Joins frontmatter and content into a jot's text, leaving out empty frontmatter.
+pub fn format_note(frontmatter: &Frontmatter, content: &str) -> Result<String> {
+    if frontmatter.tags.is_empty() && !frontmatter.pinned {
+        return Ok(content.to_string());
+    }
+    let frontmatter_str = serde_yaml::to_string(frontmatter)?;
+    Ok(format!("---\n{frontmatter_str}---\n\n{content}"))
+}
+
+/// Writes a jot's frontmatter and content back to its file.
+pub fn save_note(note: &Note) -> Result<()> {
+    write_note_file(&note.path, &format_note(&note.frontmatter, &note.content)?)
+}
+
+/// Pins or unpins a jot and saves it. Returns `false`, without saving, if it already was.
+pub fn set_pinned(note: &mut Note, pin: bool) -> Result<bool> {
+    if note.frontmatter.pinned == pin {
+        return Ok(false);
+    }
+    note.frontmatter.pinned = pin;
+    save_note(note)?;
+    Ok(true)
+}
+
 /// Parses a file into a `Note` struct, separating frontmatter from content.
 pub fn parse_note_from_file(path: &Path, notebook_name: &str) -> Result<Note> {
     let filename = path.file_name().unwrap().to_string_lossy().to_string();
@@ -254,19 +1350,8 @@ pub fn parse_note_from_file(path: &Path, notebook_name: &str) -> Result<Note> {
     let file_content =
         read_note_file(path).with_context(|| format!("Could not read file: {path:?}"))?;
 
-    let (frontmatter, content_str) = if file_content.starts_with("---") {
-        if let Some(end_frontmatter) = file_content.get(3..).and_then(|s| s.find("---")) {
-            let frontmatter_str = &file_content[3..(3 + end_frontmatter)];
-            let content_part = file_content[(3 + end_frontmatter + 3)..].trim().to_string();
-            let fm: Frontmatter = serde_yaml::from_str(frontmatter_str)
-                .with_context(|| format!("Failed to parse YAML frontmatter in {path:?}"))?;
-            (fm, content_part)
-        } else {
-            (Frontmatter::default(), file_content.clone())
-        }
-    } else {
-        (Frontmatter::default(), file_content.clone())
-    };
+    let (frontmatter, content_str) = split_frontmatter(&file_content)
+        .with_context(|| format!("Failed to parse YAML frontmatter in {path:?}"))?;
 
     let mut tasks = Vec::new();
     for line in content_str.lines() {
@@ -313,35 +1398,77 @@ pub fn get_note_path_for_action(
     }
 }
 
+// --- Recently Listed and Created Jots ---
+
+/// The jots shown by the most recent listing and the jot created most recently, which
+/// the shell's `open <n>` and `last` refer back to.
+struct RecentJots {
+    listing: Vec<PathBuf>,
+    created: Option<PathBuf>,
+}
+
+static RECENT_JOTS: Mutex<RecentJots> = Mutex::new(RecentJots {
+    listing: Vec::new(),
+    created: None,
+});
+
+/// Records the jots a listing showed, in order.
+pub fn remember_listing(notes: &[Note]) {
+    let mut recent = RECENT_JOTS.lock().unwrap_or_else(PoisonError::into_inner);
+    recent.listing = notes.iter().map(|note| note.path.clone()).collect();
+}
+
+/// Records a newly created jot.
+pub fn remember_created(path: &Path) {
+    let mut recent = RECENT_JOTS.lock().unwrap_or_else(PoisonError::into_inner);
+    recent.created = Some(path.to_path_buf());
+}
+
+/// Returns the jots shown by the most recent listing.
+pub fn last_listing() -> Vec<PathBuf> {
+    let recent = RECENT_JOTS.lock().unwrap_or_else(PoisonError::into_inner);
+    recent.listing.clone()
+}
+
+/// Returns the jot created most recently in this process.
+pub fn last_created() -> Option<PathBuf> {
+    let recent = RECENT_JOTS.lock().unwrap_or_else(PoisonError::into_inner);
+    recent.created.clone()
+}
+
 /// Formats and prints a list of notes to the console.
-pub fn display_note_list(notes: Vec<Note>) {
+pub fn display_note_list(notes: Vec<Note>) -> Result<()> {
+    remember_listing(&notes);
     if notes.is_empty() {
         println!("\nNo jots found.");
-        return;
-    }
-    println!("\n{:<22} FIRST LINE OF CONTENT", "ID");
-    println!("{:-<22} {:-<50}", "", "");
-    for note in notes {
-        let first_line = note.content.lines().next().unwrap_or("").trim();
-        println!("{:<22} {}", note.id, first_line);
+        return Ok(());
     }
+    crate::render::print_note_table(&notes, None)
 }
 
 /// Formats and prints a compiled summary of notes to the console.
-pub fn compile_notes(notes: Vec<Note>) -> Result<()> {
+pub fn compile_notes(notes: Vec<Note>, raw: bool) -> Result<()> {
+    let notebook_dir = notes
+        .first()
+        .and_then(|note| note.path.parent().map(Path::to_path_buf));
+    let mut summary = String::new();
     for note in notes {
-        println!("---\n\n# {}\n\n{}", note.id, note.content);
+        summary.push_str(&format!("---\n\n# {}\n\n{}\n", note.id, note.content));
     }
-    Ok(())
+    crate::render::print_markdown(summary.trim_end(), raw, notebook_dir.as_deref())
 }
 
 /// Finds a single, unique note file based on a starting prefix of its ID.
 pub fn find_unique_note_by_prefix(entries_dir: &Path, prefix: &str) -> Result<PathBuf> {
-    let entries = fs::read_dir(entries_dir)?;
     let mut matches = Vec::new();
-    for entry in entries.filter_map(Result::ok) {
-        if entry.file_name().to_string_lossy().starts_with(prefix) {
-            matches.push(entry.path());
+    for path in list_note_paths(entries_dir)? {
+        if path
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(prefix)
+        {
+            matches.push(path);
         }
     }
     if matches.is_empty() {
@@ -380,7 +1507,7 @@ pub fn find_note_by_index_from_end(entries_dir: &Path, index: usize) -> Result<P
     if index == 0 {
         bail!("--last index must be 1 or greater.");
     }
-    let mut entries: Vec<_> = fs::read_dir(entries_dir)?.filter_map(Result::ok).collect();
+    let mut entries = list_note_paths(entries_dir)?;
     let total_jots = entries.len();
     if total_jots == 0 {
         bail!("No jots exist to act upon.");
@@ -393,10 +1520,10 @@ pub fn find_note_by_index_from_end(entries_dir: &Path, index: usize) -> Result<P
             total_jots
         );
     }
-    entries.sort_by_key(|e| e.file_name());
+    entries.sort();
     let target_index = total_jots - index;
     entries
         .get(target_index)
-        .map(|e| e.path())
+        .cloned()
         .with_context(|| "Failed to get entry at calculated index.")
 }
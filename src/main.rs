@@ -6,44 +6,103 @@
 // Declare the modules that make up the application.
 mod cli;
 mod commands;
+mod git;
 mod helpers;
+mod render;
+mod shell;
+mod template;
+mod tui;
 
 use anyhow::Result;
 use clap::Parser;
 use cli::Commands;
-use std::path::PathBuf;
 
-pub fn run_command(command: Commands, entries_dir: PathBuf) -> Result<()> {
+/// Dispatches a parsed command.
+///
+/// `notebook` is the value of the global `--notebook` flag. The active notebook is only
+/// resolved for commands that work inside one, so commands like `notebook` and `init`
+/// keep working even if the active notebook is missing.
+pub fn run_command(command: Commands, notebook: Option<String>) -> Result<()> {
     // This logic is now decoupled from where the command originates (main or shell)
     match command {
-        Commands::Task { message } => commands::command_task(&entries_dir, &message)?,
+        Commands::Notebook(args) => return commands::command_notebook(args),
+        Commands::Config(args) => return commands::command_config(args, notebook),
+        Commands::Template(args) => return commands::command_template(args),
         Commands::New {
             template,
             variables,
-        } => commands::command_new(&entries_dir, template, variables)?,
+        } => return commands::command_new(notebook, template, variables),
+        Commands::Init { git, encrypt } => return commands::command_init(git, encrypt),
+        Commands::Sync { resolve, dry_run } => return commands::command_sync(resolve, dry_run),
+        Commands::Decrypt { force } if notebook.is_none() => {
+            return commands::command_decrypt(force, None)
+        }
+        Commands::Export(args) => return commands::command_export(args),
+        Commands::Import(args) => return commands::command_import(args),
+        // The shell command is handled in main() and will not be matched here.
+        Commands::Shell => unreachable!(),
+        _ => {}
+    }
+
+    let entries_dir = helpers::get_active_entries_dir(notebook)?;
+    match command {
+        Commands::Task { message } => commands::command_task(&entries_dir, &message)?,
         Commands::List {
             count,
             pinned,
             tasks,
-        } => commands::command_list(&entries_dir, count, pinned, tasks)?,
-        Commands::Find { query, all } => commands::command_find(&entries_dir, &query, all)?,
+            tag,
+        } => commands::command_list(&entries_dir, count, pinned, tasks, &tag)?,
+        Commands::Find {
+            query,
+            all,
+            include_archived,
+        } => commands::command_find(&entries_dir, &query, all, include_archived)?,
         Commands::Tags { tags } => commands::command_tags_filter(&entries_dir, &tags)?,
         #[cfg(not(windows))]
-        Commands::Select => commands::command_select(&entries_dir)?,
-        Commands::Today { compile } => commands::command_today(&entries_dir, compile)?,
-        Commands::Yesterday { compile } => commands::command_yesterday(&entries_dir, compile)?,
-        Commands::Week { compile } => commands::command_by_week(&entries_dir, compile)?,
-        Commands::On { date_spec, compile } => {
-            commands::command_on(&entries_dir, &date_spec, compile)?
+        Commands::Select(args) => commands::command_select(&entries_dir, args)?,
+        Commands::Today { compile, raw } => commands::command_today(&entries_dir, compile, raw)?,
+        Commands::Yesterday { compile, raw } => {
+            commands::command_yesterday(&entries_dir, compile, raw)?
         }
+        Commands::Week { compile, raw } => commands::command_by_week(&entries_dir, compile, raw)?,
+        Commands::On {
+            date_spec,
+            compile,
+            raw,
+        } => commands::command_on(&entries_dir, &date_spec, compile, raw)?,
         Commands::Edit { id_prefix, last } => {
             let note_path = helpers::get_note_path_for_action(&entries_dir, id_prefix, last)?;
             commands::command_edit(note_path)?;
         }
-        Commands::Show { id_prefix, last } => {
+        Commands::Show {
+            id_prefix,
+            last,
+            at: Some(at),
+            raw,
+        } => commands::command_show_at(&entries_dir, id_prefix, last, &at, raw)?,
+        Commands::Show {
+            id_prefix,
+            last,
+            at: None,
+            raw,
+        } => {
             let note_path = helpers::get_note_path_for_action(&entries_dir, id_prefix, last)?;
-            commands::command_show(note_path)?;
+            commands::command_show(note_path, raw)?;
+        }
+        Commands::History { id_prefix, last } => {
+            commands::command_history(&entries_dir, id_prefix, last)?
         }
+        Commands::Diff {
+            id_prefix,
+            last,
+            at,
+        } => commands::command_diff(&entries_dir, id_prefix, last, at)?,
+        Commands::Restore {
+            id_prefix,
+            last,
+            at,
+        } => commands::command_restore(&entries_dir, id_prefix, last, at)?,
         Commands::Delete {
             id_prefix,
             last,
@@ -56,16 +115,15 @@ pub fn run_command(command: Commands, entries_dir: PathBuf) -> Result<()> {
         Commands::Unpin { id_prefix, last } => {
             commands::command_unpin(&entries_dir, id_prefix, last)?
         }
+        Commands::Move(args) => commands::command_transfer(&entries_dir, args, false)?,
+        Commands::Copy(args) => commands::command_transfer(&entries_dir, args, true)?,
         Commands::Info(args) => commands::command_info(&entries_dir, args)?,
         Commands::Tag(args) => commands::command_tag(&entries_dir, args)?,
-        Commands::Notebook(args) => commands::command_notebook(args)?,
-        Commands::Init { git, encrypt } => commands::command_init(git, encrypt)?,
-        Commands::Sync => commands::command_sync()?,
-        Commands::Decrypt { force } => commands::command_decrypt(force)?,
-        Commands::Export(args) => commands::command_export(args)?,
-        Commands::Import(args) => commands::command_import(args)?,
-        // The shell command is handled in main() and will not be matched here.
-        Commands::Shell => unreachable!(),
+        Commands::Tui => tui::command_tui(&entries_dir)?,
+        Commands::Encrypt => commands::command_encrypt(&entries_dir)?,
+        Commands::Decrypt { force } => commands::command_decrypt(force, Some(&entries_dir))?,
+        // Commands that don't need a notebook were dispatched above.
+        _ => unreachable!(),
     }
 
     Ok(())
@@ -74,16 +132,16 @@ pub fn run_command(command: Commands, entries_dir: PathBuf) -> Result<()> {
 /// The main entrypoint for the rjot application.
 fn main() -> Result<()> {
     let cli = cli::Cli::parse();
+    let _flags = helpers::DisplayFlags::apply(cli.no_color, cli.columns.as_deref())?;
 
     // It either dispatches a command or handles the default jot action.
     match cli.command {
         Some(command) => {
             // The shell command is handled directly here before the dispatch.
             if let Commands::Shell = command {
-                commands::command_shell()?;
+                shell::command_shell()?;
             } else {
-                let entries_dir = helpers::get_active_entries_dir(cli.notebook)?;
-                run_command(command, entries_dir)?;
+                run_command(command, cli.notebook)?;
             }
         }
         None => {
@@ -107,7 +165,13 @@ fn main() -> Result<()> {
 // Unit tests for helpers that are simple and don't require file system access.
 #[cfg(test)]
 mod tests {
-    use crate::helpers::get_ordinal_suffix;
+    use crate::git::{merge_append_only, sync_commit_message, JotChange, JotConflict};
+    use crate::helpers::{extract_hashtags, get_ordinal_suffix, strip_ansi, Note, Task};
+    use crate::render::{self, render_markdown, NoteTable};
+    use crate::shell;
+    use crate::template::{self, Value, Variables};
+    use crate::tui;
+    use std::collections::HashMap;
 
     #[test]
     fn test_ordinal_suffix() {
@@ -124,4 +188,383 @@ mod tests {
         assert_eq!(get_ordinal_suffix(23), "rd");
         assert_eq!(get_ordinal_suffix(101), "st");
     }
+
+    #[test]
+    fn test_sync_commit_message() {
+        let changes = vec![
+            (
+                JotChange::Added,
+                "notebooks/default/2025-07-21-101500.md".to_string(),
+            ),
+            (
+                JotChange::Added,
+                "notebooks/work/2025-07-22-090000.md".to_string(),
+            ),
+            (
+                JotChange::Deleted,
+                "notebooks/work/2025-07-01-120000.md".to_string(),
+            ),
+            (
+                JotChange::Modified,
+                "notebooks/work/notebook.toml".to_string(),
+            ),
+        ];
+        assert_eq!(
+            sync_commit_message("sync: {summary}\n\n{jots}", &changes),
+            "sync: 2 jots added, 1 jot deleted, 1 other file changed\n\n\
+             - added: default/2025-07-21-101500\n\
+             - added: work/2025-07-22-090000\n\
+             - deleted: work/2025-07-01-120000"
+        );
+        assert_eq!(sync_commit_message("{summary}", &[]), "no changes");
+    }
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(
+            strip_ansi("\x1b[1m\x1b[35mrjot\x1b[0m(\x1b[33mwork\x1b[0m)> "),
+            "rjot(work)> "
+        );
+        assert_eq!(strip_ansi("plain"), "plain");
+    }
+
+    /// A template environment with in-memory partials and canned answers for prompts.
+    struct TestEnvironment {
+        partials: HashMap<&'static str, &'static str>,
+        asked: Vec<String>,
+    }
+
+    impl template::Environment for TestEnvironment {
+        fn load(&self, name: &str) -> anyhow::Result<String> {
+            self.partials
+                .get(name)
+                .map(|source| source.to_string())
+                .ok_or_else(|| anyhow::anyhow!("no template '{name}'"))
+        }
+
+        fn missing(&mut self, name: &str) -> anyhow::Result<Value> {
+            self.asked.push(name.to_string());
+            Ok(Value::Str(format!("<{name}>")))
+        }
+
+        fn command(&mut self, command: &str) -> anyhow::Result<String> {
+            match command {
+                "whoami" => Ok("ada".to_string()),
+                _ => anyhow::bail!("'{command}' is not allowed"),
+            }
+        }
+    }
+
+    fn render(source: &str, variables: &mut Variables) -> (anyhow::Result<String>, Vec<String>) {
+        let mut env = TestEnvironment {
+            partials: HashMap::from([("header", "# {{ title | upper }}\n")]),
+            asked: Vec::new(),
+        };
+        let result = template::render(source, variables, &mut env);
+        (result, env.asked)
+    }
+
+    #[test]
+    fn test_template_filters() {
+        let date = chrono::DateTime::parse_from_rfc3339("2025-07-21T10:15:00+02:00").unwrap();
+        let mut variables = Variables::from([
+            ("date".to_string(), Value::Date(date)),
+            ("tags".to_string(), Value::from("rust, cli,")),
+        ]);
+        let (result, asked) = render(
+            "{{ date | format(\"%A, %B %d\") }} | {{ ticket | default(\"none\") }} | \
+             {{ tags | split(\",\") | join(\"+\") | upper }} | {{ \"2025-01-02\" | format(\"%d.%m\") }}",
+            &mut variables,
+        );
+        assert_eq!(result.unwrap(), "Monday, July 21 | none | RUST+CLI | 02.01");
+        assert!(asked.is_empty());
+    }
+
+    #[test]
+    fn test_template_control_flow() {
+        let source = "\
+{% if branch == \"main\" %}
+On main.
+{% elif branch %}
+On {{ branch }}.
+{% else %}
+Not a repo.
+{% endif %}
+{% for tag in tags | split(\",\") %}
+- {{ loop.index }}. {{ tag }}{% if loop.last %} (last){% endif %}
+{% endfor %}
+{# A comment line disappears too. #}
+Done.";
+        let mut variables = Variables::from([
+            ("branch".to_string(), Value::from("feature")),
+            ("tags".to_string(), Value::from("a,b")),
+        ]);
+        let (result, asked) = render(source, &mut variables);
+        assert_eq!(result.unwrap(), "On feature.\n- 1. a\n- 2. b (last)\nDone.");
+        assert!(asked.is_empty(), "conditions must not prompt");
+    }
+
+    #[test]
+    fn test_template_includes_and_prompts() {
+        let mut variables = Variables::new();
+        let (result, asked) = render(
+            "{% include \"header\" %}{{ title }} by {{ author }}, again {{ author }}",
+            &mut variables,
+        );
+        assert_eq!(
+            result.unwrap(),
+            "# <TITLE>\n<title> by <author>, again <author>"
+        );
+        assert_eq!(asked, ["title", "author"]);
+        let (result, _) = render("By {{cmd: whoami }}.", &mut Variables::new());
+        assert_eq!(result.unwrap(), "By ada.");
+        assert_eq!(variables["author"], Value::from("<author>"));
+    }
+
+    #[test]
+    fn test_template_errors() {
+        let cases = [
+            ("{% if ready %}never closed", "Missing `{% endif %}`"),
+            ("{{ name | shout }}", "Unknown filter 'shout'"),
+            ("line one\n{% endfor %}", "Line 2"),
+            ("{{ unclosed", "never closed"),
+            ("{% include \"missing\" %}", "no template 'missing'"),
+            ("{{ cmd:rm -rf / }}", "'rm -rf /' is not allowed"),
+            ("{{ cmd: }}", "needs a command"),
+        ];
+        for (source, expected) in cases {
+            let (result, _) = render(source, &mut Variables::from([("name".into(), "x".into())]));
+            let message = format!("{:#}", result.unwrap_err());
+            assert!(message.contains(expected), "{source:?} gave {message:?}");
+        }
+    }
+
+    #[test]
+    fn test_template_split_meta() {
+        let source = "---\ntemplate:\n  notebook: bugs\n  tags: [bug]\n  variables:\n    title: Summary\ntags: [\"{{ kind }}\"]\n---\n# {{ title }}";
+        let (meta, rest) = template::split_meta(source).unwrap();
+        assert_eq!(meta.notebook.as_deref(), Some("bugs"));
+        assert_eq!(meta.tags, ["bug"]);
+        assert_eq!(meta.variables["title"], "Summary");
+        assert_eq!(rest, "---\ntags: [\"{{ kind }}\"]\n---\n# {{ title }}");
+
+        let (meta, rest) =
+            template::split_meta("---\ntemplate:\n  pinned: true\n---\nbody").unwrap();
+        assert!(meta.pinned);
+        assert_eq!(rest, "body");
+
+        let (_, rest) = template::split_meta("no frontmatter").unwrap();
+        assert_eq!(rest, "no frontmatter");
+        assert!(template::split_meta("---\ntemplate:\n  colour: red\n---\n").is_err());
+    }
+
+    #[test]
+    fn test_shell_completion() {
+        let root = tempfile::tempdir().unwrap();
+        let default = root.path().join("notebooks").join("default");
+        std::fs::create_dir_all(&default).unwrap();
+        std::fs::create_dir_all(root.path().join("notebooks").join("work")).unwrap();
+        std::fs::create_dir_all(root.path().join("templates").join("ops")).unwrap();
+        std::fs::write(root.path().join("templates").join("bug.md"), "").unwrap();
+        std::fs::write(root.path().join("templates/ops/deploy.md"), "").unwrap();
+        std::fs::write(
+            default.join("2025-07-21-101500.md"),
+            "---\ntags:\n- rust\n- release\n---\n\nhi",
+        )
+        .unwrap();
+        let completion = shell::Completion {
+            root: root.path().to_path_buf(),
+            active_notebook: "default".to_string(),
+        };
+
+        let complete = |line: &str| shell::complete_line(line, &completion).1;
+        assert_eq!(complete("ye"), ["yesterday"]);
+        assert_eq!(complete("ex"), ["exit", "export"]);
+        assert_eq!(complete("notebook ar"), ["archive"]);
+        assert_eq!(complete("notebook use w"), ["work"]);
+        assert_eq!(complete("use "), ["default", "work"]);
+        assert_eq!(complete("delete --f"), ["--force"]);
+        assert!(complete("today -").contains(&"--compile".to_string()));
+        assert_eq!(complete("sync --resolve o"), ["ours"]);
+        assert_eq!(complete("show 2025"), ["2025-07-21-101500"]);
+        assert_eq!(complete("pin "), ["2025-07-21-101500"]);
+        assert_eq!(complete("tags rust r"), ["release", "rust"]);
+        assert_eq!(complete("tag add -p 2025-07-21-101500 re"), ["release"]);
+        assert_eq!(complete("jot -t "), ["release", "rust"]);
+        assert_eq!(complete("new -t "), ["bug", "ops/deploy"]);
+        assert_eq!(complete("template edit o"), ["ops/deploy"]);
+        assert!(complete("template new ").is_empty());
+        assert_eq!(complete("config get list"), ["list_columns", "list_count"]);
+        assert_eq!(complete("filter --ta"), ["--tag", "--tasks"]);
+        assert_eq!(complete("open 2 p"), ["pin"]);
+        assert_eq!(complete("last e"), ["edit"]);
+        assert_eq!(
+            shell::complete_line("show --last 2 --a", &completion),
+            (14, vec!["--at".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_shell_split_words() {
+        let lookup = |name: &str| (name == "RJOT_SPLIT_TEST").then(|| "a b".to_string());
+        let cases = [
+            ("find \"borrow checker\"", vec!["find", "borrow checker"]),
+            ("t 'Write $HOME tests'", vec!["t", "Write $HOME tests"]),
+            ("one\\ word  \"\" x", vec!["one word", "", "x"]),
+            (
+                "$RJOT_SPLIT_TEST \"${RJOT_SPLIT_TEST}!\"",
+                vec!["a b", "a b!"],
+            ),
+            ("$RJOT_SPLIT_UNSET end", vec!["end"]),
+            (
+                "cost: $5 \"say \\\"hi\\\"\"",
+                vec!["cost:", "$5", "say \"hi\""],
+            ),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(
+                shell::split_words_with(line, &lookup).unwrap(),
+                expected,
+                "{line:?}"
+            );
+        }
+        for (line, expected) in [
+            ("find 'oops", "Unterminated single quote"),
+            ("find \"oops", "Unterminated double quote"),
+            ("trailing \\", "Trailing backslash"),
+            ("${NAME", "Missing `}`"),
+        ] {
+            let message = shell::split_words_with(line, &lookup)
+                .unwrap_err()
+                .to_string();
+            assert!(message.contains(expected), "{line:?} gave {message:?}");
+        }
+    }
+
+    #[test]
+    fn test_extract_hashtags() {
+        let text = "# Heading\n#rust and #cli-tools, see issue #42.\n\
+                    Link: https://example.com/#anchor #rust #work/infra\n\
+                    ```sh\n#comment\n```\nafter#nope";
+        assert_eq!(extract_hashtags(text), ["rust", "cli-tools", "work/infra"]);
+        assert!(extract_hashtags("no tags here").is_empty());
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let markdown = "# Title\n\nSome **bold** and *italic* words that wrap around.\n\n\
+                        - [ ] open task\n- [x] done task\n\n```rust\nfn main() {}\n```\n\n> quoted";
+        assert_eq!(
+            render_markdown(markdown, 24, false),
+            "Title\n\nSome bold and italic\nwords that wrap around.\n\n\
+             [ ] open task\n[x] done task\n\n    fn main() {}\n\n│ quoted\n"
+        );
+
+        let colored = render_markdown(markdown, 24, true);
+        assert!(colored.contains("\x1b[1m"));
+        assert!(colored.contains("\x1b[33m[ ] "));
+        assert_eq!(strip_ansi(&colored), render_markdown(markdown, 24, false));
+    }
+
+    #[test]
+    fn test_tui_filter_and_tasks() {
+        let filter = tui::Filter::parse("#rust @2025-07 Borrow");
+        assert_eq!(filter.tags, ["rust"]);
+        assert_eq!(filter.dates, ["2025-07"]);
+        assert_eq!(filter.words, ["borrow"]);
+
+        let content = "Plan\n- [ ] first\n  - [x] second\n- [ ] third";
+        assert_eq!(
+            tui::toggle_task(content, 1).unwrap(),
+            "Plan\n- [ ] first\n  - [ ] second\n- [ ] third"
+        );
+        assert_eq!(
+            tui::toggle_task(content, 2).unwrap(),
+            "Plan\n- [ ] first\n  - [x] second\n- [x] third"
+        );
+        assert!(tui::toggle_task(content, 3).is_none());
+    }
+
+    #[test]
+    fn test_merge_append_only() {
+        let merge = |ancestor: &str, ours: &str, theirs: &str| {
+            merge_append_only(&JotConflict {
+                path: "2025-07-21-101500.md".into(),
+                ancestor: Some(ancestor.to_string()),
+                ours: Some(ours.to_string()),
+                theirs: Some(theirs.to_string()),
+            })
+        };
+        assert_eq!(merge("a\n", "a\n", "a\nb\n").unwrap(), "a\nb\n");
+        assert_eq!(merge("a\n", "a\nb\nc\n", "a\nb\n").unwrap(), "a\nb\nc\n");
+        assert_eq!(merge("a\n", "a\nb\n", "a\nc\n").unwrap(), "a\nb\nc\n");
+        // Cutting text on one side while the other appends is a real conflict.
+        assert!(merge("a\nb\n", "a\n", "a\nb\nc\n").is_none());
+        assert!(merge("a\nb\n", "a\nb\nc\n", "a\n").is_none());
+        assert!(merge("a\n", "x\n", "a\nb\n").is_none());
+    }
+
+    #[test]
+    fn test_note_table() {
+        let age = |seconds| render::relative_time(chrono::TimeDelta::seconds(seconds));
+        assert_eq!(age(-5), "just now");
+        assert_eq!(age(59), "just now");
+        assert_eq!(age(150), "2m ago");
+        assert_eq!(age(3 * 3600 + 59), "3h ago");
+        assert_eq!(age(3 * 86400), "3d ago");
+        assert_eq!(age(20 * 86400), "2w ago");
+        assert_eq!(age(100 * 86400), "3mo ago");
+        assert_eq!(age(800 * 86400), "2y ago");
+        assert_eq!(render::truncate("rustacean", 9), "rustacean");
+        assert_eq!(render::truncate("rustacean", 5), "rust…");
+        assert_eq!(render::truncate("日本語です", 5), "日本…");
+
+        let mut note = Note {
+            id: "2025-07-01-090000".to_string(),
+            content: "Plan the week ahead\n- [x] one\n- [ ] two".to_string(),
+            tasks: vec![
+                Task {
+                    description: "one".to_string(),
+                    completed: true,
+                },
+                Task {
+                    description: "two".to_string(),
+                    completed: false,
+                },
+            ],
+            ..Default::default()
+        };
+        note.frontmatter.tags = vec!["work".to_string()];
+        note.frontmatter.pinned = true;
+        let table = NoteTable {
+            columns: &["pinned", "id", "age", "tasks", "title", "words"],
+            now: chrono::NaiveDate::from_ymd_opt(2025, 7, 1)
+                .unwrap()
+                .and_hms_opt(12, 30, 0)
+                .unwrap(),
+            date_format: "%Y-%m-%d %H:%M",
+            color: false,
+        };
+        assert_eq!(
+            table.render(std::slice::from_ref(&note), None),
+            "\n  ID                AGE    TASKS TITLE               WORDS\n\
+             - ----------------- ------ ----- ------------------- -----\n\
+             * 2025-07-01-090000 3h ago 1/2   Plan the week ahead     6\n"
+        );
+        assert!(table
+            .render(std::slice::from_ref(&note), Some(50))
+            .contains(" Plan the w…     6\n"));
+
+        let table = NoteTable {
+            columns: &["id", "title", "tags"],
+            color: true,
+            ..table
+        };
+        let narrow = table.render(std::slice::from_ref(&note), Some(36));
+        assert!(narrow.contains("\x1b[36m#work"));
+        assert!(strip_ansi(&narrow)
+            .lines()
+            .all(|line| line.chars().count() <= 36));
+        assert!(strip_ansi(&narrow).contains(" Plan the we… #work"));
+    }
 }
@@ -0,0 +1,677 @@
+//! Renders jots as styled, wrapped terminal output for `show` and `--compile`, and as
+//! tables for listings.
+//!
+//! Markdown is parsed with `pulldown-cmark` and turned into text with ANSI styles:
+//! bold headings, bold and italic spans, colored task checkboxes, and code blocks
+//! highlighted with `syntect`. Output that doesn't fit on the screen goes through `$PAGER`.
+//! Listing tables shorten their widest text columns to fit the terminal, and are paged too.
+
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+use chrono::{NaiveDateTime, TimeDelta};
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::crossterm::terminal;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::ThemeSet;
+use syntect::parsing::SyntaxSet;
+use syntect::util::{as_24_bit_terminal_escaped, LinesWithEndings};
+
+use crate::helpers::{self, Note};
+
+const BOLD: &str = "\x1b[1m";
+const ITALIC: &str = "\x1b[3m";
+const DIM: &str = "\x1b[2m";
+const STRIKE: &str = "\x1b[9m";
+const MAGENTA: &str = "\x1b[35m";
+const CYAN: &str = "\x1b[36m";
+const YELLOW: &str = "\x1b[33m";
+const GREEN: &str = "\x1b[32m";
+const BLUE: &str = "\x1b[34m";
+const RESET: &str = "\x1b[0m";
+
+// The widest paragraphs get, even on a wide terminal, so they stay readable.
+const MAX_WIDTH: usize = 100;
+
+/// Prints markdown rendered for the terminal, or unchanged if `raw` is set or stdout
+/// isn't a terminal. Rendered output taller than the screen is shown in `$PAGER`.
+pub fn print_markdown(markdown: &str, raw: bool, notebook_dir: Option<&Path>) -> Result<()> {
+    if raw || !io::stdout().is_terminal() {
+        println!("{markdown}");
+        return Ok(());
+    }
+    let color = helpers::load_preferences(notebook_dir)?.color();
+    let (columns, _) = terminal::size().unwrap_or((80, 24));
+    print_paged(&render_markdown(
+        markdown,
+        usize::from(columns).min(MAX_WIDTH),
+        color,
+    ));
+    Ok(())
+}
+
+// Prints text for the terminal, through `$PAGER` if it is taller than the screen.
+fn print_paged(text: &str) {
+    let (_, rows) = terminal::size().unwrap_or((80, 24));
+    if text.lines().count() < usize::from(rows) || !page(text) {
+        print!("{text}");
+    }
+}
+
+// Sends text to `$PAGER` (`less -R` by default). Returns `false` if there is no pager to
+// run, so the caller prints the text itself.
+fn page(text: &str) -> bool {
+    let pager = env::var("PAGER").unwrap_or_else(|_| "less -R".to_string());
+    let mut words = pager.split_whitespace();
+    let Some(program) = words.next() else {
+        return false;
+    };
+    let Ok(mut child) = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()
+    else {
+        return false;
+    };
+    // The pager closes its input when the user quits early, so a broken pipe is expected.
+    let _ = child.stdin.take().unwrap().write_all(text.as_bytes());
+    let _ = child.wait();
+    true
+}
+
+/// Prints jots as a table with the columns from the `list_columns` preference, shortened
+/// to fit the terminal and paged like [`print_markdown`] if it is taller than the screen.
+/// `extra` names a column to add after the ID if it isn't chosen.
+pub fn print_note_table(notes: &[Note], extra: Option<&str>) -> Result<()> {
+    let notebook_dir = notes.first().and_then(|note| note.path.parent());
+    let preferences = helpers::load_preferences(notebook_dir)?;
+    let mut columns = preferences.list_columns();
+    if let Some(extra) = extra.filter(|extra| !columns.contains(extra)) {
+        let after_id = columns.iter().position(|c| *c == "id").map_or(0, |i| i + 1);
+        columns.insert(after_id, extra);
+    }
+    // The marker column would only be blank space.
+    if !notes.iter().any(|note| note.frontmatter.pinned) {
+        columns.retain(|column| *column != "pinned");
+    }
+    // Piped output keeps every character and skips the pager, so scripts can rely on it.
+    let interactive = io::stdout().is_terminal();
+    let width = interactive
+        .then(|| {
+            terminal::size()
+                .map(|(columns, _)| usize::from(columns))
+                .ok()
+        })
+        .flatten();
+    let table = NoteTable {
+        columns: &columns,
+        now: preferences.now()?.naive_local(),
+        date_format: &preferences.datetime_format(),
+        color: preferences.color(),
+    };
+    let rendered = table.render(notes, width);
+    if interactive {
+        print_paged(&rendered);
+    } else {
+        print!("{rendered}");
+    }
+    Ok(())
+}
+
+/// The settings for turning jots into table rows.
+pub struct NoteTable<'a> {
+    pub columns: &'a [&'a str],
+    /// The current local time, which the `age` column counts from.
+    pub now: NaiveDateTime,
+    pub date_format: &'a str,
+    pub color: bool,
+}
+
+impl NoteTable<'_> {
+    /// Renders a header, a rule, and a row per jot, at most `width` columns wide if given.
+    pub fn render(&self, notes: &[Note], width: Option<usize>) -> String {
+        let rows: Vec<Vec<String>> = notes
+            .iter()
+            .map(|note| self.columns.iter().map(|c| self.cell(note, c)).collect())
+            .collect();
+        let mut widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                rows.iter()
+                    .map(|row| display_width(&row[i]))
+                    .chain([display_width(column_header(column))])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        if let Some(width) = width {
+            self.fit(&mut widths, width);
+        }
+
+        let mut out = String::from("\n");
+        let header: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| column_header(c).into())
+            .collect();
+        out.push_str(&self.line(&header, &widths, |_| BOLD));
+        let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+        out.push_str(&self.line(&rule, &widths, |_| ""));
+        for row in &rows {
+            out.push_str(&self.line(row, &widths, |i| self.cell_style(&row[i], i)));
+        }
+        out
+    }
+
+    // Shrinks the title, then the tags, until the table fits, keeping some of each.
+    fn fit(&self, widths: &mut [usize], width: usize) {
+        let total = widths.iter().sum::<usize>() + widths.len().saturating_sub(1);
+        let mut excess = total.saturating_sub(width);
+        for (column, floor) in [("title", 20), ("tags", 10), ("title", 10)] {
+            if let Some(i) = self.columns.iter().position(|c| *c == column) {
+                let shrink = excess.min(widths[i].saturating_sub(floor));
+                widths[i] -= shrink;
+                excess -= shrink;
+            }
+        }
+    }
+
+    // Lays out one table line, padding each cell but the last and styling it if colored.
+    fn line<'s>(
+        &self,
+        cells: &[String],
+        widths: &[usize],
+        style: impl Fn(usize) -> &'s str,
+    ) -> String {
+        let mut line = String::new();
+        for (i, (cell, width)) in cells.iter().zip(widths).enumerate() {
+            if i > 0 {
+                line.push(' ');
+            }
+            let text = truncate(cell, *width);
+            let padding = " ".repeat(width - display_width(&text));
+            let text = match self.columns[i] {
+                "words" => format!("{padding}{text}"),
+                _ if i + 1 == cells.len() => text,
+                _ => format!("{text}{padding}"),
+            };
+            match style(i) {
+                code if self.color && !code.is_empty() && !cell.is_empty() => {
+                    line.push_str(&format!("{code}{text}{RESET}"))
+                }
+                _ => line.push_str(&text),
+            }
+        }
+        line.trim_end().to_string() + "\n"
+    }
+
+    fn cell(&self, note: &Note, column: &str) -> String {
+        let created = NaiveDateTime::parse_from_str(&note.id, "%Y-%m-%d-%H%M%S").ok();
+        match column {
+            "pinned" if note.frontmatter.pinned => "*".to_string(),
+            "id" => note.id.clone(),
+            "date" => created.map_or_else(String::new, |created| {
+                created.format(self.date_format).to_string()
+            }),
+            "age" => created.map_or_else(String::new, |created| relative_time(self.now - created)),
+            "title" => note.content.lines().next().unwrap_or("").trim().to_string(),
+            "tags" => note
+                .frontmatter
+                .tags
+                .iter()
+                .map(|tag| format!("#{tag}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            "tasks" if !note.tasks.is_empty() => {
+                let done = note.tasks.iter().filter(|task| task.completed).count();
+                format!("{done}/{}", note.tasks.len())
+            }
+            "notebook" => note.notebook.clone(),
+            // Markup like list bullets and task boxes isn't counted.
+            "words" => note
+                .content
+                .split_whitespace()
+                .filter(|word| !matches!(*word, "[x]" | "[X]"))
+                .filter(|word| word.chars().any(char::is_alphanumeric))
+                .count()
+                .to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn cell_style(&self, cell: &str, i: usize) -> &'static str {
+        match self.columns[i] {
+            "pinned" => MAGENTA,
+            "id" => YELLOW,
+            "date" | "age" | "words" => DIM,
+            "tags" => CYAN,
+            "notebook" => BLUE,
+            "tasks" => match cell.split_once('/') {
+                Some((done, total)) if done == total => GREEN,
+                _ => YELLOW,
+            },
+            _ => "",
+        }
+    }
+}
+
+fn column_header(column: &str) -> &'static str {
+    match column {
+        "id" => "ID",
+        "date" => "CREATED",
+        "age" => "AGE",
+        "title" => "TITLE",
+        "tags" => "TAGS",
+        "tasks" => "TASKS",
+        "notebook" => "NOTEBOOK",
+        "words" => "WORDS",
+        _ => "",
+    }
+}
+
+/// Describes how long ago something happened, like `3h ago`.
+pub fn relative_time(age: TimeDelta) -> String {
+    let days = age.num_days();
+    match age.num_seconds() {
+        ..60 => "just now".to_string(),
+        60..3600 => format!("{}m ago", age.num_minutes()),
+        3600..86400 => format!("{}h ago", age.num_hours()),
+        _ if days < 14 => format!("{days}d ago"),
+        _ if days < 60 => format!("{}w ago", days / 7),
+        _ if days < 365 => format!("{}mo ago", days / 30),
+        _ => format!("{}y ago", days / 365),
+    }
+}
+
+/// Shortens text to at most `width` columns, ending it with `…` if anything was cut.
+pub fn truncate(text: &str, width: usize) -> String {
+    if display_width(text) <= width {
+        return text.to_string();
+    }
+    let mut short = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let c_width = display_width(c.encode_utf8(&mut [0; 4]));
+        if used + c_width + 1 > width {
+            break;
+        }
+        short.push(c);
+        used += c_width;
+    }
+    if width > 0 {
+        short.truncate(short.trim_end().len());
+        short.push('…');
+    }
+    short
+}
+
+fn display_width(text: &str) -> usize {
+    textwrap::core::display_width(text)
+}
+
+/// Renders markdown as terminal text wrapped to `width` columns, with ANSI styles if
+/// `color` is set.
+pub fn render_markdown(markdown: &str, width: usize, color: bool) -> String {
+    let mut renderer = Renderer {
+        out: String::new(),
+        width: width.max(20),
+        color,
+        line: String::new(),
+        styles: Vec::new(),
+        lists: Vec::new(),
+        items: Vec::new(),
+        bullet: None,
+        quote: 0,
+        code: None,
+        link: None,
+        blank: None,
+    };
+    let options = Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH;
+    for event in Parser::new_ext(markdown, options) {
+        renderer.event(event);
+    }
+    renderer.flush();
+    renderer.out
+}
+
+// An open list item.
+struct Item {
+    // The width of its marker, which later lines are indented by.
+    hang: usize,
+    // Whether it is a finished task, shown dimmed and struck through.
+    done: bool,
+}
+
+struct Renderer {
+    out: String,
+    width: usize,
+    color: bool,
+    // Inline text of the current block, waiting to be wrapped.
+    line: String,
+    // Active inline styles, so they can be restored after one of them ends.
+    styles: Vec<&'static str>,
+    // The next number of each open list, or `None` for bullet lists.
+    lists: Vec<Option<u64>>,
+    items: Vec<Item>,
+    // The marker for the first line of the current list item, until it is written.
+    bullet: Option<String>,
+    quote: usize,
+    // The language and text of the code block being read.
+    code: Option<(String, String)>,
+    // Where the current link's text starts in `line`, and where it points.
+    link: Option<(usize, String)>,
+    // The quote depth of a blank line that is written only once more text follows, so
+    // that it is inside a quote only when the text on both sides is.
+    blank: Option<usize>,
+}
+
+impl Renderer {
+    fn event(&mut self, event: Event) {
+        if let Some((_, code)) = &mut self.code {
+            match event {
+                Event::Text(text) => code.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => self.code_block(),
+                _ => {}
+            }
+            return;
+        }
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                self.flush();
+                self.push_style(BOLD);
+                if level <= HeadingLevel::H2 {
+                    self.push_style(MAGENTA);
+                }
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                if level <= HeadingLevel::H2 {
+                    self.pop_style();
+                }
+                self.pop_style();
+                self.end_block();
+            }
+            Event::End(TagEnd::Paragraph) => self.end_block(),
+            Event::Start(Tag::BlockQuote(_)) => {
+                self.flush();
+                self.quote += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                self.flush();
+                self.quote -= 1;
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                self.flush();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or("").to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((language, String::new()));
+            }
+            Event::Start(Tag::List(start)) => {
+                self.flush();
+                self.lists.push(start);
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.flush();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.blank_line();
+                }
+            }
+            Event::Start(Tag::Item) => {
+                self.flush();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                self.items.push(Item {
+                    hang: marker.chars().count(),
+                    done: false,
+                });
+                self.bullet = Some(marker);
+            }
+            Event::End(TagEnd::Item) => {
+                if self.items.last().is_some_and(|item| item.done) {
+                    self.pop_style();
+                    self.pop_style();
+                }
+                self.flush();
+                self.items.pop();
+            }
+            Event::TaskListMarker(done) => {
+                let (mark, style) = if done {
+                    ("[x] ", GREEN)
+                } else {
+                    ("[ ] ", YELLOW)
+                };
+                let checkbox = if self.color {
+                    format!("{style}{mark}{RESET}")
+                } else {
+                    mark.to_string()
+                };
+                // The checkbox takes the place of a bullet, or follows a number.
+                let marker = match self.bullet.take() {
+                    Some(bullet) if bullet != "• " => format!("{bullet}{checkbox}"),
+                    _ => checkbox,
+                };
+                if let Some(item) = self.items.last_mut() {
+                    item.hang = textwrap::core::display_width(&marker);
+                    item.done = done;
+                }
+                self.bullet = Some(marker);
+                if done {
+                    self.push_style(DIM);
+                    self.push_style(STRIKE);
+                }
+            }
+            Event::Start(Tag::Emphasis) => self.push_style(ITALIC),
+            Event::Start(Tag::Strong) => self.push_style(BOLD),
+            Event::Start(Tag::Strikethrough) => self.push_style(STRIKE),
+            Event::End(TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough) => {
+                self.pop_style()
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                self.push_style(CYAN);
+                self.link = Some((self.line.len(), dest_url.to_string()));
+            }
+            Event::End(TagEnd::Link) => {
+                self.pop_style();
+                if let Some((start, url)) = self.link.take() {
+                    // Autolinks already show the address.
+                    if helpers::strip_ansi(self.line.get(start..).unwrap_or_default()) != url {
+                        self.styled(DIM, &format!(" ({url})"));
+                    }
+                }
+            }
+            Event::Text(text) | Event::Html(text) | Event::InlineHtml(text) => {
+                self.line.push_str(&text)
+            }
+            Event::Code(code) => {
+                if self.color {
+                    self.styled(CYAN, &code);
+                } else {
+                    self.line.push_str(&format!("`{code}`"));
+                }
+            }
+            Event::SoftBreak => self.line.push(' '),
+            Event::HardBreak => self.line.push('\n'),
+            Event::Rule => {
+                self.flush();
+                let rule = "─".repeat(self.width.saturating_sub(self.indent_width()));
+                self.styled(DIM, &rule);
+                self.end_block();
+            }
+            _ => {}
+        }
+    }
+
+    fn push_style(&mut self, style: &'static str) {
+        self.styles.push(style);
+        if self.color {
+            self.line.push_str(style);
+        }
+    }
+
+    fn pop_style(&mut self) {
+        self.styles.pop();
+        if self.color {
+            self.line.push_str(RESET);
+            for style in &self.styles {
+                self.line.push_str(style);
+            }
+        }
+    }
+
+    fn styled(&mut self, style: &'static str, text: &str) {
+        self.push_style(style);
+        self.line.push_str(text);
+        self.pop_style();
+    }
+
+    // The bars in front of lines inside `depth` block quotes.
+    fn quote_bars(&self, depth: usize) -> String {
+        if self.color {
+            format!("{DIM}│{RESET} ").repeat(depth)
+        } else {
+            "│ ".repeat(depth)
+        }
+    }
+
+    // The text in front of every line: quote bars, then room for the markers of the
+    // first `items` enclosing list items.
+    fn indent(&self, items: usize) -> String {
+        let hang: usize = self.items[..items].iter().map(|item| item.hang).sum();
+        format!("{}{}", self.quote_bars(self.quote), " ".repeat(hang))
+    }
+
+    fn indent_width(&self) -> usize {
+        2 * self.quote + self.items.iter().map(|item| item.hang).sum::<usize>()
+    }
+
+    // Wraps and writes the pending inline text.
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.line);
+        if helpers::strip_ansi(&text).trim().is_empty() {
+            return;
+        }
+        // The current item's marker goes on its first line; later lines line up with the text.
+        let (prefix, hanging) = match self.bullet.take() {
+            Some(bullet) => (
+                format!("{}{bullet}", self.indent(self.items.len() - 1)),
+                self.indent(self.items.len()),
+            ),
+            None => {
+                let indent = self.indent(self.items.len());
+                (indent.clone(), indent)
+            }
+        };
+        self.write_pending_blank_line();
+        let available = self.width.saturating_sub(self.indent_width()).max(10);
+        // Styles are re-opened on every line, so each line stands on its own.
+        let mut carried = String::new();
+        let mut first = true;
+        for segment in text.split('\n') {
+            for line in textwrap::wrap(segment, available) {
+                self.out.push_str(if first {
+                    prefix.as_str()
+                } else {
+                    hanging.as_str()
+                });
+                self.out.push_str(&carried);
+                self.out.push_str(&line);
+                if self.color {
+                    self.out.push_str(RESET);
+                }
+                self.out.push('\n');
+                carried = open_styles(&format!("{carried}{line}"));
+                first = false;
+            }
+        }
+        if self.color {
+            // Styles that are still open carry on into the next block of text.
+            self.line.push_str(&self.styles.concat());
+        }
+    }
+
+    // Ends a paragraph-like block, with a blank line after it outside of lists.
+    fn end_block(&mut self) {
+        self.flush();
+        if self.lists.is_empty() {
+            self.blank_line();
+        }
+    }
+
+    fn blank_line(&mut self) {
+        if !self.out.is_empty() {
+            self.blank = Some(self.blank.unwrap_or(self.quote).min(self.quote));
+        }
+    }
+
+    fn write_pending_blank_line(&mut self) {
+        if let Some(quote) = self.blank.take() {
+            let bars = self.quote_bars(quote.min(self.quote));
+            self.out.push_str(bars.trim_end());
+            self.out.push('\n');
+        }
+    }
+
+    fn code_block(&mut self) {
+        let Some((language, code)) = self.code.take() else {
+            return;
+        };
+        self.write_pending_blank_line();
+        let indent = format!("{}    ", self.indent(self.items.len()));
+        if self.color {
+            let syntaxes = SyntaxSet::load_defaults_newlines();
+            let themes = ThemeSet::load_defaults();
+            let syntax = syntaxes
+                .find_syntax_by_token(&language)
+                .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+            let mut highlighter = HighlightLines::new(syntax, &themes.themes["base16-ocean.dark"]);
+            for line in LinesWithEndings::from(&code) {
+                let ranges = highlighter
+                    .highlight_line(line, &syntaxes)
+                    .unwrap_or_default();
+                self.out.push_str(&indent);
+                self.out
+                    .push_str(as_24_bit_terminal_escaped(&ranges, false).trim_end_matches('\n'));
+                self.out.push_str(RESET);
+                self.out.push('\n');
+            }
+        } else {
+            for line in code.lines() {
+                self.out.push_str(&indent);
+                self.out.push_str(line);
+                self.out.push('\n');
+            }
+        }
+        if self.lists.is_empty() {
+            self.blank_line();
+        }
+    }
+}
+
+// The style codes still in effect at the end of some text: everything after its last reset.
+fn open_styles(text: &str) -> String {
+    let after_reset = text.rsplit(RESET).next().unwrap_or_default();
+    let mut codes = String::new();
+    let mut rest = after_reset;
+    while let Some(start) = rest.find("\x1b[") {
+        let Some(end) = rest[start..].find('m') else {
+            break;
+        };
+        codes.push_str(&rest[start..=start + end]);
+        rest = &rest[start + end + 1..];
+    }
+    codes
+}
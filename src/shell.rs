@@ -0,0 +1,1059 @@
+//! This module contains the interactive `rjot shell`.
+//!
+//! The shell reads commands with `rustyline` and runs them through the same `clap`
+//! definitions as the command line. Tab completion is derived from those definitions
+//! too, so new commands and flags are completed without any changes here.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use clap::{Arg, CommandFactory, Parser};
+use rand::Rng;
+use rustyline::completion::Completer;
+use rustyline::config::Configurer;
+use rustyline::history::DefaultHistory;
+use rustyline::CompletionType;
+use rustyline::Editor;
+use rustyline_derive::{Helper, Highlighter, Hinter, Validator};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{Cli, Commands, NotebookAction};
+use crate::commands;
+use crate::helpers;
+
+/// Commands that only exist inside the shell.
+const SHELL_BUILTINS: [&str; 10] = [
+    "use", "write", ":compose", "last", "open", "filter", "alias", "unalias", "exit", "quit",
+];
+
+/// The commands that `last` and `open <n>` can run on a jot.
+const JOT_ACTIONS: [&str; 8] = [
+    "show", "edit", "delete", "pin", "unpin", "history", "diff", "restore",
+];
+
+/// Where tab completion finds notebooks, jots, tags, templates, and aliases.
+pub struct Completion {
+    /// The rjot directory.
+    pub root: PathBuf,
+    /// The notebook whose jots and tags are offered when the line doesn't name one.
+    pub active_notebook: String,
+}
+
+impl Completion {
+    fn notebook_dir(&self, notebook: Option<&str>) -> PathBuf {
+        self.root
+            .join("notebooks")
+            .join(notebook.unwrap_or(&self.active_notebook))
+    }
+}
+
+/// The rustyline helper that provides tab completion.
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct RjotHelper {
+    completion: Completion,
+}
+
+impl Completer for RjotHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Self::Candidate>)> {
+        Ok(complete_line(&line[..pos], &self.completion))
+    }
+}
+
+/// Returns where the word being completed starts and the candidates for it.
+///
+/// The words before it are walked through the `clap` command tree to find the
+/// subcommand and, if any, the argument the word belongs to.
+pub fn complete_line(line: &str, completion: &Completion) -> (usize, Vec<String>) {
+    let mut words: Vec<&str> = line.split_whitespace().collect();
+    let current = if line.is_empty() || line.ends_with(char::is_whitespace) {
+        ""
+    } else {
+        words.pop().unwrap_or_default()
+    };
+    let start = line.len() - current.len();
+
+    match (words.first().copied(), words.len()) {
+        (Some("use"), 1) => return (start, matching(notebook_names(completion), current)),
+        (Some("last"), 1) | (Some("open"), 2) => {
+            return (
+                start,
+                matching(JOT_ACTIONS.map(String::from).to_vec(), current),
+            );
+        }
+        (Some("use" | "last" | "open" | "alias" | "unalias"), _) => return (start, Vec::new()),
+        // A filter takes the same flags as `list`.
+        (Some("filter"), _) => words[0] = "list",
+        _ => {}
+    }
+
+    let mut root = Cli::command();
+    root.build();
+    let mut command = &root;
+    let mut path: Vec<&str> = Vec::new();
+    let mut pending: Option<&Arg> = None;
+    let mut positional = 0;
+    let mut notebook = None;
+
+    for word in words {
+        if let Some(arg) = pending {
+            if arg.get_id() == "notebook" {
+                notebook = Some(word.to_string());
+            }
+            // Arguments like `--tags a b` keep taking values until the next flag.
+            if !takes_many_values(arg) {
+                pending = None;
+            }
+            continue;
+        }
+        if let Some(flag) = word.strip_prefix("--") {
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (flag, None),
+            };
+            let arg = command
+                .get_arguments()
+                .find(|arg| arg.get_long() == Some(name));
+            if let (Some(arg), Some(value)) = (arg, value) {
+                if arg.get_id() == "notebook" {
+                    notebook = Some(value.to_string());
+                }
+            }
+            pending = arg.filter(|arg| value.is_none() && takes_value(arg));
+        } else if let Some(short) = word.strip_prefix('-').filter(|s| s.chars().count() == 1) {
+            let short = short.chars().next().unwrap();
+            pending = command
+                .get_arguments()
+                .find(|arg| arg.get_short() == Some(short))
+                .filter(|arg| takes_value(arg));
+        } else if let Some(subcommand) = command.find_subcommand(word) {
+            path.push(subcommand.get_name());
+            command = subcommand;
+            positional = 0;
+        } else {
+            positional += 1;
+        }
+    }
+
+    let candidates = if let Some(arg) = pending.filter(|_| !current.starts_with('-')) {
+        arg_values(arg, &path, notebook.as_deref(), completion)
+    } else if current.starts_with('-') {
+        let mut flags = Vec::new();
+        for arg in command.get_arguments().filter(|arg| !arg.is_hide_set()) {
+            if let Some(long) = arg.get_long() {
+                flags.push(format!("--{long}"));
+            }
+            if let Some(short) = arg.get_short() {
+                flags.push(format!("-{short}"));
+            }
+        }
+        flags
+    } else if command.has_subcommands() {
+        let mut names: Vec<String> = command
+            .get_subcommands()
+            .filter(|subcommand| subcommand.get_name() != "help")
+            .flat_map(|subcommand| {
+                std::iter::once(subcommand.get_name()).chain(subcommand.get_all_aliases())
+            })
+            .map(String::from)
+            .collect();
+        if path.is_empty() {
+            names.extend(SHELL_BUILTINS.map(String::from));
+            if let Ok(config) = helpers::load_config_in(&completion.root) {
+                names.extend(config.aliases.into_keys());
+            }
+        }
+        names
+    } else {
+        let positionals: Vec<&Arg> = command.get_positionals().collect();
+        let arg = positionals.get(positional).or_else(|| {
+            positionals
+                .last()
+                .filter(|arg| takes_many_values(arg) && positional > 0)
+        });
+        match arg {
+            Some(arg) => arg_values(arg, &path, notebook.as_deref(), completion),
+            None => Vec::new(),
+        }
+    };
+
+    let mut candidates = matching(candidates, current);
+    candidates.sort();
+    candidates.dedup();
+    (start, candidates)
+}
+
+/// Splits a shell line into words, the way a POSIX shell would.
+///
+/// Single quotes keep everything literally. Inside double quotes, `$VAR` and `${VAR}`
+/// are expanded and a backslash escapes `"`, `\`, and `$`. Outside quotes, a backslash
+/// escapes any character. Unset variables expand to nothing.
+pub fn split_words(line: &str) -> Result<Vec<String>> {
+    split_words_with(line, &|name| env::var(name).ok())
+}
+
+/// Splits a shell line like [`split_words`], looking variables up with `lookup`.
+pub fn split_words_with(
+    line: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    // Set once the word has content or quotes, so `""` is an empty word but `$UNSET` is none.
+    let mut in_word = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated single quote. Close it with a matching `'`."),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => {
+                                bail!("Unterminated double quote. Close it with a matching `\"`.")
+                            }
+                        },
+                        Some('$') => word.push_str(&expand_variable(&mut chars, lookup)?),
+                        Some(c) => word.push(c),
+                        None => bail!("Unterminated double quote. Close it with a matching `\"`."),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => {
+                    word.push(c);
+                    in_word = true;
+                }
+                None => bail!("Trailing backslash. Use `\\\\` for a literal backslash."),
+            },
+            '$' => {
+                let value = expand_variable(&mut chars, lookup)?;
+                in_word |= !value.is_empty();
+                word.push_str(&value);
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Expands the variable after a `$`. A `$` not followed by a name is kept as is.
+fn expand_variable(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<String> {
+    let is_name_char = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+    let name = if chars.peek() == Some(&'{') {
+        chars.next();
+        let mut name = String::new();
+        loop {
+            match chars.next() {
+                Some('}') => break,
+                Some(c) if is_name_char(&c) => name.push(c),
+                Some(c) => bail!("Invalid character '{c}' in a `${{...}}` variable name."),
+                None => bail!("Missing `}}` after `${{{name}`."),
+            }
+        }
+        if name.is_empty() {
+            bail!("Empty variable name in `${{}}`.");
+        }
+        name
+    } else {
+        let mut name = String::new();
+        if chars
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || *c == '_')
+        {
+            while let Some(c) = chars.next_if(is_name_char) {
+                name.push(c);
+            }
+        }
+        if name.is_empty() {
+            return Ok("$".to_string());
+        }
+        name
+    };
+    Ok(lookup(&name).unwrap_or_default())
+}
+
+/// Renders the prompt from the `prompt` setting, or the default colored prompt.
+fn render_prompt(notebook: &str) -> String {
+    let entries_dir = helpers::get_active_entries_dir(Some(notebook.to_string())).ok();
+    let preferences = helpers::load_preferences(entries_dir.as_deref()).unwrap_or_default();
+    let Some(template) = preferences.prompt() else {
+        let prompt = format!("\x1b[1m\x1b[35mrjot\x1b[0m(\x1b[33m{notebook}\x1b[0m)> ");
+        return if preferences.color() {
+            prompt
+        } else {
+            helpers::strip_ansi(&prompt)
+        };
+    };
+    let mut prompt = template.replace("{notebook}", notebook);
+    // Counting tasks reads every jot, so only do it when the prompt shows them.
+    if prompt.contains("{pending}") {
+        let pending = entries_dir
+            .as_deref()
+            .and_then(|dir| commands::calculate_stats_for_dir(dir).ok())
+            .map_or(0, |(_, _, tasks)| tasks.pending);
+        prompt = prompt.replace("{pending}", &pending.to_string());
+    }
+    if prompt.contains("{today}") {
+        let today = preferences
+            .now()
+            .map(|now| now.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        let count = entries_dir
+            .as_deref()
+            .and_then(|dir| helpers::list_note_paths(dir).ok())
+            .unwrap_or_default()
+            .iter()
+            .filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with(&today))
+            })
+            .count();
+        prompt = prompt.replace("{today}", &count.to_string());
+    }
+    prompt
+}
+
+/// Replaces the first word with its alias, if it has one. Aliases aren't expanded again.
+fn expand_alias(words: Vec<String>, aliases: &BTreeMap<String, String>) -> Result<Vec<String>> {
+    let Some(definition) = words.first().and_then(|word| aliases.get(word)) else {
+        return Ok(words);
+    };
+    let mut expanded = split_words(definition)?;
+    expanded.extend(words.into_iter().skip(1));
+    Ok(expanded)
+}
+
+/// Runs `alias`, `alias name=command`, or `unalias name`, saving aliases in `config.toml`.
+fn update_aliases(words: &[String], aliases: &mut BTreeMap<String, String>) -> Result<()> {
+    let mut config = helpers::load_config()?;
+    if words[0] == "unalias" {
+        let [_, name] = words else {
+            bail!("Usage: unalias <NAME>");
+        };
+        if config.aliases.remove(name).is_none() {
+            bail!("There is no alias named '{name}'.");
+        }
+        println!("Removed alias '{name}'.");
+    } else {
+        let definition = words[1..].join(" ");
+        let Some((name, command)) = definition.split_once('=') else {
+            if config.aliases.is_empty() {
+                println!("No aliases yet. Create one with `alias <NAME>=<COMMAND>`.");
+            }
+            for (name, command) in &config.aliases {
+                if definition.is_empty() || definition == *name {
+                    println!("{name}={command}");
+                }
+            }
+            return Ok(());
+        };
+        let (name, command) = (name.trim(), command.trim());
+        let valid_name = name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+        if name.is_empty() || !valid_name {
+            bail!("Invalid alias name '{name}'. Use letters, numbers, '-', and '_'.");
+        }
+        if SHELL_BUILTINS.contains(&name) {
+            bail!("'{name}' is a shell command and can't be an alias.");
+        }
+        if split_words(command)?.is_empty() {
+            bail!("The alias needs a command, as in `alias {name}=today --compile`.");
+        }
+        config.aliases.insert(name.to_string(), command.to_string());
+        println!("Alias '{name}' set to '{command}'.");
+    }
+    helpers::save_config(&config)?;
+    *aliases = config.aliases;
+    Ok(())
+}
+
+/// A sticky filter set with `filter`, applied to every later `list`.
+#[derive(Default)]
+struct ListFilter {
+    pinned: bool,
+    tasks: bool,
+    tags: Vec<String>,
+}
+
+impl ListFilter {
+    /// Parses `list` flags such as `--tag rust --pinned` into a filter.
+    fn parse(args: &[String]) -> Result<ListFilter, clap::Error> {
+        let args = ["rjot", "list"]
+            .into_iter()
+            .map(String::from)
+            .chain(args.iter().cloned());
+        match Cli::try_parse_from(args)?.command {
+            Some(Commands::List {
+                count: None,
+                pinned,
+                tasks,
+                tag,
+            }) => Ok(ListFilter {
+                pinned,
+                tasks,
+                tags: tag,
+            }),
+            _ => Err(Cli::command().error(
+                clap::error::ErrorKind::ArgumentConflict,
+                "A filter can't include a count. Use `list <COUNT>` instead.",
+            )),
+        }
+    }
+
+    /// Describes the filter as `list` flags, or `None` if it is empty.
+    fn describe(&self) -> Option<String> {
+        let mut flags = Vec::new();
+        if self.pinned {
+            flags.push("--pinned".to_string());
+        }
+        if self.tasks {
+            flags.push("--tasks".to_string());
+        }
+        if !self.tags.is_empty() {
+            flags.push(format!("--tag {}", self.tags.join(",")));
+        }
+        (!flags.is_empty()).then(|| flags.join(" "))
+    }
+
+    /// Adds the filter to a `list` command. Other commands are returned unchanged.
+    fn apply(&self, command: Commands) -> Commands {
+        match command {
+            Commands::List {
+                count,
+                pinned,
+                tasks,
+                mut tag,
+            } => {
+                for extra in &self.tags {
+                    if !tag.contains(extra) {
+                        tag.push(extra.clone());
+                    }
+                }
+                Commands::List {
+                    count,
+                    pinned: pinned || self.pinned,
+                    tasks: tasks || self.tasks,
+                    tag,
+                }
+            }
+            other => other,
+        }
+    }
+}
+
+/// Turns `last [ACTION]` or `open <N> [ACTION]` into the command for that jot.
+fn jot_action(words: &[String]) -> Result<Vec<String>> {
+    let (path, rest) = if words[0] == "last" {
+        let path = helpers::last_created().context("No jot has been created in this shell yet.")?;
+        (path, &words[1..])
+    } else {
+        let n: usize = words
+            .get(1)
+            .and_then(|n| n.parse().ok())
+            .filter(|n| *n > 0)
+            .context("Usage: open <N> [ACTION], where N is a row of the last listing.")?;
+        let listing = helpers::last_listing();
+        if listing.is_empty() {
+            bail!("There is no listing to open from. Run `list`, `find`, or `today` first.");
+        }
+        let path = listing
+            .get(n - 1)
+            .cloned()
+            .with_context(|| format!("The last listing only has {} jot(s).", listing.len()))?;
+        (path, &words[2..])
+    };
+    let action = match rest {
+        [] => "show",
+        [action] if JOT_ACTIONS.contains(&action.as_str()) => action,
+        [action] => bail!(
+            "Unknown action '{action}'. Use one of: {}.",
+            JOT_ACTIONS.join(", ")
+        ),
+        _ => bail!("Too many arguments. Use `{} [ACTION]`.", words[0]),
+    };
+    let id = path.file_stem().unwrap_or_default().to_string_lossy();
+    if !path.exists() && !matches!(action, "history" | "diff" | "restore") {
+        bail!("The jot {id} no longer exists.");
+    }
+    let notebook = path
+        .parent()
+        .and_then(Path::file_name)
+        .unwrap_or_default()
+        .to_string_lossy();
+    Ok(vec![
+        action.to_string(),
+        id.to_string(),
+        "--notebook".to_string(),
+        notebook.to_string(),
+    ])
+}
+
+/// Reads the lines of a jot in compose mode, until a line with only `.` or Ctrl-D.
+///
+/// Returns `None` if Ctrl-C cancels the jot. The lines are not added to the history.
+fn read_composed_jot(rl: &mut Editor<RjotHelper, DefaultHistory>) -> Result<Option<String>> {
+    let mut lines = Vec::new();
+    loop {
+        match rl.readline("... ") {
+            Ok(line) if line.trim_end() == "." => break,
+            Ok(line) => lines.push(line),
+            Err(rustyline::error::ReadlineError::Eof) => break,
+            Err(rustyline::error::ReadlineError::Interrupted) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+    }
+    Ok(Some(lines.join("\n")))
+}
+
+/// A command entered in the shell, with the notebook that was active at the time.
+#[derive(Serialize, Deserialize)]
+struct HistoryEntry {
+    notebook: String,
+    line: String,
+}
+
+/// The shell's command history, kept in the rjot directory.
+///
+/// Each command is appended to the file as soon as it is entered, so history survives
+/// a crash and several shells can share it. Old entries are dropped on load.
+struct History {
+    path: PathBuf,
+    size: usize,
+    entries: Vec<HistoryEntry>,
+}
+
+impl History {
+    /// Loads the most recent `size` entries.
+    fn load(size: usize) -> Result<History> {
+        let path = helpers::get_rjot_dir_root()?.join(helpers::HISTORY_FILE);
+        let entries = match fs::read_to_string(&path) {
+            Ok(content) => content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).with_context(|| format!("Could not read {path:?}")),
+        };
+        let mut history = History {
+            path,
+            size,
+            entries,
+        };
+        if history.entries.len() > size {
+            history.trim()?;
+        }
+        Ok(history)
+    }
+
+    /// Appends a command to the history.
+    fn record(&mut self, notebook: &str, line: &str) -> Result<()> {
+        if self.size == 0 {
+            return Ok(());
+        }
+        let entry = HistoryEntry {
+            notebook: notebook.to_string(),
+            line: line.to_string(),
+        };
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        self.entries.push(entry);
+        // Rewriting on every command would be wasteful, so let a long session run over a bit.
+        if self.entries.len() > self.size * 2 {
+            self.trim()?;
+        }
+        Ok(())
+    }
+
+    /// Drops the oldest entries beyond the size limit and rewrites the file.
+    fn trim(&mut self) -> Result<()> {
+        let excess = self.entries.len().saturating_sub(self.size);
+        self.entries.drain(..excess);
+        let mut content = String::new();
+        for entry in &self.entries {
+            content.push_str(&serde_json::to_string(entry)?);
+            content.push('\n');
+        }
+        fs::write(&self.path, content)?;
+        Ok(())
+    }
+
+    /// Loads the entries into the line editor, only those for `notebook` if one is given.
+    fn fill(
+        &self,
+        rl: &mut Editor<RjotHelper, DefaultHistory>,
+        notebook: Option<&str>,
+    ) -> Result<()> {
+        rl.clear_history()?;
+        for entry in &self.entries {
+            if notebook.is_none_or(|notebook| entry.notebook == notebook) {
+                rl.add_history_entry(entry.line.as_str())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Points the shell's completion and, if it is kept per notebook, its history at a
+/// newly active notebook.
+fn follow_notebook(
+    rl: &mut Editor<RjotHelper, DefaultHistory>,
+    history: &History,
+    per_notebook: bool,
+    notebook: &str,
+) -> Result<()> {
+    if let Some(helper) = rl.helper_mut() {
+        helper.completion.active_notebook = notebook.to_string();
+    }
+    if per_notebook {
+        history.fill(rl, Some(notebook))?;
+    }
+    Ok(())
+}
+
+/// Whether a shell command writes a jot body into an encrypted notebook.
+///
+/// Such commands are kept out of the history file, which is never encrypted. When in
+/// doubt, for example if the notebook can't be found or the line doesn't parse, this errs
+/// on the side of `true`.
+fn writes_encrypted_jot(words: &[String], active_notebook: &str) -> bool {
+    if words
+        .first()
+        .is_some_and(|word| SHELL_BUILTINS.contains(&word.as_str()))
+    {
+        return false;
+    }
+    let encrypted = |notebook: Option<String>| {
+        helpers::get_active_entries_dir(notebook.or(Some(active_notebook.to_string())))
+            .and_then(|dir| helpers::resolve_recipient(&dir))
+            .map_or(true, |recipient| recipient.is_some())
+    };
+    let args: Vec<String> = std::iter::once("rjot".to_string())
+        .chain(words.iter().cloned())
+        .collect();
+    let Ok(cli) = Cli::try_parse_from(&args) else {
+        // A mistyped line may still hold a jot body, so keep it out if it could be meant
+        // for an encrypted notebook. Parsing leniently still finds `--notebook` in any of
+        // the forms clap accepts.
+        let Ok(matches) = Cli::command()
+            .ignore_errors(true)
+            .try_get_matches_from(&args)
+        else {
+            return true;
+        };
+        let named = matches.get_one::<String>("notebook").cloned();
+        return encrypted(None) || encrypted(named);
+    };
+    let writes_body = match cli.command {
+        None => !cli.message.is_empty(),
+        Some(Commands::Task { .. }) => true,
+        Some(_) => false,
+    };
+    writes_body && encrypted(cli.notebook)
+}
+
+/// Whether a flag is followed by a value, as opposed to a switch like `--force`.
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_action().takes_values()
+}
+
+/// Whether an argument accepts several values, like the tags after `tags`.
+fn takes_many_values(arg: &Arg) -> bool {
+    arg.get_num_args()
+        .is_some_and(|range| range.max_values() > 1)
+}
+
+/// Returns the values that an argument can take, based on what it is for.
+fn arg_values(
+    arg: &Arg,
+    path: &[&str],
+    notebook: Option<&str>,
+    completion: &Completion,
+) -> Vec<String> {
+    let possible: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    if !possible.is_empty() {
+        return possible;
+    }
+    match (arg.get_id().as_str(), path) {
+        ("id_prefix", _) => jot_ids(&completion.notebook_dir(notebook)),
+        ("tags" | "tag", _) => tag_names(&completion.notebook_dir(notebook)),
+        ("template", _) => template_names(completion),
+        ("name", ["template", action]) if *action != "new" => template_names(completion),
+        ("name", ["notebook", action]) if *action != "new" => notebook_names(completion),
+        ("notebook" | "to" | "notebook_name" | "old" | "source" | "destination", _) => {
+            notebook_names(completion)
+        }
+        ("key", ["config", _]) => helpers::PREFERENCE_KEYS.map(String::from).to_vec(),
+        _ => Vec::new(),
+    }
+}
+
+/// Keeps the candidates that start with the word being completed.
+fn matching(candidates: Vec<String>, current: &str) -> Vec<String> {
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(current))
+        .collect()
+}
+
+/// The names of all notebooks, including archived ones.
+fn notebook_names(completion: &Completion) -> Vec<String> {
+    helpers::list_notebooks_in(&completion.root.join("notebooks"), true)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect()
+}
+
+/// The IDs of the jots in a notebook.
+fn jot_ids(notebook_dir: &Path) -> Vec<String> {
+    helpers::list_note_paths(notebook_dir)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|path| path.file_stem())
+        .map(|stem| stem.to_string_lossy().to_string())
+        .collect()
+}
+
+/// The tags used in a notebook.
+fn tag_names(notebook_dir: &Path) -> Vec<String> {
+    commands::calculate_stats_for_dir(notebook_dir)
+        .map(|(_, tags, _)| tags.into_keys().collect())
+        .unwrap_or_default()
+}
+
+/// The names of all templates.
+fn template_names(completion: &Completion) -> Vec<String> {
+    let mut names = Vec::new();
+    let _ = commands::template_names(&completion.root.join("templates"), "", &mut names);
+    names
+}
+
+/// Enters the interactive rjot shell.
+pub fn command_shell() -> Result<()> {
+    const VERSION: &str = env!("CARGO_PKG_VERSION");
+    let mut active_notebook = helpers::active_notebook_name()?;
+    // The notebook picked with the `use` builtin. It only applies to this shell, so it
+    // is passed to each command like `--notebook` instead of being saved anywhere.
+    let mut shell_notebook: Option<String> = None;
+
+    let entries_dir = helpers::get_active_entries_dir(Some(active_notebook.clone()))?;
+    let preferences = helpers::load_preferences(Some(&entries_dir))?;
+    let (note_count, _, _) = commands::calculate_stats_for_dir(&entries_dir).unwrap_or((
+        0,
+        Default::default(),
+        Default::default(),
+    ));
+
+    let tips = [
+        // Shell Tips
+        "In the shell, type `use <name>` and press Tab to autocomplete notebook names.",
+        "Press Tab after `show`, `edit`, or `pin` to complete jot IDs.",
+        "Type `write` to compose a multi-line jot without leaving the shell.",
+        "Use the Up/Down arrow keys in the shell to navigate your command history.",
+        "You can exit the shell at any time with `exit`, `quit`, or by pressing Ctrl-D.",
+        // Basic Usage Tips
+        "The `t` command is a fast alias for `task`. Try `t 'My new task'`.",
+        "You can use `rm` as a shorter alias for the `delete` command.",
+        "Tags can be comma-separated (`-t a,b`) or space-separated (`-t a b`).",
+        // Advanced Viewing & Filtering
+        "Filter for a date range like this: `on 2025-01-01..2025-01-31`.",
+        "Compile a full week's notes into a single file with `week --compile > summary.md`.",
+        "Pin important notes with `pin <ID>` and view them with `list --pinned`.",
+        "Find notes with multiple tags, like `tags rust,project`.",
+        // Note Management
+        "You can edit the last jot you created instantly with `edit --last`.",
+        "The `--force` flag on `delete` and `decrypt` will skip confirmation prompts.",
+        "Use a unique prefix of a jot's ID for any command, like `show 2025-07-21`.",
+        // Configuration & Templates
+        "Create custom note structures for `new` by adding files to your templates directory.",
+        "Find your templates folder and other important paths with `info --paths`.",
+        "Pass custom variables to your templates with the `-v` flag, like `new -t bug -v id=123`.",
+        // Notebooks & Syncing
+        "Run a single command in another notebook with the global `--notebook <name>` flag.",
+        "Use `notebook status` to quickly check which notebook is active.",
+        "After setting up a git remote, use `sync` to commit and push all changes.",
+    ];
+    let mut rng = rand::thread_rng();
+    let tip = tips[rng.gen_range(0..tips.len())];
+
+    // Use the new oh-my-logo generated ASCII art
+    let startup_message = format!(
+        "\n\
+        \x1b[38;5;208m ██████╗       ██╗  ██████╗  ████████╗\x1b[0m\n\
+        \x1b[38;5;209m ██╔══██╗      ██║ ██╔═══██╗ ╚══██╔══╝\x1b[0m\n\
+        \x1b[38;5;210m ██████╔╝      ██║ ██║   ██║    ██║   \x1b[0m\n\
+        \x1b[38;5;211m ██╔══██╗ ██   ██║ ██║   ██║    ██║   \x1b[0m\n\
+        \x1b[38;5;212m ██║  ██║ ╚█████╔╝ ╚██████╔╝    ██║   \x1b[0m\n\
+        \x1b[38;5;213m ╚═╝  ╚═╝  ╚════╝   ╚═════╝     ╚═╝   \x1b[0m\n\
+        \n  \x1b[0;1mrjot v{}\x1b[0m | Today: \x1b[32m{}\x1b[0m | Stats: \x1b[33m{} notes in '{}'\x1b[0m\n  \
+        \x1b[2mTip: {}\x1b[0m\n  \
+        \x1b[2mType 'exit' or 'quit' to leave the shell.\x1b[0m\n",
+        VERSION,
+        preferences.now()?.format(preferences.date_format()),
+        note_count,
+        active_notebook,
+        tip
+    );
+
+    let startup_message = if preferences.color() {
+        startup_message
+    } else {
+        helpers::strip_ansi(&startup_message)
+    };
+
+    let helper = RjotHelper {
+        completion: Completion {
+            root: helpers::get_rjot_dir_root()?,
+            active_notebook: active_notebook.clone(),
+        },
+    };
+    let mut rl = Editor::new()?;
+    rl.set_helper(Some(helper));
+    rl.set_completion_type(CompletionType::List);
+
+    let mut history = History::load(preferences.history_size())?;
+    rl.set_max_history_size(preferences.history_size().max(1))?;
+    let history_filter = preferences.history_per_notebook();
+    history.fill(&mut rl, history_filter.then_some(active_notebook.as_str()))?;
+
+    println!("{startup_message}");
+
+    let mut aliases = helpers::load_config()?.aliases;
+    let mut list_filter = ListFilter::default();
+
+    loop {
+        let readline = rl.readline(&render_prompt(&active_notebook));
+
+        match readline {
+            Ok(line) => {
+                let _ = rl.add_history_entry(line.as_str());
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                let mut words = match split_words(line).and_then(|w| expand_alias(w, &aliases)) {
+                    Ok(words) => words,
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        continue;
+                    }
+                };
+                if !writes_encrypted_jot(&words, &active_notebook) {
+                    if let Err(e) = history.record(&active_notebook, line) {
+                        eprintln!("Warning: Could not save shell history: {e}");
+                    }
+                }
+                let command_name = words.first().map_or("", String::as_str);
+                match command_name {
+                    "exit" | "quit" => break,
+                    "write" | ":compose" => {
+                        if words.len() > 1 {
+                            eprintln!("Usage: write (then type the jot on the following lines)");
+                            continue;
+                        }
+                        println!(
+                            "Writing a jot in '{active_notebook}'. #tags become tags. \
+                             End with a line containing only '.', or press Ctrl-D. Ctrl-C cancels."
+                        );
+                        let result = read_composed_jot(&mut rl).and_then(|text| match text {
+                            Some(text) => helpers::get_active_entries_dir(shell_notebook.clone())
+                                .and_then(|entries_dir| {
+                                    commands::command_compose(&entries_dir, &text)
+                                }),
+                            None => {
+                                println!("Jot discarded.");
+                                Ok(())
+                            }
+                        });
+                        if let Err(e) = result {
+                            eprintln!("Error: {e}");
+                        }
+                        continue;
+                    }
+                    "alias" | "unalias" => {
+                        if let Err(e) = update_aliases(&words, &mut aliases) {
+                            eprintln!("Error: {e}");
+                        }
+                        continue;
+                    }
+                    "filter" => {
+                        match words.get(1).map(String::as_str) {
+                            None => {}
+                            Some("off" | "clear") if words.len() == 2 => {
+                                list_filter = ListFilter::default()
+                            }
+                            Some(_) => match ListFilter::parse(&words[1..]) {
+                                Ok(filter) => list_filter = filter,
+                                Err(e) => {
+                                    e.print().unwrap_or_default();
+                                    continue;
+                                }
+                            },
+                        }
+                        match list_filter.describe() {
+                            Some(filter) => println!("Filtering lists with: {filter}"),
+                            None => println!("No list filter. Set one with `filter --tag <TAG>`."),
+                        }
+                        continue;
+                    }
+                    "last" | "open" => match jot_action(&words) {
+                        Ok(action) => words = action,
+                        Err(e) => {
+                            eprintln!("Error: {e}");
+                            continue;
+                        }
+                    },
+                    "use" => {
+                        if let Some(name) = words.get(1) {
+                            match commands::existing_notebook_dir(name) {
+                                Ok(_) => {
+                                    shell_notebook = Some(name.to_string());
+                                    active_notebook = name.to_string();
+                                    follow_notebook(
+                                        &mut rl,
+                                        &history,
+                                        history_filter,
+                                        &active_notebook,
+                                    )?;
+                                    println!("Active notebook switched to '{active_notebook}'.");
+                                }
+                                Err(e) => eprintln!("Error: {e}"),
+                            }
+                        } else {
+                            eprintln!("Usage: use <NOTEBOOK_NAME>");
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+
+                let args = std::iter::once("rjot".to_string()).chain(words);
+                match Cli::try_parse_from(args) {
+                    Ok(cli) => {
+                        let manages_notebooks = match &cli.command {
+                            Some(Commands::Notebook(args)) => {
+                                if matches!(args.action, NotebookAction::Use { .. }) {
+                                    // Switching with `notebook use` replaces the shell's choice.
+                                    shell_notebook = None;
+                                }
+                                true
+                            }
+                            _ => false,
+                        };
+                        let notebook = cli.notebook.or_else(|| shell_notebook.clone());
+                        let flags =
+                            helpers::DisplayFlags::apply(cli.no_color, cli.columns.as_deref());
+                        let result = flags.and_then(|_flags| match cli.command {
+                            Some(command) => {
+                                crate::run_command(list_filter.apply(command), notebook)
+                            }
+                            None if !cli.message.is_empty() => {
+                                let message = cli.message.join(" ");
+                                helpers::get_active_entries_dir(notebook).and_then(|entries_dir| {
+                                    commands::command_down(&entries_dir, &message, cli.tags)
+                                })
+                            }
+                            None => Ok(()),
+                        });
+                        if let Err(e) = result {
+                            eprintln!("Error: {e}");
+                        }
+                        if manages_notebooks {
+                            // A rename, merge, or delete can remove the shell's notebook, and
+                            // `notebook use` changes the saved one, so look it up again.
+                            if shell_notebook.as_deref().is_some_and(|name| {
+                                !matches!(helpers::existing_notebook_path(name), Ok(Some(_)))
+                            }) {
+                                shell_notebook = None;
+                            }
+                            let current = match &shell_notebook {
+                                Some(name) => name.clone(),
+                                None => helpers::active_notebook_name()
+                                    .unwrap_or_else(|_| active_notebook.clone()),
+                            };
+                            if current != active_notebook {
+                                active_notebook = current;
+                                follow_notebook(
+                                    &mut rl,
+                                    &history,
+                                    history_filter,
+                                    &active_notebook,
+                                )?;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        e.print().unwrap_or_default();
+                    }
+                }
+            }
+            Err(rustyline::error::ReadlineError::Interrupted) => {
+                println!("\nInterrupted (Ctrl-C). Type 'exit' or press Ctrl-D to leave.");
+            }
+            Err(rustyline::error::ReadlineError::Eof) => {
+                break;
+            }
+            Err(err) => {
+                println!("Shell Error: {err:?}");
+                break;
+            }
+        }
+    }
+
+    println!("Exiting rjot shell.");
+    Ok(())
+}
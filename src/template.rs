@@ -0,0 +1,910 @@
+//! A small template language for `rjot new --template`.
+//!
+//! Templates are Markdown files with Jinja-style tags:
+//!
+//! - `{{ name }}` prints a variable. Filters transform values, as in
+//!   `{{ date | format("%A, %B %d") }}` or `{{ ticket | default("none") }}`.
+//! - `{% if name %}...{% elif other %}...{% else %}...{% endif %}` and
+//!   `{% for item in list %}...{% endfor %}` control what is printed.
+//! - `{% include "partial" %}` inserts another template.
+//! - `{# ... #}` is a comment.
+//! - `{{ cmd:some command }}` prints the output of a command, if the template allows it.
+//!
+//! Printing a variable that has no value asks the [`Environment`] for one, which
+//! `rjot new` uses to prompt the user. Conditions and loops treat missing variables
+//! as empty instead.
+//!
+//! A template can also declare settings for the jots made from it in a `template:`
+//! block of its frontmatter; see [`TemplateMeta`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::Deserialize;
+
+/// How deeply `{% include %}` tags may nest before we assume a cycle.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A value that a template variable can hold.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Str(String),
+    List(Vec<Value>),
+    Date(DateTime<FixedOffset>),
+    Bool(bool),
+}
+
+impl Value {
+    /// Empty strings, empty lists, and `false` are falsy; everything else is truthy.
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Str(s) => !s.is_empty(),
+            Value::List(items) => !items.is_empty(),
+            Value::Date(_) => true,
+            Value::Bool(b) => *b,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Str(s) => f.write_str(s),
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_string).collect();
+                f.write_str(&items.join(", "))
+            }
+            Value::Date(date) => f.write_str(&date.to_rfc3339()),
+            Value::Bool(b) => write!(f, "{b}"),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Str(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Str(s)
+    }
+}
+
+/// The variables available to a template, by name.
+pub type Variables = HashMap<String, Value>;
+
+/// Supplies what a template needs from outside: included templates and missing values.
+pub trait Environment {
+    /// Returns the source of the template included as `name`.
+    fn load(&self, name: &str) -> Result<String>;
+
+    /// Returns a value for a printed variable that isn't set.
+    fn missing(&mut self, name: &str) -> Result<Value>;
+
+    /// Runs a `{{ cmd:... }}` command and returns its output.
+    fn command(&mut self, command: &str) -> Result<String>;
+}
+
+/// Renders a template. Values supplied by [`Environment::missing`] are added to `variables`.
+pub fn render(
+    source: &str,
+    variables: &mut Variables,
+    env: &mut dyn Environment,
+) -> Result<String> {
+    let mut renderer = Renderer { env, depth: 0 };
+    let mut out = String::new();
+    renderer.render_source(source, variables, &mut out)?;
+    Ok(out)
+}
+
+// --- Parsing ---
+
+/// A piece of template source, before block tags are matched up.
+#[derive(Debug)]
+enum Token {
+    Text(String),
+    Print(String, usize),
+    Tag(String, usize),
+}
+
+/// Splits a template into text, `{{ }}` and `{% %}` tokens, dropping comments.
+///
+/// A block tag or comment that sits on a line of its own takes the whole line with
+/// it, so control flow doesn't leave blank lines behind.
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut line = 1;
+    // Whether the next text starts at the beginning of a line.
+    let mut at_line_start = true;
+    loop {
+        let next = ["{{", "{%", "{#"]
+            .iter()
+            .filter_map(|open| rest.find(open).map(|i| (i, *open)))
+            .min();
+        let Some((start, open)) = next else {
+            if !rest.is_empty() {
+                tokens.push(Token::Text(rest.to_string()));
+            }
+            return Ok(tokens);
+        };
+
+        let mut text = rest[..start].to_string();
+        line += text.matches('\n').count();
+        let close = match open {
+            "{{" => "}}",
+            "{%" => "%}",
+            _ => "#}",
+        };
+        let inner_start = start + open.len();
+        let end = rest[inner_start..]
+            .find(close)
+            .ok_or_else(|| anyhow!("Line {line}: `{open}` is never closed with `{close}`."))?;
+        let inner = &rest[inner_start..inner_start + end];
+        rest = &rest[inner_start + end + close.len()..];
+
+        if open != "{{" {
+            let line_start = text.rfind('\n').map_or(0, |i| i + 1);
+            let starts_line = line_start > 0 || at_line_start;
+            let indent_only = text[line_start..].chars().all(|c| c == ' ' || c == '\t');
+            let ends_line = rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n");
+            at_line_start = starts_line && indent_only && ends_line;
+            if at_line_start {
+                text.truncate(line_start);
+                rest = rest.strip_prefix('\r').unwrap_or(rest);
+                rest = rest.strip_prefix('\n').unwrap_or(rest);
+            }
+        } else {
+            at_line_start = false;
+        }
+        if !text.is_empty() {
+            tokens.push(Token::Text(text));
+        }
+        match open {
+            "{{" => tokens.push(Token::Print(inner.trim().to_string(), line)),
+            "{%" => tokens.push(Token::Tag(inner.trim().to_string(), line)),
+            _ => {}
+        }
+        line += inner.matches('\n').count() + usize::from(at_line_start);
+    }
+}
+
+/// A parsed template element.
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Print(Expr, usize),
+    If {
+        branches: Vec<(Expr, Vec<Node>)>,
+        otherwise: Vec<Node>,
+        line: usize,
+    },
+    For {
+        name: String,
+        iterable: Expr,
+        body: Vec<Node>,
+        line: usize,
+    },
+    Include(Expr, usize),
+    Command(String, usize),
+}
+
+/// An expression inside `{{ }}` or a block tag.
+#[derive(Debug)]
+enum Expr {
+    Var(String),
+    Literal(Value),
+    Filter {
+        value: Box<Expr>,
+        name: String,
+        args: Vec<Expr>,
+    },
+    Not(Box<Expr>),
+    Compare {
+        left: Box<Expr>,
+        right: Box<Expr>,
+        equal: bool,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+/// Parses a template into a tree of nodes.
+fn parse(source: &str) -> Result<Vec<Node>> {
+    let mut tokens = tokenize(source)?.into_iter();
+    let (nodes, end) = parse_nodes(&mut tokens, &[])?;
+    debug_assert!(end.is_none());
+    Ok(nodes)
+}
+
+/// The tag that ended a block of nodes, such as `{% else %}` or `{% endif %}`.
+struct EndTag {
+    keyword: String,
+    args: String,
+    line: usize,
+}
+
+/// Parses nodes until one of `end_tags` or the end of the template.
+fn parse_nodes(
+    tokens: &mut std::vec::IntoIter<Token>,
+    end_tags: &[&str],
+) -> Result<(Vec<Node>, Option<EndTag>)> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text)),
+            Token::Print(source, line) if source.starts_with("cmd:") => {
+                let command = source["cmd:".len()..].trim();
+                if command.is_empty() {
+                    bail!("Line {line}: `{{{{ cmd: }}}}` needs a command to run.");
+                }
+                nodes.push(Node::Command(command.to_string(), line));
+            }
+            Token::Print(source, line) => {
+                let expr = parse_expr(&source).with_context(|| format!("Line {line}"))?;
+                nodes.push(Node::Print(expr, line));
+            }
+            Token::Tag(source, line) => {
+                let (keyword, args) = source
+                    .split_once(char::is_whitespace)
+                    .map_or((source.as_str(), ""), |(k, a)| (k, a.trim()));
+                if end_tags.contains(&keyword) {
+                    let end = EndTag {
+                        keyword: keyword.to_string(),
+                        args: args.to_string(),
+                        line,
+                    };
+                    return Ok((nodes, Some(end)));
+                }
+                let node = match keyword {
+                    "if" => parse_if(tokens, args, line)?,
+                    "for" => parse_for(tokens, args, line)?,
+                    "include" => {
+                        let expr = parse_expr(args).with_context(|| format!("Line {line}"))?;
+                        Node::Include(expr, line)
+                    }
+                    "elif" | "else" | "endif" | "endfor" => {
+                        bail!("Line {line}: `{{% {keyword} %}}` without a matching opening tag.")
+                    }
+                    _ => bail!("Line {line}: Unknown tag `{{% {keyword} %}}`."),
+                };
+                nodes.push(node);
+            }
+        }
+    }
+    if let Some(end) = end_tags.last() {
+        bail!("Missing `{{% {end} %}}` at the end of the template.");
+    }
+    Ok((nodes, None))
+}
+
+/// Parses the rest of an `{% if %}` block.
+fn parse_if(tokens: &mut std::vec::IntoIter<Token>, condition: &str, line: usize) -> Result<Node> {
+    let mut branches = Vec::new();
+    let mut condition = parse_expr(condition).with_context(|| format!("Line {line}"))?;
+    loop {
+        let (body, end) = parse_nodes(tokens, &["elif", "else", "endif"])?;
+        let end = end.expect("parse_nodes fails without an end tag");
+        branches.push((condition, body));
+        match end.keyword.as_str() {
+            "elif" => {
+                condition = parse_expr(&end.args).with_context(|| format!("Line {}", end.line))?;
+            }
+            "else" => {
+                let (otherwise, _) = parse_nodes(tokens, &["endif"])?;
+                return Ok(Node::If {
+                    branches,
+                    otherwise,
+                    line,
+                });
+            }
+            _ => {
+                return Ok(Node::If {
+                    branches,
+                    otherwise: Vec::new(),
+                    line,
+                })
+            }
+        }
+    }
+}
+
+/// Parses the rest of a `{% for name in iterable %}` block.
+fn parse_for(tokens: &mut std::vec::IntoIter<Token>, args: &str, line: usize) -> Result<Node> {
+    let (name, iterable) = args
+        .split_once(" in ")
+        .map(|(name, iterable)| (name.trim(), iterable.trim()))
+        .filter(|(name, _)| is_identifier(name))
+        .ok_or_else(|| anyhow!("Line {line}: Expected `{{% for <name> in <list> %}}`."))?;
+    let iterable = parse_expr(iterable).with_context(|| format!("Line {line}"))?;
+    let (body, _) = parse_nodes(tokens, &["endfor"])?;
+    Ok(Node::For {
+        name: name.to_string(),
+        iterable,
+        body,
+        line,
+    })
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with(|c: char| c.is_ascii_digit())
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
+
+/// A lexical token inside an expression.
+#[derive(Debug, PartialEq)]
+enum ExprToken {
+    Ident(String),
+    Str(String),
+    Pipe,
+    LParen,
+    RParen,
+    Comma,
+    Eq,
+    Ne,
+}
+
+fn lex_expr(source: &str) -> Result<Vec<ExprToken>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '|' => {
+                chars.next();
+                tokens.push(ExprToken::Pipe);
+            }
+            '(' => {
+                chars.next();
+                tokens.push(ExprToken::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(ExprToken::RParen);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(ExprToken::Comma);
+            }
+            '=' | '!' => {
+                chars.next();
+                if chars.next_if_eq(&'=').is_none() {
+                    bail!("Expected `==` or `!=` in `{source}`.");
+                }
+                tokens.push(if c == '=' {
+                    ExprToken::Eq
+                } else {
+                    ExprToken::Ne
+                });
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('\\') => match chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(other) => s.push(other),
+                            None => bail!("Unterminated string in `{source}`."),
+                        },
+                        Some(q) if q == c => break,
+                        Some(other) => s.push(other),
+                        None => bail!("Unterminated string in `{source}`."),
+                    }
+                }
+                tokens.push(ExprToken::Str(s));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' => {
+                let mut word = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || "_-.".contains(*c)) {
+                    word.push(c);
+                }
+                if word.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+                    // Numbers are plain text as far as templates are concerned.
+                    tokens.push(ExprToken::Str(word));
+                } else {
+                    tokens.push(ExprToken::Ident(word));
+                }
+            }
+            other => bail!("Unexpected character '{other}' in `{source}`."),
+        }
+    }
+    Ok(tokens)
+}
+
+/// A recursive-descent parser over expression tokens.
+struct ExprParser {
+    tokens: Vec<ExprToken>,
+    pos: usize,
+}
+
+fn parse_expr(source: &str) -> Result<Expr> {
+    let mut parser = ExprParser {
+        tokens: lex_expr(source)?,
+        pos: 0,
+    };
+    if parser.tokens.is_empty() {
+        bail!("Empty expression.");
+    }
+    let expr = parser.or()?;
+    if parser.pos < parser.tokens.len() {
+        bail!("Unexpected {:?} in `{source}`.", parser.tokens[parser.pos]);
+    }
+    Ok(expr)
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&ExprToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, token: &ExprToken) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.eat(&ExprToken::Ident(keyword.to_string()))
+    }
+
+    fn or(&mut self) -> Result<Expr> {
+        let mut expr = self.and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        let left = self.pipe()?;
+        for (token, equal) in [(ExprToken::Eq, true), (ExprToken::Ne, false)] {
+            if self.eat(&token) {
+                return Ok(Expr::Compare {
+                    left: Box::new(left),
+                    right: Box::new(self.pipe()?),
+                    equal,
+                });
+            }
+        }
+        Ok(left)
+    }
+
+    fn pipe(&mut self) -> Result<Expr> {
+        let mut expr = self.primary()?;
+        while self.eat(&ExprToken::Pipe) {
+            let Some(ExprToken::Ident(name)) = self.tokens.get(self.pos) else {
+                bail!("Expected a filter name after `|`.");
+            };
+            let name = name.clone();
+            self.pos += 1;
+            let mut args = Vec::new();
+            if self.eat(&ExprToken::LParen) && !self.eat(&ExprToken::RParen) {
+                loop {
+                    args.push(self.or()?);
+                    if self.eat(&ExprToken::RParen) {
+                        break;
+                    }
+                    if !self.eat(&ExprToken::Comma) {
+                        bail!("Expected `,` or `)` in the arguments to `{name}`.");
+                    }
+                }
+            }
+            expr = Expr::Filter {
+                value: Box::new(expr),
+                name,
+                args,
+            };
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        match token {
+            Some(ExprToken::Ident(name)) if name == "true" || name == "false" => {
+                Ok(Expr::Literal(Value::Bool(name == "true")))
+            }
+            Some(ExprToken::Ident(name)) => Ok(Expr::Var(name.clone())),
+            Some(ExprToken::Str(s)) => Ok(Expr::Literal(Value::Str(s.clone()))),
+            Some(ExprToken::LParen) => {
+                let expr = self.or()?;
+                if !self.eat(&ExprToken::RParen) {
+                    bail!("Expected `)`.");
+                }
+                Ok(expr)
+            }
+            Some(other) => bail!("Unexpected {other:?}."),
+            None => bail!("Unexpected end of expression."),
+        }
+    }
+}
+
+// --- Rendering ---
+
+struct Renderer<'a> {
+    env: &'a mut dyn Environment,
+    depth: usize,
+}
+
+impl Renderer<'_> {
+    fn render_source(
+        &mut self,
+        source: &str,
+        variables: &mut Variables,
+        out: &mut String,
+    ) -> Result<()> {
+        let nodes = parse(source)?;
+        self.render_nodes(&nodes, variables, out)
+    }
+
+    fn render_nodes(
+        &mut self,
+        nodes: &[Node],
+        variables: &mut Variables,
+        out: &mut String,
+    ) -> Result<()> {
+        for node in nodes {
+            match node {
+                Node::Text(text) => out.push_str(text),
+                Node::Print(expr, line) => {
+                    let value = self
+                        .eval(expr, variables, true)
+                        .with_context(|| format!("Line {line}"))?;
+                    if let Some(value) = value {
+                        out.push_str(&value.to_string());
+                    }
+                }
+                Node::If {
+                    branches,
+                    otherwise,
+                    line,
+                } => {
+                    let mut chosen = otherwise;
+                    for (condition, body) in branches {
+                        let value = self
+                            .eval(condition, variables, false)
+                            .with_context(|| format!("Line {line}"))?;
+                        if value.is_some_and(|v| v.is_truthy()) {
+                            chosen = body;
+                            break;
+                        }
+                    }
+                    self.render_nodes(chosen, variables, out)?;
+                }
+                Node::For {
+                    name,
+                    iterable,
+                    body,
+                    line,
+                } => {
+                    let items = match self
+                        .eval(iterable, variables, false)
+                        .with_context(|| format!("Line {line}"))?
+                    {
+                        None => Vec::new(),
+                        Some(Value::List(items)) => items,
+                        Some(_) => bail!(
+                            "Line {line}: `{{% for %}}` needs a list. Use `| split(\",\")` to turn text into one."
+                        ),
+                    };
+                    let count = items.len();
+                    let saved: Vec<(String, Option<Value>)> =
+                        [name.as_str(), "loop.index", "loop.first", "loop.last"]
+                            .into_iter()
+                            .map(|key| (key.to_string(), variables.get(key).cloned()))
+                            .collect();
+                    for (i, item) in items.into_iter().enumerate() {
+                        variables.insert(name.clone(), item);
+                        variables.insert("loop.index".into(), Value::Str((i + 1).to_string()));
+                        variables.insert("loop.first".into(), Value::Bool(i == 0));
+                        variables.insert("loop.last".into(), Value::Bool(i + 1 == count));
+                        self.render_nodes(body, variables, out)?;
+                    }
+                    for (key, value) in saved {
+                        match value {
+                            Some(value) => variables.insert(key, value),
+                            None => variables.remove(&key),
+                        };
+                    }
+                }
+                Node::Include(expr, line) => {
+                    let name = self
+                        .eval(expr, variables, false)
+                        .with_context(|| format!("Line {line}"))?
+                        .ok_or_else(|| anyhow!("Line {line}: The included template has no name."))?
+                        .to_string();
+                    if self.depth >= MAX_INCLUDE_DEPTH {
+                        bail!("Line {line}: Templates are included too deeply. Does '{name}' include itself?");
+                    }
+                    let source = self.env.load(&name)?;
+                    self.depth += 1;
+                    self.render_source(&source, variables, out)
+                        .with_context(|| format!("In included template '{name}'"))?;
+                    self.depth -= 1;
+                }
+                Node::Command(command, line) => {
+                    let output = self
+                        .env
+                        .command(command)
+                        .with_context(|| format!("Line {line}"))?;
+                    out.push_str(&output);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluates an expression. `None` means a variable was missing.
+    ///
+    /// With `prompt` set, missing variables are requested from the environment.
+    fn eval(
+        &mut self,
+        expr: &Expr,
+        variables: &mut Variables,
+        prompt: bool,
+    ) -> Result<Option<Value>> {
+        Ok(match expr {
+            Expr::Literal(value) => Some(value.clone()),
+            Expr::Var(name) => match variables.get(name) {
+                Some(value) => Some(value.clone()),
+                None if prompt => {
+                    let value = self.env.missing(name)?;
+                    variables.insert(name.clone(), value.clone());
+                    Some(value)
+                }
+                None => None,
+            },
+            Expr::Filter { value, name, args } if name == "default" => {
+                let [fallback] = args.as_slice() else {
+                    bail!("`default` takes one argument.");
+                };
+                match self.eval(value, variables, false)? {
+                    Some(value) if value.is_truthy() => Some(value),
+                    _ => self.eval(fallback, variables, prompt)?,
+                }
+            }
+            Expr::Filter { value, name, args } => {
+                let Some(value) = self.eval(value, variables, prompt)? else {
+                    return Ok(None);
+                };
+                let mut arg_values = Vec::new();
+                for arg in args {
+                    let arg = self
+                        .eval(arg, variables, prompt)?
+                        .ok_or_else(|| anyhow!("Missing argument to `{name}`."))?;
+                    arg_values.push(arg);
+                }
+                Some(apply_filter(name, value, &arg_values)?)
+            }
+            Expr::Not(inner) => Some(Value::Bool(
+                !self
+                    .eval(inner, variables, false)?
+                    .is_some_and(|v| v.is_truthy()),
+            )),
+            Expr::Compare { left, right, equal } => {
+                let left = self.eval(left, variables, false)?.map(|v| v.to_string());
+                let right = self.eval(right, variables, false)?.map(|v| v.to_string());
+                Some(Value::Bool((left == right) == *equal))
+            }
+            Expr::And(left, right) => {
+                let left = self.eval(left, variables, false)?;
+                let right = self.eval(right, variables, false)?;
+                Some(Value::Bool(
+                    left.is_some_and(|v| v.is_truthy()) && right.is_some_and(|v| v.is_truthy()),
+                ))
+            }
+            Expr::Or(left, right) => {
+                let left = self.eval(left, variables, false)?;
+                let right = self.eval(right, variables, false)?;
+                Some(Value::Bool(
+                    left.is_some_and(|v| v.is_truthy()) || right.is_some_and(|v| v.is_truthy()),
+                ))
+            }
+        })
+    }
+}
+
+/// Applies a filter other than `default`, which needs to see missing values.
+fn apply_filter(name: &str, value: Value, args: &[Value]) -> Result<Value> {
+    let text_arg = |i: usize| -> Result<String> {
+        args.get(i)
+            .map(Value::to_string)
+            .ok_or_else(|| anyhow!("`{name}` takes {} argument(s).", i + 1))
+    };
+    Ok(match name {
+        "format" => {
+            let format = text_arg(0)?;
+            if StrftimeItems::new(&format).any(|item| item == Item::Error) {
+                bail!("Invalid date format '{format}'.");
+            }
+            let date = match value {
+                Value::Date(date) => date,
+                other => parse_date(&other.to_string())?,
+            };
+            Value::Str(date.format(&format).to_string())
+        }
+        "upper" => Value::Str(value.to_string().to_uppercase()),
+        "lower" => Value::Str(value.to_string().to_lowercase()),
+        "trim" => Value::Str(value.to_string().trim().to_string()),
+        "capitalize" => {
+            let text = value.to_string();
+            let mut chars = text.chars();
+            Value::Str(match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            })
+        }
+        "replace" => Value::Str(value.to_string().replace(&text_arg(0)?, &text_arg(1)?)),
+        "split" => {
+            let separator = text_arg(0)?;
+            let text = value.to_string();
+            Value::List(
+                text.split(separator.as_str())
+                    .map(str::trim)
+                    .filter(|part| !part.is_empty())
+                    .map(Value::from)
+                    .collect(),
+            )
+        }
+        "join" => match value {
+            Value::List(items) => {
+                let items: Vec<String> = items.iter().map(Value::to_string).collect();
+                Value::Str(items.join(&text_arg(0)?))
+            }
+            other => other,
+        },
+        "length" => Value::Str(match value {
+            Value::List(items) => items.len().to_string(),
+            other => other.to_string().chars().count().to_string(),
+        }),
+        "first" | "last" => match value {
+            Value::List(items) => {
+                let item = if name == "first" {
+                    items.into_iter().next()
+                } else {
+                    items.into_iter().last()
+                };
+                item.unwrap_or(Value::Str(String::new()))
+            }
+            other => other,
+        },
+        _ => bail!("Unknown filter '{name}'."),
+    })
+}
+
+/// Parses text as an RFC 3339 timestamp or a `YYYY-MM-DD` date.
+fn parse_date(text: &str) -> Result<DateTime<FixedOffset>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(text) {
+        return Ok(date);
+    }
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| date.and_utc().fixed_offset())
+        .ok_or_else(|| anyhow!("`format` needs a date, but got '{text}'."))
+}
+
+// --- Template metadata ---
+
+/// Settings a template declares for the jots created from it.
+///
+/// They live under a `template:` key in the template's frontmatter, which is removed
+/// before the jot is written:
+///
+/// ```yaml
+/// ---
+/// template:
+///   description: Bug report
+///   notebook: bugs
+///   tags: [bug]
+///   variables:
+///     ticket: The ticket ID
+///   commits: 3
+///   commands:
+///     - kubectl config current-context
+/// ---
+/// ```
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplateMeta {
+    /// A one-line summary shown by `template list`.
+    pub description: Option<String>,
+    /// The notebook that jots from this template are created in.
+    pub notebook: Option<String>,
+    /// Tags added to every jot created from this template.
+    pub tags: Vec<String>,
+    /// Whether jots created from this template start out pinned.
+    pub pinned: bool,
+    /// Variables that must have a value, with the description used to prompt for them.
+    pub variables: BTreeMap<String, String>,
+    /// How many recent commits `{{ commits }}` lists.
+    pub commits: Option<usize>,
+    /// The commands that `{{ cmd:... }}` tags are allowed to run.
+    pub commands: Vec<String>,
+}
+
+/// Splits the `template:` block out of a template's frontmatter.
+///
+/// Returns the metadata and the template source without it. The block is found by its
+/// indentation instead of parsing the whole frontmatter, because the rest of the
+/// frontmatter may contain template tags that aren't valid YAML until rendered.
+pub fn split_meta(source: &str) -> Result<(TemplateMeta, String)> {
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+    let is_fence = |line: &str| line.trim_end() == "---";
+    if lines.first().is_none_or(|line| !is_fence(line)) {
+        return Ok((TemplateMeta::default(), source.to_string()));
+    }
+    let Some(end) = lines
+        .iter()
+        .skip(1)
+        .position(|line| is_fence(line))
+        .map(|i| i + 1)
+    else {
+        return Ok((TemplateMeta::default(), source.to_string()));
+    };
+    let Some(start) = (1..end).find(|&i| lines[i].starts_with("template:")) else {
+        return Ok((TemplateMeta::default(), source.to_string()));
+    };
+    let block_end = (start + 1..end)
+        .find(|&i| {
+            let line = lines[i];
+            !(line.trim().is_empty() || line.starts_with(' ') || line.starts_with('\t'))
+        })
+        .unwrap_or(end);
+
+    #[derive(Deserialize)]
+    struct Wrapper {
+        template: Option<TemplateMeta>,
+    }
+    let block = lines[start..block_end].concat();
+    let meta = serde_yaml::from_str::<Wrapper>(&block)
+        .context("Invalid `template:` block in the frontmatter")?
+        .template
+        .unwrap_or_default();
+
+    let frontmatter_rest: Vec<&str> = lines[1..start]
+        .iter()
+        .chain(&lines[block_end..end])
+        .copied()
+        .collect();
+    let body = lines[end + 1..].concat();
+    let rest = if frontmatter_rest.iter().all(|line| line.trim().is_empty()) {
+        // Nothing but template settings: drop the frontmatter entirely.
+        body.trim_start_matches(['\r', '\n']).to_string()
+    } else {
+        format!(
+            "{}{}{}{}",
+            lines[0],
+            frontmatter_rest.concat(),
+            lines[end],
+            body
+        )
+    };
+    Ok((meta, rest))
+}
@@ -0,0 +1,656 @@
+//! A full-screen browser for jots, started with `rjot tui`.
+//!
+//! The screen has a notebook sidebar, a filterable jot list, and a preview of the jot under
+//! the cursor. Jots are read with `parse_note_from_file` and saved with `write_note_file`,
+//! so encrypted notebooks work the same as everywhere else. Editing and creating jots
+//! suspend the interface and hand the terminal to the editor.
+
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::commands;
+use crate::git;
+use crate::helpers::{self, list_note_paths, parse_note_from_file, Note};
+
+/// A parsed `/` filter: `#tag` words must all be tags of the jot, `@date` words must be a
+/// prefix of its ID (e.g. `@2025-07`), and any other word must appear in its content.
+#[derive(Debug, Default, PartialEq)]
+pub struct Filter {
+    pub tags: Vec<String>,
+    pub dates: Vec<String>,
+    pub words: Vec<String>,
+}
+
+impl Filter {
+    pub fn parse(query: &str) -> Self {
+        let mut filter = Filter::default();
+        for word in query.split_whitespace() {
+            if let Some(tag) = word.strip_prefix('#').filter(|tag| !tag.is_empty()) {
+                filter.tags.push(tag.to_string());
+            } else if let Some(date) = word.strip_prefix('@').filter(|date| !date.is_empty()) {
+                filter.dates.push(date.to_string());
+            } else {
+                filter.words.push(word.to_lowercase());
+            }
+        }
+        filter
+    }
+
+    pub fn matches(&self, note: &Note) -> bool {
+        let content = note.content.to_lowercase();
+        self.tags
+            .iter()
+            .all(|tag| note.frontmatter.tags.contains(tag))
+            && self.dates.iter().all(|date| note.id.starts_with(date))
+            && self.words.iter().all(|word| content.contains(word))
+    }
+}
+
+/// Flips the checkbox of the `index`th task (zero-based) in a jot's content, or returns
+/// `None` if there aren't that many tasks.
+pub fn toggle_task(content: &str, index: usize) -> Option<String> {
+    let mut seen = 0;
+    let mut toggled = false;
+    let lines: Vec<String> = content
+        .split('\n')
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let indent = &line[..line.len() - trimmed.len()];
+            let flipped = if let Some(rest) = trimmed.strip_prefix("- [ ] ") {
+                Some(format!("{indent}- [x] {rest}"))
+            } else {
+                trimmed
+                    .strip_prefix("- [x] ")
+                    .map(|rest| format!("{indent}- [ ] {rest}"))
+            };
+            match flipped {
+                Some(flipped) if seen == index => {
+                    seen += 1;
+                    toggled = true;
+                    flipped
+                }
+                Some(_) => {
+                    seen += 1;
+                    line.to_string()
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect();
+    toggled.then(|| lines.join("\n"))
+}
+
+// Which pane the arrow keys move in.
+#[derive(PartialEq)]
+enum Focus {
+    Notebooks,
+    Jots,
+}
+
+// What the status line is asking for.
+enum Prompt {
+    Filter,
+    Tags,
+    Task,
+    Delete,
+}
+
+// Work that needs the terminal back from the interface.
+enum Suspend {
+    Edit(PathBuf),
+    New,
+}
+
+// What the event loop does after a key press.
+enum Flow {
+    Continue,
+    Quit,
+    Suspend(Suspend),
+}
+
+struct App {
+    notebooks: Vec<PathBuf>,
+    notebook: usize,
+    notes: Vec<Note>,
+    visible: Vec<usize>,
+    jots: ListState,
+    focus: Focus,
+    filter: String,
+    prompt: Option<(Prompt, String)>,
+    status: String,
+    scroll: u16,
+}
+
+impl App {
+    fn new(active: &Path) -> Result<Self> {
+        let mut notebooks = helpers::list_notebooks(false)?;
+        if !notebooks.iter().any(|dir| dir == active) {
+            notebooks.push(active.to_path_buf());
+        }
+        notebooks.sort();
+        let notebook = notebooks.iter().position(|dir| dir == active).unwrap_or(0);
+        let mut app = App {
+            notebooks,
+            notebook,
+            notes: Vec::new(),
+            visible: Vec::new(),
+            jots: ListState::default(),
+            focus: Focus::Jots,
+            filter: String::new(),
+            prompt: None,
+            status: String::new(),
+            scroll: 0,
+        };
+        app.reload()?;
+        Ok(app)
+    }
+
+    fn notebook_dir(&self) -> &Path {
+        &self.notebooks[self.notebook]
+    }
+
+    fn notebook_name(&self) -> String {
+        self.notebook_dir()
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .to_string()
+    }
+
+    // Reads every jot of a notebook, newest first.
+    fn load_notes(&self, notebook: usize) -> Result<Vec<Note>> {
+        let dir = &self.notebooks[notebook];
+        let notebook_name = dir.file_name().unwrap().to_string_lossy().to_string();
+        let mut notes = list_note_paths(dir)?
+            .iter()
+            .map(|path| parse_note_from_file(path, &notebook_name))
+            .collect::<Result<Vec<_>>>()?;
+        notes.sort_by(|a, b| b.id.cmp(&a.id));
+        Ok(notes)
+    }
+
+    // Re-reads the active notebook, keeping the cursor on the same jot if it still exists.
+    fn reload(&mut self) -> Result<()> {
+        let current = self.selected().map(|note| note.id.clone());
+        self.notes = self.load_notes(self.notebook)?;
+        self.apply_filter();
+        if let Some(id) = current {
+            if let Some(row) = self.visible.iter().position(|&i| self.notes[i].id == id) {
+                self.jots.select(Some(row));
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_filter(&mut self) {
+        let filter = Filter::parse(&self.filter);
+        self.visible = (0..self.notes.len())
+            .filter(|&i| filter.matches(&self.notes[i]))
+            .collect();
+        let row = self
+            .jots
+            .selected()
+            .unwrap_or(0)
+            .min(self.visible.len().saturating_sub(1));
+        self.jots.select((!self.visible.is_empty()).then_some(row));
+        self.scroll = 0;
+    }
+
+    fn selected(&self) -> Option<&Note> {
+        let row = self.jots.selected()?;
+        self.visible.get(row).map(|&i| &self.notes[i])
+    }
+
+    fn move_cursor(&mut self, down: bool) -> Result<()> {
+        match self.focus {
+            Focus::Notebooks => {
+                let last = self.notebooks.len() - 1;
+                let notebook = if down {
+                    (self.notebook + 1).min(last)
+                } else {
+                    self.notebook.saturating_sub(1)
+                };
+                // Stay on the current notebook if the next one can't be read, so actions
+                // never apply to jots from a notebook that isn't highlighted.
+                self.notes = self.load_notes(notebook)?;
+                self.notebook = notebook;
+                self.jots.select(Some(0));
+                self.apply_filter();
+            }
+            Focus::Jots => {
+                if down {
+                    self.jots.select_next();
+                } else {
+                    self.jots.select_previous();
+                }
+                if let Some(row) = self.jots.selected() {
+                    self.jots
+                        .select(Some(row.min(self.visible.len().saturating_sub(1))));
+                }
+                self.scroll = 0;
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Result<Flow> {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(Flow::Quit);
+        }
+        if self.prompt.is_some() {
+            self.handle_prompt_key(key)?;
+            return Ok(Flow::Continue);
+        }
+        self.status.clear();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(Flow::Quit),
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::Notebooks => Focus::Jots,
+                    Focus::Jots => Focus::Notebooks,
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.focus = Focus::Notebooks,
+            KeyCode::Right | KeyCode::Char('l') => self.focus = Focus::Jots,
+            KeyCode::Down | KeyCode::Char('j') => self.move_cursor(true)?,
+            KeyCode::Up | KeyCode::Char('k') => self.move_cursor(false)?,
+            KeyCode::PageDown | KeyCode::Char('J') => self.scroll = self.scroll.saturating_add(5),
+            KeyCode::PageUp | KeyCode::Char('K') => self.scroll = self.scroll.saturating_sub(5),
+            KeyCode::Char('/') => self.prompt = Some((Prompt::Filter, self.filter.clone())),
+            KeyCode::Char('n') => return Ok(Flow::Suspend(Suspend::New)),
+            KeyCode::Char('r') => {
+                self.reload()?;
+                self.status = "Reloaded.".to_string();
+            }
+            KeyCode::Enter if self.focus == Focus::Notebooks => self.focus = Focus::Jots,
+            KeyCode::Enter | KeyCode::Char('e') => {
+                if let Some(note) = self.selected() {
+                    return Ok(Flow::Suspend(Suspend::Edit(note.path.clone())));
+                }
+            }
+            KeyCode::Char('t') if self.selected().is_some() => {
+                self.prompt = Some((Prompt::Tags, String::new()))
+            }
+            KeyCode::Char('d') if self.selected().is_some() => {
+                self.prompt = Some((Prompt::Delete, String::new()))
+            }
+            KeyCode::Char('p') => self.toggle_pin()?,
+            KeyCode::Char('x') => match self.selected().map(|note| note.tasks.len()) {
+                Some(0) => self.status = "This jot has no tasks.".to_string(),
+                Some(1) => self.toggle_task(0)?,
+                Some(_) => self.prompt = Some((Prompt::Task, String::new())),
+                None => {}
+            },
+            _ => {}
+        }
+        Ok(Flow::Continue)
+    }
+
+    fn handle_prompt_key(&mut self, key: KeyEvent) -> Result<()> {
+        let Some((prompt, mut text)) = self.prompt.take() else {
+            return Ok(());
+        };
+        if let Prompt::Delete = prompt {
+            if key.code == KeyCode::Char('y') {
+                self.delete()?;
+            } else {
+                self.status = "Deletion aborted.".to_string();
+            }
+            return Ok(());
+        }
+        match key.code {
+            KeyCode::Esc => {
+                if let Prompt::Filter = prompt {
+                    self.filter.clear();
+                    self.apply_filter();
+                }
+                return Ok(());
+            }
+            KeyCode::Enter => {
+                match prompt {
+                    Prompt::Filter => {}
+                    Prompt::Tags => self.change_tags(&text)?,
+                    Prompt::Task => match text.trim().parse::<usize>() {
+                        Ok(number) if number > 0 => self.toggle_task(number - 1)?,
+                        _ => self.status = format!("'{text}' is not a task number."),
+                    },
+                    Prompt::Delete => unreachable!(),
+                }
+                return Ok(());
+            }
+            KeyCode::Backspace => {
+                text.pop();
+            }
+            KeyCode::Char(c) => text.push(c),
+            _ => {}
+        }
+        if let Prompt::Filter = prompt {
+            self.filter = text.clone();
+            self.apply_filter();
+        }
+        self.prompt = Some((prompt, text));
+        Ok(())
+    }
+
+    // Saves a changed jot, records a commit, and refreshes the list.
+    fn save(&mut self, note: &Note, commit_message: &str) -> Result<()> {
+        helpers::save_note(note)?;
+        self.commit(commit_message)
+    }
+
+    // Records a commit for a jot that was already written, and refreshes the list.
+    fn commit(&mut self, commit_message: &str) -> Result<()> {
+        git::auto_commit(commit_message)?;
+        self.reload()
+    }
+
+    fn toggle_pin(&mut self) -> Result<()> {
+        let Some(note) = self.selected() else {
+            return Ok(());
+        };
+        let mut note = parse_note_from_file(&note.path, &self.notebook_name())?;
+        let pin = !note.frontmatter.pinned;
+        helpers::set_pinned(&mut note, pin)?;
+        let action = if pin { "pin" } else { "unpin" };
+        self.commit(&format!("{action} {}", note.id))?;
+        self.status = format!("{}ned '{}'.", capitalize(action), note.id);
+        Ok(())
+    }
+
+    // Applies a tags prompt: `tag` adds a tag and `-tag` removes one.
+    fn change_tags(&mut self, input: &str) -> Result<()> {
+        let Some(note) = self.selected() else {
+            return Ok(());
+        };
+        let mut note = parse_note_from_file(&note.path, &self.notebook_name())?;
+        let (removed, added): (Vec<&str>, Vec<&str>) = input
+            .split([',', ' '])
+            .filter(|tag| !tag.is_empty())
+            .partition(|tag| tag.starts_with('-'));
+        if removed.is_empty() && added.is_empty() {
+            return Ok(());
+        }
+        note.frontmatter
+            .tags
+            .retain(|tag| !removed.iter().any(|r| r[1..] == **tag));
+        note.frontmatter
+            .tags
+            .extend(added.iter().map(|tag| tag.to_string()));
+        note.frontmatter.tags.sort();
+        note.frontmatter.tags.dedup();
+        self.save(&note, &format!("tag {} on {}", input.trim(), note.id))?;
+        self.status = format!("Updated tags for '{}'.", note.id);
+        Ok(())
+    }
+
+    fn toggle_task(&mut self, index: usize) -> Result<()> {
+        let Some(note) = self.selected() else {
+            return Ok(());
+        };
+        let mut note = parse_note_from_file(&note.path, &self.notebook_name())?;
+        let Some(content) = toggle_task(&note.content, index) else {
+            self.status = format!("'{}' has no task {}.", note.id, index + 1);
+            return Ok(());
+        };
+        note.content = content;
+        self.save(&note, &format!("toggle task {} in {}", index + 1, note.id))?;
+        self.status = format!("Toggled task {} in '{}'.", index + 1, note.id);
+        Ok(())
+    }
+
+    fn delete(&mut self) -> Result<()> {
+        let Some(note) = self.selected() else {
+            return Ok(());
+        };
+        let id = note.id.clone();
+        std::fs::remove_file(&note.path)?;
+        git::auto_commit(&format!("delete {id}"))?;
+        self.reload()?;
+        self.status = format!("Deleted '{id}'.");
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [sidebar, list, preview] = Layout::horizontal([
+            Constraint::Length(20),
+            Constraint::Percentage(40),
+            Constraint::Min(0),
+        ])
+        .areas(main);
+
+        self.draw_notebooks(frame, sidebar);
+        self.draw_jots(frame, list);
+        self.draw_preview(frame, preview);
+
+        let line = match &self.prompt {
+            Some((prompt, text)) => {
+                let label = match prompt {
+                    Prompt::Filter => "Filter (#tag @date words): ".to_string(),
+                    Prompt::Tags => "Tags (tag adds, -tag removes): ".to_string(),
+                    Prompt::Task => "Toggle task number: ".to_string(),
+                    Prompt::Delete => match self.selected() {
+                        Some(note) => format!("Delete '{}'? [y/N] ", note.id),
+                        None => String::new(),
+                    },
+                };
+                Line::from(vec![Span::styled(label, bold()), Span::raw(text.as_str())])
+            }
+            None if !self.status.is_empty() => Line::from(self.status.as_str()),
+            None => Line::styled(
+                "q quit  tab focus  / filter  n new  e edit  t tag  p pin  d delete  x task  J/K scroll",
+                Style::default().add_modifier(Modifier::DIM),
+            ),
+        };
+        frame.render_widget(Paragraph::new(line), status);
+    }
+
+    fn draw_notebooks(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .notebooks
+            .iter()
+            .map(|dir| ListItem::new(dir.file_name().unwrap().to_string_lossy().to_string()))
+            .collect();
+        let mut state = ListState::default().with_selected(Some(self.notebook));
+        let list = List::new(items)
+            .block(self.block(" Notebooks ", self.focus == Focus::Notebooks))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+
+    fn draw_jots(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|&i| {
+                let note = &self.notes[i];
+                let marker = if note.frontmatter.pinned { "* " } else { "  " };
+                let first_line = note.content.lines().next().unwrap_or("").trim();
+                let mut spans = vec![
+                    Span::raw(marker),
+                    Span::styled(note.id.clone(), Style::default().fg(Color::Yellow)),
+                    Span::raw(format!(" {first_line}")),
+                ];
+                if !note.frontmatter.tags.is_empty() {
+                    let tags: Vec<String> = note
+                        .frontmatter
+                        .tags
+                        .iter()
+                        .map(|t| format!("#{t}"))
+                        .collect();
+                    spans.push(Span::styled(
+                        format!(" {}", tags.join(" ")),
+                        Style::default().fg(Color::Cyan),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+        let mut title = format!(" Jots ({}/{}) ", self.visible.len(), self.notes.len());
+        if !self.filter.is_empty() {
+            title.push_str(&format!("/{} ", self.filter));
+        }
+        let list = List::new(items)
+            .block(self.block(&title, self.focus == Focus::Jots))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.jots);
+    }
+
+    fn draw_preview(&self, frame: &mut Frame, area: Rect) {
+        let lines = match self.selected() {
+            Some(note) => preview_lines(note),
+            None => vec![Line::from("No jots found.")],
+        };
+        let preview = Paragraph::new(lines)
+            .block(self.block(" Preview ", false))
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0));
+        frame.render_widget(preview, area);
+    }
+
+    fn block<'a>(&self, title: &'a str, focused: bool) -> Block<'a> {
+        let style = if focused {
+            Style::default().fg(Color::Magenta)
+        } else {
+            Style::default()
+        };
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(style)
+            .title(title)
+    }
+}
+
+// Styles a jot for the preview: its ID and tags on top, then the content with headings
+// in bold, numbered task checkboxes in color, and code blocks dimmed.
+fn preview_lines(note: &Note) -> Vec<Line<'static>> {
+    let mut heading = vec![Span::styled(note.id.clone(), bold())];
+    if note.frontmatter.pinned {
+        heading.push(Span::raw(" (pinned)"));
+    }
+    let mut lines = vec![Line::from(heading)];
+    if !note.frontmatter.tags.is_empty() {
+        lines.push(Line::styled(
+            format!("Tags: {}", note.frontmatter.tags.join(", ")),
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+    lines.push(Line::default());
+
+    let mut in_code = false;
+    let mut task = 0;
+    for line in note.content.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            lines.push(Line::styled(line.to_string(), dim()));
+        } else if in_code {
+            lines.push(Line::styled(line.to_string(), dim()));
+        } else if trimmed.starts_with('#') {
+            lines.push(Line::styled(line.to_string(), bold().fg(Color::Magenta)));
+        } else if let Some(rest) = trimmed.strip_prefix("- [ ] ") {
+            task += 1;
+            lines.push(Line::from(vec![
+                Span::styled(format!("{task}. [ ] "), Style::default().fg(Color::Yellow)),
+                Span::raw(rest.to_string()),
+            ]));
+        } else if let Some(rest) = trimmed.strip_prefix("- [x] ") {
+            task += 1;
+            lines.push(Line::from(vec![
+                Span::styled(format!("{task}. [x] "), Style::default().fg(Color::Green)),
+                Span::styled(rest.to_string(), dim()),
+            ]));
+        } else {
+            lines.push(Line::from(line.to_string()));
+        }
+    }
+    lines
+}
+
+fn bold() -> Style {
+    Style::default().add_modifier(Modifier::BOLD)
+}
+
+fn dim() -> Style {
+    Style::default().add_modifier(Modifier::DIM)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+// Hands the terminal to the editor for work that can't happen inside the interface.
+fn run_suspended(app: &mut App, work: Suspend) -> Result<()> {
+    match work {
+        Suspend::Edit(path) => commands::command_edit(path)?,
+        Suspend::New => {
+            commands::command_new(Some(app.notebook_name()), None, Vec::new())?;
+            app.reload()?;
+            // Put the cursor on the new jot.
+            if let Some(path) = helpers::last_created() {
+                if let Some(row) = app.visible.iter().position(|&i| app.notes[i].path == path) {
+                    app.jots.select(Some(row));
+                }
+            }
+            return Ok(());
+        }
+    }
+    app.reload()
+}
+
+fn run(app: &mut App, terminal: &mut DefaultTerminal) -> Result<()> {
+    loop {
+        terminal.draw(|frame| app.draw(frame))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        // Errors from an action are shown in the status line instead of closing the interface.
+        let flow = match app.handle_key(key) {
+            Ok(flow) => flow,
+            Err(e) => {
+                app.status = format!("Error: {e:#}");
+                Flow::Continue
+            }
+        };
+        match flow {
+            Flow::Continue => {}
+            Flow::Quit => return Ok(()),
+            Flow::Suspend(work) => {
+                ratatui::try_restore()?;
+                let result = run_suspended(app, work);
+                *terminal = ratatui::try_init()?;
+                if let Err(e) = result {
+                    app.status = format!("Error: {e:#}");
+                }
+            }
+        }
+    }
+}
+
+/// Starts the full-screen interface on the given notebook.
+pub fn command_tui(entries_dir: &Path) -> Result<()> {
+    if !io::stdout().is_terminal() || !io::stdin().is_terminal() {
+        bail!("`rjot tui` needs an interactive terminal.");
+    }
+    let mut app = App::new(entries_dir)?;
+    let mut terminal = ratatui::try_init()?;
+    let result = run(&mut app, &mut terminal);
+    ratatui::try_restore()?;
+    result
+}
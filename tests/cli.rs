@@ -2,7 +2,7 @@ use assert_cmd::Command;
 use chrono::Local;
 use predicates::prelude::*;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tempfile::{tempdir, TempDir};
 
 type TestResult = Result<(), Box<dyn std::error::Error>>;
@@ -106,6 +106,48 @@ fn test_list_and_find() -> TestResult {
     Ok(())
 }
 
+#[test]
+fn test_show_and_compile_raw() -> TestResult {
+    let (_temp_dir, rjot_dir) = setup();
+    let default = rjot_dir.join("notebooks").join("default");
+    fs::create_dir_all(&default)?;
+    fs::write(
+        default.join("2025-07-01-090000.md"),
+        "---\ntags:\n- rust\n---\n\n# Borrow notes\n- [ ] read",
+    )?;
+
+    // Output that isn't going to a terminal stays plain markdown, with or without --raw.
+    for args in [
+        &["show", "2025-07-01"][..],
+        &["show", "2025-07-01", "--raw"],
+    ] {
+        Command::cargo_bin("rjot")?
+            .args(args)
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("tags:\n- rust"))
+            .stdout(predicate::str::contains("# Borrow notes\n- [ ] read"));
+    }
+
+    Command::cargo_bin("rjot")?
+        .args(["on", "2025-07-01", "--compile", "--raw"])
+        .env("RJOT_DIR", &rjot_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "---\n\n# 2025-07-01-090000\n\n# Borrow notes",
+        ));
+
+    Command::cargo_bin("rjot")?
+        .args(["on", "2025-07-01", "--raw"])
+        .env("RJOT_DIR", &rjot_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--compile"));
+    Ok(())
+}
+
 #[test]
 fn test_show_edit_delete() -> TestResult {
     let (temp_dir, rjot_dir) = setup();
@@ -269,363 +311,1923 @@ fn test_git_init_and_sync() -> TestResult {
     Ok(())
 }
 
-// Test module for notebooks
+// Test module for syncing between two machines through a shared remote.
 #[cfg(test)]
-mod notebooks {
+mod sync {
     use super::*;
+    use git2::Repository;
+    use std::io::Write;
 
-    #[test]
-    fn test_notebook_creation_and_list() -> TestResult {
-        let (_temp_dir, rjot_dir) = setup();
+    /// Sets up a bare "remote" and a first machine that has pushed to it.
+    fn setup_remote_and_first_machine(
+    ) -> Result<(TempDir, PathBuf, PathBuf), Box<dyn std::error::Error>> {
+        let (temp_dir, root) = setup();
+        // Keep the remote outside the rjot directory so it is never staged.
+        let machine_a = root.join("machine-a");
+        fs::create_dir_all(machine_a.join("notebooks").join("default"))?;
+        let remote_path = root.join("remote.git");
+        Repository::init_bare(&remote_path)?;
 
-        // Create a new notebook
         Command::cargo_bin("rjot")?
-            .args(["notebook", "new", "work"])
-            .env("RJOT_DIR", &rjot_dir)
+            .args(["init", "--git"])
+            .env("RJOT_DIR", &machine_a)
             .assert()
+            .success();
+        Repository::open(&machine_a)?.remote("origin", remote_path.to_str().unwrap())?;
+
+        Ok((temp_dir, machine_a, remote_path))
+    }
+
+    /// Clones the remote into a fresh rjot directory for a second machine.
+    fn clone_second_machine(
+        remote_path: &Path,
+    ) -> Result<(TempDir, PathBuf), Box<dyn std::error::Error>> {
+        let temp_dir = tempdir()?;
+        let machine_b = temp_dir.path().join("rjot");
+        Repository::clone(remote_path.to_str().unwrap(), &machine_b)?;
+        Ok((temp_dir, machine_b))
+    }
+
+    /// Encrypts `content` to the rjot recipient and writes it over a default-notebook jot.
+    fn write_encrypted(
+        rjot_dir: &Path,
+        file_name: &std::ffi::OsStr,
+        content: &str,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let config = fs::read_to_string(rjot_dir.join("config.toml"))?;
+        let recipient: age::x25519::Recipient = config.split('"').nth(1).unwrap().parse()?;
+        let encryptor = age::Encryptor::with_recipients(vec![Box::new(recipient)]).unwrap();
+        let mut encrypted = vec![];
+        let mut writer = encryptor.wrap_output(&mut encrypted)?;
+        writer.write_all(content.as_bytes())?;
+        writer.finish()?;
+        fs::write(
+            rjot_dir.join("notebooks").join("default").join(file_name),
+            encrypted,
+        )?;
+        Ok(())
+    }
+
+    fn sync(rjot_dir: &Path) -> Result<assert_cmd::assert::Assert, Box<dyn std::error::Error>> {
+        Ok(Command::cargo_bin("rjot")?
+            .arg("sync")
+            .env("RJOT_DIR", rjot_dir)
+            .assert())
+    }
+
+    /// Tests that diverged histories are merged and append-only edits to the same jot
+    /// are combined automatically.
+    #[test]
+    fn test_sync_merges_and_combines_appended_jots() -> TestResult {
+        let (_temp_a, machine_a, remote_path) = setup_remote_and_first_machine()?;
+        let shared = Path::new("notebooks")
+            .join("default")
+            .join("2025-01-01-100000.md");
+        fs::write(machine_a.join(&shared), "shared line")?;
+        sync(&machine_a)?
+            .success()
+            .stdout(predicate::str::contains("Sync complete."));
+
+        let (_temp_b, machine_b) = clone_second_machine(&remote_path)?;
+
+        // Both machines append to the same jot and add a jot of their own.
+        fs::write(machine_a.join(&shared), "shared line\nfrom A")?;
+        fs::write(
+            machine_a.join("notebooks/default/2025-01-02-100000.md"),
+            "note from A",
+        )?;
+        sync(&machine_a)?.success();
+
+        fs::write(machine_b.join(&shared), "shared line\nfrom B")?;
+        fs::write(
+            machine_b.join("notebooks/default/2025-01-03-100000.md"),
+            "note from B",
+        )?;
+        sync(&machine_b)?
             .success()
             .stdout(predicate::str::contains(
-                "Successfully created new notebook: 'work'",
-            ));
+                "Automatically merged appended content",
+            ))
+            .stdout(predicate::str::contains("Sync complete."));
 
-        assert!(rjot_dir.join("notebooks").join("work").exists());
+        assert_eq!(
+            fs::read_to_string(machine_b.join(&shared))?,
+            "shared line\nfrom B\nfrom A"
+        );
 
-        // List notebooks
-        Command::cargo_bin("rjot")?
-            .args(["notebook", "list"])
-            .env("RJOT_DIR", &rjot_dir)
-            .assert()
+        // The first machine fast-forwards to the merged history.
+        sync(&machine_a)?
             .success()
-            .stdout(predicate::str::contains("* default"))
-            .stdout(predicate::str::contains("  work"));
+            .stdout(predicate::str::contains("Fast-forwarded"));
+        assert!(machine_a
+            .join("notebooks/default/2025-01-03-100000.md")
+            .exists());
+        assert_eq!(
+            fs::read_to_string(machine_a.join(&shared))?,
+            "shared line\nfrom B\nfrom A"
+        );
 
         Ok(())
     }
 
+    /// Tests that a real conflict stops the sync until it is resolved.
     #[test]
-    fn test_notebook_status_and_use() -> TestResult {
-        let (_temp_dir, rjot_dir) = setup();
+    fn test_sync_conflict_requires_resolution() -> TestResult {
+        let (_temp_a, machine_a, remote_path) = setup_remote_and_first_machine()?;
+        let shared = Path::new("notebooks")
+            .join("default")
+            .join("2025-01-01-100000.md");
+        fs::write(machine_a.join(&shared), "original")?;
+        sync(&machine_a)?.success();
+
+        let (_temp_b, machine_b) = clone_second_machine(&remote_path)?;
+
+        fs::write(machine_a.join(&shared), "rewritten on A")?;
+        sync(&machine_a)?.success();
+
+        fs::write(machine_b.join(&shared), "rewritten on B")?;
+        sync(&machine_b)?
+            .failure()
+            .stdout(predicate::str::contains(
+                "could not be merged automatically",
+            ))
+            .stdout(predicate::str::contains("2025-01-01-100000.md"))
+            .stderr(predicate::str::contains("Sync stopped with conflicts"));
+
+        // A plain sync refuses to continue while conflicts remain.
+        sync(&machine_b)?
+            .failure()
+            .stderr(predicate::str::contains("rjot sync --resolve"));
 
-        // Default status
         Command::cargo_bin("rjot")?
-            .args(["notebook", "status"])
-            .env("RJOT_DIR", &rjot_dir)
+            .args(["sync", "--resolve=theirs"])
+            .env("RJOT_DIR", &machine_b)
             .assert()
             .success()
-            .stdout(predicate::str::contains("Active notebook: default"));
+            .stdout(predicate::str::contains("All conflicts resolved"))
+            .stdout(predicate::str::contains("Sync complete."));
+
+        assert_eq!(
+            fs::read_to_string(machine_b.join(&shared))?,
+            "rewritten on A"
+        );
+
+        Ok(())
+    }
+
+    /// Tests that `--resolve=both` keeps both bodies under one merged frontmatter.
+    #[test]
+    fn test_sync_resolve_both() -> TestResult {
+        let (_temp_a, machine_a, remote_path) = setup_remote_and_first_machine()?;
+        let shared = Path::new("notebooks")
+            .join("default")
+            .join("2025-01-01-100000.md");
+        fs::write(machine_a.join(&shared), "original")?;
+        sync(&machine_a)?.success();
+
+        let (_temp_b, machine_b) = clone_second_machine(&remote_path)?;
+
+        fs::write(
+            machine_a.join(&shared),
+            "---\ntags:\n- shared\n- from-a\n---\n\nrewritten on A",
+        )?;
+        sync(&machine_a)?.success();
+
+        fs::write(
+            machine_b.join(&shared),
+            "---\ntags:\n- shared\npinned: true\n---\n\nrewritten on B",
+        )?;
+        sync(&machine_b)?.failure().stdout(predicate::str::contains(
+            "could not be merged automatically",
+        ));
 
-        // Status with env var
         Command::cargo_bin("rjot")?
-            .args(["notebook", "status"])
-            .env("RJOT_DIR", &rjot_dir)
-            .env("RJOT_ACTIVE_NOTEBOOK", "personal")
+            .args(["sync", "--resolve=both"])
+            .env("RJOT_DIR", &machine_b)
             .assert()
             .success()
-            .stdout(predicate::str::contains("Active notebook: personal"));
+            .stdout(predicate::str::contains("Sync complete."));
 
-        // `use` command should print the export command
-        Command::cargo_bin("rjot")?
-            .args(["notebook", "new", "project-x"])
-            .env("RJOT_DIR", &rjot_dir)
-            .assert()
-            .success();
+        assert_eq!(
+            fs::read_to_string(machine_b.join(&shared))?,
+            "---\ntags:\n- from-a\n- shared\npinned: true\n---\n\nrewritten on B\n\nrewritten on A"
+        );
+
+        Ok(())
+    }
+
+    /// Tests that `--dry-run` describes the pending commit without making it.
+    #[test]
+    fn test_sync_dry_run() -> TestResult {
+        let (_temp_a, machine_a, remote_path) = setup_remote_and_first_machine()?;
+        fs::write(
+            machine_a.join("notebooks/default/2025-01-01-100000.md"),
+            "not yet synced",
+        )?;
 
         Command::cargo_bin("rjot")?
-            .args(["notebook", "use", "project-x"])
-            .env("RJOT_DIR", &rjot_dir)
+            .args(["sync", "--dry-run"])
+            .env("RJOT_DIR", &machine_a)
             .assert()
             .success()
+            .stdout(predicate::str::contains("rjot sync: 1 jot added"))
             .stdout(predicate::str::contains(
-                "export RJOT_ACTIVE_NOTEBOOK=\"project-x\"",
-            ));
+                "- added: default/2025-01-01-100000",
+            ))
+            .stdout(predicate::str::contains("Dry run"));
+
+        let remote = Repository::open_bare(&remote_path)?;
+        assert!(remote.references()?.next().is_none());
+        let repo = Repository::open(&machine_a)?;
+        let head = repo.head()?.peel_to_commit()?;
+        assert!(!head.message().unwrap_or_default().contains("rjot sync"));
 
         Ok(())
     }
 
+    /// Tests that the author, remote, branch, and message template come from `config.toml`.
     #[test]
-    fn test_jotting_in_different_notebooks() -> TestResult {
-        let (_temp_dir, rjot_dir) = setup();
+    fn test_sync_uses_configured_settings() -> TestResult {
+        let (_temp_a, machine_a, _origin_path) = setup_remote_and_first_machine()?;
+        let backup_dir = tempdir()?;
+        let backup_path = backup_dir.path().join("backup.git");
+        Repository::init_bare(&backup_path)?;
+        Repository::open(&machine_a)?.remote("backup", backup_path.to_str().unwrap())?;
+        fs::write(
+            machine_a.join("config.toml"),
+            r#"[git]
+remote = "backup"
+branch = "journal"
+name = "Jo Writer"
+email = "jo@example.com"
+commit_message = "journal: {summary}"
+ssh_keys = ["~/.ssh/id_ed25519_journal"]
+"#,
+        )?;
+        fs::write(
+            machine_a.join("notebooks/default/2025-01-01-100000.md"),
+            "configured sync",
+        )?;
+
+        sync(&machine_a)?.success().stdout(predicate::str::contains(
+            "remote 'backup' on branch 'journal'",
+        ));
+
+        let remote = Repository::open_bare(&backup_path)?;
+        let commit = remote
+            .find_reference("refs/heads/journal")?
+            .peel_to_commit()?;
+        assert_eq!(commit.message(), Some("journal: 1 jot added"));
+        assert_eq!(commit.author().name(), Some("Jo Writer"));
+        assert_eq!(commit.author().email(), Some("jo@example.com"));
+
+        Ok(())
+    }
+
+    /// Tests that appended encrypted jots are decrypted, merged, and re-encrypted.
+    #[test]
+    fn test_sync_merges_encrypted_jots() -> TestResult {
+        let (_temp_a, machine_a, remote_path) = setup_remote_and_first_machine()?;
         Command::cargo_bin("rjot")?
-            .args(["notebook", "new", "work"])
-            .env("RJOT_DIR", &rjot_dir)
+            .args(["init", "--encrypt"])
+            .env("RJOT_DIR", &machine_a)
             .assert()
             .success();
-
-        // Jot in default
         Command::cargo_bin("rjot")?
-            .arg("a personal note")
-            .env("RJOT_DIR", &rjot_dir)
+            .arg("encrypted start")
+            .env("RJOT_DIR", &machine_a)
             .assert()
             .success();
+        sync(&machine_a)?.success();
 
-        // Added sleep to prevent filename collision due to second-level timestamp resolution.
-        std::thread::sleep(std::time::Duration::from_millis(1200));
+        // The key files are not tracked, so the second machine gets a copy.
+        let (_temp_b, machine_b) = clone_second_machine(&remote_path)?;
+        fs::copy(
+            machine_a.join("identity.txt"),
+            machine_b.join("identity.txt"),
+        )?;
+        fs::copy(machine_a.join("config.toml"), machine_b.join("config.toml"))?;
 
-        // Jot in work notebook using --notebook flag
+        let note_path = fs::read_dir(machine_a.join("notebooks").join("default"))?
+            .next()
+            .unwrap()?
+            .path();
+        let note_name = note_path.file_name().unwrap().to_owned();
+
+        // Each machine appends a line to the jot, re-encrypting it as rjot would.
+        write_encrypted(&machine_a, &note_name, "encrypted start\nfrom A")?;
+        sync(&machine_a)?.success();
+        write_encrypted(&machine_b, &note_name, "encrypted start\nfrom B")?;
+
+        sync(&machine_b)?.success().stdout(predicate::str::contains(
+            "Automatically merged appended content",
+        ));
+
+        let merged_path = machine_b.join("notebooks/default").join(&note_name);
+        assert!(fs::read(&merged_path)?.starts_with(b"age-encryption.org"));
         Command::cargo_bin("rjot")?
-            .arg("a work note")
-            .args(["--notebook", "work"])
-            .env("RJOT_DIR", &rjot_dir)
+            .args(["show", "--last"])
+            .env("RJOT_DIR", &machine_b)
             .assert()
-            .success();
+            .success()
+            .stdout(predicate::str::contains("encrypted start\nfrom B\nfrom A"));
 
-        std::thread::sleep(std::time::Duration::from_millis(1200));
+        Ok(())
+    }
 
-        // Jot in work notebook using env var
+    /// Tests that a conflicting notebook config in an encrypted journal is kept as
+    /// plain TOML and can only be resolved by picking one side.
+    #[test]
+    fn test_sync_resolves_notebook_config_verbatim() -> TestResult {
+        let (_temp_a, machine_a, remote_path) = setup_remote_and_first_machine()?;
         Command::cargo_bin("rjot")?
-            .arg("another work note")
-            .env("RJOT_DIR", &rjot_dir)
-            .env("RJOT_ACTIVE_NOTEBOOK", "work")
+            .args(["init", "--encrypt"])
+            .env("RJOT_DIR", &machine_a)
             .assert()
             .success();
+        let config_path = Path::new("notebooks").join("default").join("notebook.toml");
+        fs::write(
+            machine_a.join(&config_path),
+            "default_tags = [\"shared\"]\n",
+        )?;
+        sync(&machine_a)?.success();
 
-        // Verify counts
-        assert_eq!(
-            fs::read_dir(rjot_dir.join("notebooks").join("default"))?.count(),
-            1
-        );
-        assert_eq!(
-            fs::read_dir(rjot_dir.join("notebooks").join("work"))?.count(),
-            2
-        );
+        let (_temp_b, machine_b) = clone_second_machine(&remote_path)?;
+        fs::copy(
+            machine_a.join("identity.txt"),
+            machine_b.join("identity.txt"),
+        )?;
+        fs::copy(machine_a.join("config.toml"), machine_b.join("config.toml"))?;
 
-        // Verify `list` is scoped correctly
+        let from_a = "default_tags = [\"shared\", \"from-a\"]\n";
+        fs::write(machine_a.join(&config_path), from_a)?;
+        sync(&machine_a)?.success();
+        fs::write(
+            machine_b.join(&config_path),
+            "default_tags = [\"shared\", \"from-b\"]\n",
+        )?;
+        sync(&machine_b)?
+            .failure()
+            .stdout(predicate::str::contains("notebook.toml"));
+
+        // Only jots can keep both versions.
         Command::cargo_bin("rjot")?
-            .arg("list")
-            .env("RJOT_DIR", &rjot_dir)
-            .env("RJOT_ACTIVE_NOTEBOOK", "work")
+            .args(["sync", "--resolve=both"])
+            .env("RJOT_DIR", &machine_b)
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("only jots can keep both versions"));
+
+        Command::cargo_bin("rjot")?
+            .args(["sync", "--resolve=theirs"])
+            .env("RJOT_DIR", &machine_b)
             .assert()
             .success()
-            .stdout(predicate::str::contains("a work note"))
-            .stdout(predicate::str::contains("another work note"))
-            .stdout(predicate::str::contains("a personal note").not());
+            .stdout(predicate::str::contains("Sync complete."));
+        assert_eq!(fs::read_to_string(machine_b.join(&config_path))?, from_a);
 
         Ok(())
     }
+}
 
-    #[test]
-    fn test_legacy_migration() -> TestResult {
-        let temp_dir = tempdir().expect("Failed to create temp dir");
-        let rjot_dir = temp_dir.path().to_path_buf();
+// Test module for automatic commits after mutating commands.
+#[cfg(test)]
+mod auto_commit {
+    use super::*;
+    use git2::Repository;
 
-        // 1. Create the legacy `entries` directory structure
-        let legacy_entries = rjot_dir.join("entries");
-        fs::create_dir_all(&legacy_entries)?;
-        fs::write(legacy_entries.join("legacy_note.md"), "old note")?;
+    fn head_message(repo_path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+        let repo = Repository::open(repo_path)?;
+        let commit = repo.head()?.peel_to_commit()?;
+        Ok(commit.message().unwrap_or_default().to_string())
+    }
 
-        // 2. Run any rjot command, which should trigger the migration
+    fn init_git(rjot_dir: &Path, config: &str) -> TestResult {
         Command::cargo_bin("rjot")?
-            .arg("info")
-            .arg("--paths")
-            .env("RJOT_DIR", &rjot_dir)
+            .args(["init", "--git"])
+            .env("RJOT_DIR", rjot_dir)
             .assert()
-            .success()
-            .stdout(predicate::str::contains("Migrating your existing notes"));
-
-        // 3. Verify the new structure
-        assert!(
-            !rjot_dir.join("entries").exists(),
-            "Legacy entries dir should be gone"
-        );
-        let default_notebook = rjot_dir.join("notebooks").join("default");
-        assert!(
-            default_notebook.exists(),
-            "Default notebook should be created"
-        );
-        assert!(
-            default_notebook.join("legacy_note.md").exists(),
-            "Legacy note should be moved"
-        );
-
+            .success();
+        fs::write(rjot_dir.join("config.toml"), config)?;
         Ok(())
     }
 
+    /// Tests that `init --git` signs its first commit with the configured author.
     #[test]
-    fn test_info_stats_all_notebooks() -> TestResult {
+    fn test_init_commit_uses_configured_author() -> TestResult {
         let (_temp_dir, rjot_dir) = setup();
+        fs::write(
+            rjot_dir.join("config.toml"),
+            "[git]\nname = \"Jo Writer\"\nemail = \"jo@example.com\"\n",
+        )?;
         Command::cargo_bin("rjot")?
-            .args(["notebook", "new", "work"])
+            .args(["init", "--git"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
 
-        // Add one note to default, two to work
+        let repo = Repository::open(&rjot_dir)?;
+        let commit = repo.head()?.peel_to_commit()?;
+        assert_eq!(commit.author().name(), Some("Jo Writer"));
+        assert_eq!(commit.author().email(), Some("jo@example.com"));
+        Ok(())
+    }
+
+    /// Tests that each mutating command creates a descriptive commit.
+    #[test]
+    fn test_auto_commit_messages() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        init_git(&rjot_dir, "[git]\nauto_commit = true\n")?;
+
         Command::cargo_bin("rjot")?
-            .arg("note 1")
+            .args(["down", "an auto-committed jot"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
-        std::thread::sleep(std::time::Duration::from_millis(1200));
+        let note_path = fs::read_dir(rjot_dir.join("notebooks").join("default"))?
+            .next()
+            .unwrap()?
+            .path();
+        let id = note_path.file_stem().unwrap().to_string_lossy().to_string();
+        assert_eq!(head_message(&rjot_dir)?, format!("down {id}"));
+
         Command::cargo_bin("rjot")?
-            .arg("note 2")
-            .args(["--notebook", "work"])
+            .args(["tag", "add", "--last=1", "rust"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
-        std::thread::sleep(std::time::Duration::from_millis(1200));
+        assert_eq!(head_message(&rjot_dir)?, format!("tag add rust to {id}"));
+
         Command::cargo_bin("rjot")?
-            .arg("note 3")
-            .args(["--notebook", "work"])
+            .args(["pin", "--last=1"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
+        assert_eq!(head_message(&rjot_dir)?, format!("pin {id}"));
 
-        // Check stats for all
         Command::cargo_bin("rjot")?
-            .args(["info", "--stats", "--all"])
+            .args(["delete", "--last=1", "--force"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
-            .success()
-            .stdout(predicate::str::contains("Stats for all notebooks combined"))
-            .stdout(predicate::str::contains("Total jots: 3"));
+            .success();
+        assert_eq!(head_message(&rjot_dir)?, format!("delete {id}"));
 
         Ok(())
     }
 
+    /// Tests that nothing is committed unless `auto_commit` is enabled.
     #[test]
-    fn test_tags_filter_is_scoped() -> TestResult {
+    fn test_auto_commit_disabled_by_default() -> TestResult {
         let (_temp_dir, rjot_dir) = setup();
+        init_git(&rjot_dir, "")?;
+        let initial = head_message(&rjot_dir)?;
+
         Command::cargo_bin("rjot")?
-            .args(["notebook", "new", "work"])
+            .args(["task", "not committed"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        assert_eq!(head_message(&rjot_dir)?, initial);
+
+        Ok(())
+    }
+
+    /// Tests that `auto_push` pushes each automatic commit to `origin`.
+    #[test]
+    fn test_auto_push() -> TestResult {
+        let (_temp_dir, root) = setup();
+        let rjot_dir = root.join("journal");
+        fs::create_dir_all(rjot_dir.join("notebooks").join("default"))?;
+        let remote_path = root.join("remote.git");
+        Repository::init_bare(&remote_path)?;
+        init_git(&rjot_dir, "[git]\nauto_commit = true\nauto_push = true\n")?;
+        Repository::open(&rjot_dir)?.remote("origin", remote_path.to_str().unwrap())?;
+
+        Command::cargo_bin("rjot")?
+            .args(["task", "pushed right away"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        assert!(head_message(&remote_path)?.starts_with("task "));
+
+        Ok(())
+    }
+}
+
+// Test module for per-jot history backed by Git.
+#[cfg(test)]
+mod history {
+    use super::*;
+
+    /// Initializes Git with automatic commits and creates one jot, returning its ID.
+    fn setup_history(rjot_dir: &Path, encrypt: bool) -> Result<String, Box<dyn std::error::Error>> {
+        let mut init_args = vec!["init", "--git"];
+        if encrypt {
+            init_args.push("--encrypt");
+        }
+        Command::cargo_bin("rjot")?
+            .args(&init_args)
+            .env("RJOT_DIR", rjot_dir)
+            .assert()
+            .success();
+        let mut config = fs::read_to_string(rjot_dir.join("config.toml")).unwrap_or_default();
+        config.push_str("\n[git]\nauto_commit = true\n");
+        fs::write(rjot_dir.join("config.toml"), config)?;
+
+        Command::cargo_bin("rjot")?
+            .arg("first version")
+            .env("RJOT_DIR", rjot_dir)
+            .assert()
+            .success();
+        Command::cargo_bin("rjot")?
+            .args(["tag", "add", "--last=1", "rust"])
+            .env("RJOT_DIR", rjot_dir)
+            .assert()
+            .success();
+
+        let note_path = fs::read_dir(rjot_dir.join("notebooks").join("default"))?
+            .filter_map(Result::ok)
+            .find(|e| e.path().extension().is_some_and(|ext| ext == "md"))
+            .unwrap()
+            .path();
+        Ok(note_path.file_stem().unwrap().to_string_lossy().to_string())
+    }
+
+    /// Tests `history`, `show --at`, and `diff` on a plaintext jot.
+    #[test]
+    fn test_history_show_at_and_diff() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let id = setup_history(&rjot_dir, false)?;
+
+        Command::cargo_bin("rjot")?
+            .args(["history", &id])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("added"))
+            .stdout(predicate::str::contains("modified"))
+            .stdout(predicate::str::contains(format!("tag add rust to {id}")));
+
+        Command::cargo_bin("rjot")?
+            .args(["show", &id, "--at", "HEAD~1"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("first version"))
+            .stdout(predicate::str::contains("rust").not());
+
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        Command::cargo_bin("rjot")?
+            .args(["show", "--last", "--at", &today])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("rust"));
+
+        Command::cargo_bin("rjot")?
+            .args(["diff", &id])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("No changes"));
+
+        let note_path = rjot_dir.join("notebooks/default").join(format!("{id}.md"));
+        let mut content = fs::read_to_string(&note_path)?;
+        content.push_str("\nan uncommitted line");
+        fs::write(&note_path, content)?;
+
+        Command::cargo_bin("rjot")?
+            .args(["diff", &id])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("+an uncommitted line"));
+
+        Ok(())
+    }
+
+    /// Tests that `--at <date>` ends the day in the configured time zone.
+    #[test]
+    fn test_show_at_date_uses_timezone() -> TestResult {
+        use git2::{Repository, Signature, Time};
+
+        let (_temp_dir, rjot_dir) = setup();
+        let repo = Repository::init(&rjot_dir)?;
+        let jot = Path::new("notebooks/default/2025-07-01-120000.md");
+        // 2025-07-01 20:00 UTC, then 2025-07-02 02:00 UTC, which is still July 1st
+        // in New York.
+        for (content, seconds) in [("evening", 1_751_400_000), ("late night", 1_751_421_600)] {
+            fs::write(rjot_dir.join(jot), content)?;
+            let mut index = repo.index()?;
+            index.add_path(jot)?;
+            index.write()?;
+            let tree = repo.find_tree(index.write_tree()?)?;
+            let signature = Signature::new("rjot", "rjot@localhost", &Time::new(seconds, 0))?;
+            let parent = repo
+                .head()
+                .ok()
+                .map(|head| head.peel_to_commit())
+                .transpose()?;
+            let parents: Vec<_> = parent.iter().collect();
+            repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                content,
+                &tree,
+                &parents,
+            )?;
+        }
+
+        for (timezone, expected) in [("UTC", "evening"), ("America/New_York", "late night")] {
+            Command::cargo_bin("rjot")?
+                .args(["show", "2025-07-01-120000", "--at", "2025-07-01"])
+                .env("RJOT_DIR", &rjot_dir)
+                .env("RJOT_TIMEZONE", timezone)
+                .assert()
+                .success()
+                .stdout(predicate::str::diff(format!("{expected}\n")));
+        }
+        Ok(())
+    }
+
+    /// Tests that a deleted jot can be found and restored.
+    #[test]
+    fn test_restore_deleted_jot() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let id = setup_history(&rjot_dir, false)?;
+        let note_path = rjot_dir.join("notebooks/default").join(format!("{id}.md"));
+        let original = fs::read_to_string(&note_path)?;
+
+        Command::cargo_bin("rjot")?
+            .args(["delete", &id, "--force"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        assert!(!note_path.exists());
+
+        Command::cargo_bin("rjot")?
+            .args(["history", &id])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("deleted"));
+
+        Command::cargo_bin("rjot")?
+            .args(["restore", &id])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(format!("Restored '{id}'")));
+        assert_eq!(fs::read_to_string(&note_path)?, original);
+
+        // An older version can be restored explicitly.
+        Command::cargo_bin("rjot")?
+            .args(["restore", &id, "--at", "HEAD~3"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        assert_eq!(fs::read_to_string(&note_path)?, "first version");
+
+        Ok(())
+    }
+
+    /// Tests that encrypted blobs are decrypted before diffing.
+    #[test]
+    fn test_diff_encrypted_jot() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let id = setup_history(&rjot_dir, true)?;
+
+        Command::cargo_bin("rjot")?
+            .args(["diff", &id, "--at", "HEAD~1"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("+tags:"))
+            .stdout(predicate::str::contains(" first version"))
+            .stdout(predicate::str::contains("age-encryption").not());
+
+        Ok(())
+    }
+}
+
+// Test module for notebooks
+#[cfg(test)]
+mod notebooks {
+    use super::*;
+
+    #[test]
+    fn test_notebook_creation_and_list() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+
+        // Create a new notebook
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully created new notebook: 'work'",
+            ));
+
+        assert!(rjot_dir.join("notebooks").join("work").exists());
+
+        // List notebooks
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "list"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("* default"))
+            .stdout(predicate::str::contains("  work"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_notebook_status_and_use() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+
+        // Default status
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "status"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Active notebook: default"));
+
+        // Status with env var
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "status"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("RJOT_ACTIVE_NOTEBOOK", "personal")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Active notebook: personal"));
+
+        // `use` command should print the export command
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "project-x"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "use", "project-x"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "export RJOT_ACTIVE_NOTEBOOK='project-x'",
+            ));
+
+        // Names that were created outside rjot are quoted for the shell.
+        fs::create_dir_all(rjot_dir.join("notebooks").join("it's $(x)"))?;
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "use", "it's $(x)"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                r"export RJOT_ACTIVE_NOTEBOOK='it'\''s $(x)'",
+            ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_notebook_use_persists() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        fs::create_dir_all(rjot_dir.join("notebooks").join("work"))?;
+
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "use", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // A later invocation, without the env var, still uses 'work'.
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "status"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Active notebook: work (set by `rjot notebook use`)",
+            ));
+        Command::cargo_bin("rjot")?
+            .arg("a persisted note")
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        assert_eq!(
+            fs::read_dir(rjot_dir.join("notebooks").join("work"))?.count(),
+            1
+        );
+
+        // The env var and the --notebook flag still take precedence.
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "status"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("RJOT_ACTIVE_NOTEBOOK", "default")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Active notebook: default"));
+        Command::cargo_bin("rjot")?
+            .args(["--notebook", "default", "an overridden note"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        assert_eq!(
+            fs::read_dir(rjot_dir.join("notebooks").join("default"))?.count(),
+            1
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_directory_bound_notebook() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        fs::create_dir_all(rjot_dir.join("notebooks").join("icarus"))?;
+        let project_dir = tempdir()?;
+        let nested_dir = project_dir.path().join("src").join("wings");
+        fs::create_dir_all(&nested_dir)?;
+
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "use", "icarus", "--here"])
+            .env("RJOT_DIR", &rjot_dir)
+            .current_dir(project_dir.path())
+            .assert()
+            .success();
+        assert_eq!(
+            fs::read_to_string(project_dir.path().join(".rjot-notebook"))?,
+            "icarus\n"
+        );
+
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "status"])
+            .env("RJOT_DIR", &rjot_dir)
+            .current_dir(&nested_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Active notebook: icarus"))
+            .stdout(predicate::str::contains(".rjot-notebook"));
+        Command::cargo_bin("rjot")?
+            .arg("a project note")
+            .env("RJOT_DIR", &rjot_dir)
+            .current_dir(&nested_dir)
+            .assert()
+            .success();
+        assert_eq!(
+            fs::read_dir(rjot_dir.join("notebooks").join("icarus"))?.count(),
+            1
+        );
+
+        // Outside the project, the binding doesn't apply.
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "status"])
+            .env("RJOT_DIR", &rjot_dir)
+            .current_dir(&rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Active notebook: default"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_jotting_in_different_notebooks() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // Jot in default
+        Command::cargo_bin("rjot")?
+            .arg("a personal note")
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // Added sleep to prevent filename collision due to second-level timestamp resolution.
+        std::thread::sleep(std::time::Duration::from_millis(1200));
+
+        // Jot in work notebook using --notebook flag
+        Command::cargo_bin("rjot")?
+            .arg("a work note")
+            .args(["--notebook", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        std::thread::sleep(std::time::Duration::from_millis(1200));
+
+        // Jot in work notebook using env var
+        Command::cargo_bin("rjot")?
+            .arg("another work note")
+            .env("RJOT_DIR", &rjot_dir)
+            .env("RJOT_ACTIVE_NOTEBOOK", "work")
+            .assert()
+            .success();
+
+        // Verify counts
+        assert_eq!(
+            fs::read_dir(rjot_dir.join("notebooks").join("default"))?.count(),
+            1
+        );
+        assert_eq!(
+            fs::read_dir(rjot_dir.join("notebooks").join("work"))?.count(),
+            2
+        );
+
+        // Verify `list` is scoped correctly
+        Command::cargo_bin("rjot")?
+            .arg("list")
+            .env("RJOT_DIR", &rjot_dir)
+            .env("RJOT_ACTIVE_NOTEBOOK", "work")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("a work note"))
+            .stdout(predicate::str::contains("another work note"))
+            .stdout(predicate::str::contains("a personal note").not());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_migration() -> TestResult {
+        let temp_dir = tempdir().expect("Failed to create temp dir");
+        let rjot_dir = temp_dir.path().to_path_buf();
+
+        // 1. Create the legacy `entries` directory structure
+        let legacy_entries = rjot_dir.join("entries");
+        fs::create_dir_all(&legacy_entries)?;
+        fs::write(legacy_entries.join("legacy_note.md"), "old note")?;
+
+        // 2. Run any rjot command, which should trigger the migration
+        Command::cargo_bin("rjot")?
+            .arg("info")
+            .arg("--paths")
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Migrating your existing notes"));
+
+        // 3. Verify the new structure
+        assert!(
+            !rjot_dir.join("entries").exists(),
+            "Legacy entries dir should be gone"
+        );
+        let default_notebook = rjot_dir.join("notebooks").join("default");
+        assert!(
+            default_notebook.exists(),
+            "Default notebook should be created"
+        );
+        assert!(
+            default_notebook.join("legacy_note.md").exists(),
+            "Legacy note should be moved"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_info_stats_all_notebooks() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // Add one note to default, two to work
+        Command::cargo_bin("rjot")?
+            .arg("note 1")
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        std::thread::sleep(std::time::Duration::from_millis(1200));
+        Command::cargo_bin("rjot")?
+            .arg("note 2")
+            .args(["--notebook", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        std::thread::sleep(std::time::Duration::from_millis(1200));
+        Command::cargo_bin("rjot")?
+            .arg("note 3")
+            .args(["--notebook", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // Check stats for all
+        Command::cargo_bin("rjot")?
+            .args(["info", "--stats", "--all"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Stats for all notebooks combined"))
+            .stdout(predicate::str::contains("Total jots: 3"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tags_filter_is_scoped() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "work"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
 
         // Create two notes with the same tag in different notebooks
         Command::cargo_bin("rjot")?
-            .arg("personal task")
-            .args(["--tags", "todo"])
+            .arg("personal task")
+            .args(["--tags", "todo"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        std::thread::sleep(std::time::Duration::from_millis(1200));
+        Command::cargo_bin("rjot")?
+            .arg("work task")
+            .args(["--tags", "todo", "--notebook", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // Filter in the 'work' notebook
+        Command::cargo_bin("rjot")?
+            .args(["tags", "todo", "--notebook", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("work task"))
+            .stdout(predicate::str::contains("personal task").not());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_with_template_in_notebook() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let templates_dir = rjot_dir.join("templates");
+        fs::create_dir(&templates_dir)?;
+        fs::write(templates_dir.join("meeting.md"), "## Meeting Notes")?;
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        Command::cargo_bin("rjot")?
+            .args(["new", "--template", "meeting.md", "--notebook", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("EDITOR", "true") // Use `true` as a no-op editor
+            .assert()
+            .success();
+
+        let work_notebook = rjot_dir.join("notebooks").join("work");
+        let entry_path = fs::read_dir(work_notebook)?.next().unwrap()?.path();
+        let content = fs::read_to_string(entry_path)?;
+        assert!(content.contains("## Meeting Notes"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_is_scoped_and_global_search_works() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+
+        // 1. Create a couple of new notebooks
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "personal"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // 2. Create notes with a shared keyword in different notebooks
+        // Note in 'default'
+        Command::cargo_bin("rjot")?
+            .arg("A note about a database_migration in default.")
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        std::thread::sleep(std::time::Duration::from_millis(1200));
+
+        // Note in 'work'
+        Command::cargo_bin("rjot")?
+            .arg("Work note on the database_migration plan.")
+            .args(["--notebook", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        std::thread::sleep(std::time::Duration::from_millis(1200));
+
+        // A note without the keyword
+        Command::cargo_bin("rjot")?
+            .arg("A personal note about something else.")
+            .args(["--notebook", "personal"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // 3. Test that a normal find is scoped to the active notebook ('default')
+        Command::cargo_bin("rjot")?
+            .args(["find", "database_migration"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("in default"))
+            .stdout(predicate::str::contains("in work").not());
+
+        // 4. Test that `find --all` searches across all notebooks
+        Command::cargo_bin("rjot")?
+            .args(["find", "database_migration", "--all"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("NOTEBOOK")) // Check for new header
+            .stdout(predicate::str::contains("default"))
+            .stdout(predicate::str::contains("work"))
+            .stdout(predicate::str::contains("something else").not());
+
+        Ok(())
+    }
+}
+
+// Test module for renaming, deleting, archiving, and merging notebooks
+#[cfg(test)]
+mod notebook_lifecycle {
+    use super::*;
+
+    fn rjot(
+        rjot_dir: &Path,
+        args: &[&str],
+    ) -> Result<assert_cmd::assert::Assert, Box<dyn std::error::Error>> {
+        Ok(Command::cargo_bin("rjot")?
+            .args(args)
+            .env("RJOT_DIR", rjot_dir)
+            .assert())
+    }
+
+    #[test]
+    fn test_notebook_rename() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let notebooks = rjot_dir.join("notebooks");
+        fs::create_dir_all(notebooks.join("work"))?;
+        fs::write(notebooks.join("work/2025-01-01-100000.md"), "work jot")?;
+
+        rjot(&rjot_dir, &["notebook", "rename", "work", "job"])?
+            .success()
+            .stdout(predicate::str::contains("Renamed notebook 'work' to 'job'"));
+        assert!(!notebooks.join("work").exists());
+        assert!(notebooks.join("job/2025-01-01-100000.md").exists());
+
+        // Renaming onto an existing notebook is refused.
+        rjot(&rjot_dir, &["notebook", "rename", "job", "default"])?
+            .failure()
+            .stderr(predicate::str::contains("already exists"));
+
+        // A notebook made by hand under a name that `notebook new` refuses still works,
+        // and can be renamed to a valid name, but not to another invalid one.
+        fs::create_dir_all(notebooks.join("my notes"))?;
+        fs::write(notebooks.join("my notes/2025-01-02-100000.md"), "loose jot")?;
+        rjot(&rjot_dir, &["--notebook", "my notes", "list"])?
+            .success()
+            .stdout(predicate::str::contains("loose jot"));
+        rjot(&rjot_dir, &["notebook", "rename", "my notes", "our notes"])?
+            .failure()
+            .stderr(predicate::str::contains("Invalid notebook name"));
+        rjot(&rjot_dir, &["notebook", "rename", "my notes", "mine"])?.success();
+        assert!(notebooks.join("mine/2025-01-02-100000.md").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_notebook_delete_to_trash_and_purge() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let notebooks = rjot_dir.join("notebooks");
+        fs::create_dir_all(notebooks.join("scratch"))?;
+        fs::write(
+            notebooks.join("scratch/2025-01-01-100000.md"),
+            "scratch jot",
+        )?;
+        fs::create_dir_all(notebooks.join("temp"))?;
+
+        // Declining the prompt keeps the notebook.
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "delete", "scratch"])
+            .env("RJOT_DIR", &rjot_dir)
+            .write_stdin("n\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Deletion aborted."));
+        assert!(notebooks.join("scratch").exists());
+
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "delete", "scratch"])
+            .env("RJOT_DIR", &rjot_dir)
+            .write_stdin("y\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("to the trash"));
+        assert!(!notebooks.join("scratch").exists());
+        let trashed = fs::read_dir(rjot_dir.join(".trash"))?
+            .next()
+            .unwrap()?
+            .path();
+        assert!(trashed.join("2025-01-01-100000.md").exists());
+
+        rjot(
+            &rjot_dir,
+            &["notebook", "delete", "temp", "--force", "--purge"],
+        )?
+        .success()
+        .stdout(predicate::str::contains("Permanently deleted"));
+        assert!(!notebooks.join("temp").exists());
+
+        // The active notebook can't be deleted.
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "delete", "default", "--force"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("RJOT_ACTIVE_NOTEBOOK", "default")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("active notebook"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_notebook_archive_hides_from_global_views() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let notebooks = rjot_dir.join("notebooks");
+        fs::create_dir_all(notebooks.join("old"))?;
+        fs::write(
+            notebooks.join("old/2024-01-01-100000.md"),
+            "---\ntags:\n- legacy\n---\n\nneedle in the archive",
+        )?;
+
+        rjot(&rjot_dir, &["notebook", "archive", "old"])?
+            .success()
+            .stdout(predicate::str::contains(
+                "Successfully archived notebook 'old'",
+            ));
+
+        rjot(&rjot_dir, &["notebook", "list"])?
+            .success()
+            .stdout(predicate::str::contains("old").not());
+        rjot(&rjot_dir, &["notebook", "list", "--all"])?
+            .success()
+            .stdout(predicate::str::contains("old (archived)"));
+
+        rjot(&rjot_dir, &["find", "needle", "--all"])?
+            .success()
+            .stdout(predicate::str::contains("No jots found."));
+        rjot(
+            &rjot_dir,
+            &["find", "needle", "--all", "--include-archived"],
+        )?
+        .success()
+        .stdout(predicate::str::contains("needle in the archive"));
+        rjot(&rjot_dir, &["--notebook", "old", "find", "needle"])?
+            .success()
+            .stdout(predicate::str::contains("needle in the archive"));
+
+        rjot(&rjot_dir, &["info", "--stats", "--all"])?
+            .success()
+            .stdout(predicate::str::contains("legacy").not());
+        rjot(
+            &rjot_dir,
+            &["info", "--stats", "--all", "--include-archived"],
+        )?
+        .success()
+        .stdout(predicate::str::contains("legacy"));
+
+        rjot(&rjot_dir, &["notebook", "unarchive", "old"])?.success();
+        rjot(&rjot_dir, &["notebook", "list"])?
+            .success()
+            .stdout(predicate::str::contains("old"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_notebook_merge_handles_id_collisions() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let notebooks = rjot_dir.join("notebooks");
+        fs::create_dir_all(notebooks.join("inbox"))?;
+        fs::write(notebooks.join("inbox/2025-01-01-100000.md"), "from inbox")?;
+        fs::write(
+            notebooks.join("inbox/2025-01-02-100000.md"),
+            "only in inbox",
+        )?;
+        fs::write(
+            notebooks.join("default/2025-01-01-100000.md"),
+            "from default",
+        )?;
+
+        rjot(&rjot_dir, &["notebook", "merge", "inbox", "default"])?
+            .success()
+            .stdout(predicate::str::contains("saved as '2025-01-01-100001'"))
+            .stdout(predicate::str::contains("Merged 2 jot(s)"));
+
+        let default = notebooks.join("default");
+        assert!(!notebooks.join("inbox").exists());
+        assert_eq!(
+            fs::read_to_string(default.join("2025-01-01-100000.md"))?,
+            "from default"
+        );
+        assert_eq!(
+            fs::read_to_string(default.join("2025-01-01-100001.md"))?,
+            "from inbox"
+        );
+        assert!(default.join("2025-01-02-100000.md").exists());
+        Ok(())
+    }
+
+    /// Tests that merging away the active notebook makes the destination active.
+    #[test]
+    fn test_notebook_merge_moves_active_notebook() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        fs::create_dir_all(rjot_dir.join("notebooks").join("inbox"))?;
+        rjot(&rjot_dir, &["notebook", "use", "inbox"])?.success();
+        rjot(&rjot_dir, &["inbox jot"])?.success();
+
+        rjot(&rjot_dir, &["notebook", "merge", "inbox", "default"])?
+            .success()
+            .stdout(predicate::str::contains(
+                "Active notebook set to 'default'.",
+            ));
+        rjot(&rjot_dir, &["notebook", "status"])?
+            .success()
+            .stdout(predicate::str::contains("Active notebook: default"));
+        rjot(&rjot_dir, &["list"])?
+            .success()
+            .stdout(predicate::str::contains("inbox jot"));
+        Ok(())
+    }
+}
+
+// Test module for per-notebook settings in `notebook.toml`
+#[cfg(test)]
+mod notebook_config {
+    use super::*;
+
+    #[test]
+    fn test_default_tags_are_added_to_new_jots() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let default = rjot_dir.join("notebooks").join("default");
+        fs::write(
+            default.join("notebook.toml"),
+            "default_tags = [\"work\", \"rust\"]\n",
+        )?;
+
+        Command::cargo_bin("rjot")?
+            .arg("a tagged note")
+            .args(["--tags", "rust,urgent"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        let entry_path = first_jot(&default)?;
+        let content = fs::read_to_string(entry_path)?;
+        assert!(content.contains("- urgent"));
+        assert!(content.contains("- work"));
+        assert_eq!(content.matches("- rust").count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_count_and_default_template() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let default = rjot_dir.join("notebooks").join("default");
+        fs::write(
+            default.join("notebook.toml"),
+            "list_count = 1\ndefault_template = \"standup\"\n",
+        )?;
+        fs::create_dir_all(rjot_dir.join("templates"))?;
+        fs::write(rjot_dir.join("templates/standup.md"), "## Standup")?;
+        fs::write(default.join("2025-01-01-100000.md"), "older jot")?;
+        fs::write(default.join("2025-01-02-100000.md"), "newer jot")?;
+
+        Command::cargo_bin("rjot")?
+            .arg("list")
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("newer jot"))
+            .stdout(predicate::str::contains("older jot").not());
+
+        Command::cargo_bin("rjot")?
+            .arg("new")
+            .env("RJOT_DIR", &rjot_dir)
+            .env("EDITOR", "true")
+            .assert()
+            .success();
+        let has_standup = fs::read_dir(&default)?.any(|entry| {
+            fs::read_to_string(entry.unwrap().path()).is_ok_and(|c| c == "## Standup")
+        });
+        assert!(has_standup, "Expected `new` to use the notebook's template");
+        Ok(())
+    }
+
+    #[test]
+    fn test_notebook_list_shows_counts_and_descriptions() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let work = rjot_dir.join("notebooks").join("work");
+        fs::create_dir_all(&work)?;
+        fs::write(
+            work.join("notebook.toml"),
+            "display_name = \"Work\"\ndescription = \"Client projects\"\n",
+        )?;
+        fs::write(work.join("2025-01-01-100000.md"), "one")?;
+        fs::write(work.join("2025-01-02-100000.md"), "two")?;
+
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "list"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("0 jots"))
+            .stdout(predicate::str::contains("2 jots  Work: Client projects"));
+        Ok(())
+    }
+
+    fn first_jot(dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "md") {
+                return Ok(path);
+            }
+        }
+        Err("no jot found".into())
+    }
+}
+
+// Test module for layered preferences and the `config` command
+#[cfg(test)]
+mod config {
+    use super::*;
+
+    fn rjot(rjot_dir: &Path) -> Result<Command, Box<dyn std::error::Error>> {
+        let mut cmd = Command::cargo_bin("rjot")?;
+        cmd.env("RJOT_DIR", rjot_dir)
+            .env("RJOT_SYSTEM_CONFIG", rjot_dir.join("system.toml"));
+        Ok(cmd)
+    }
+
+    #[test]
+    fn test_config_layers() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        rjot(&rjot_dir)?
+            .args(["config", "set", "list_count", "2", "--scope", "system"])
+            .assert()
+            .success();
+        rjot(&rjot_dir)?
+            .args(["config", "set", "list_count", "3"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Set list_count = 3 in config.toml.",
+            ));
+        rjot(&rjot_dir)?
+            .args(["config", "get", "list_count"])
+            .assert()
+            .success()
+            .stdout("3\n");
+
+        rjot(&rjot_dir)?
+            .args(["config", "set", "list_count", "4", "--scope", "notebook"])
+            .assert()
+            .success();
+        rjot(&rjot_dir)?
+            .args(["config", "list"])
+            .assert()
+            .success()
+            .stdout(predicate::str::is_match(
+                r"list_count\s+= 4\s+\(notebook\)",
+            )?);
+        rjot(&rjot_dir)?
+            .args(["config", "get", "list_count"])
+            .env("RJOT_LIST_COUNT", "5")
+            .assert()
+            .success()
+            .stdout("5\n");
+
+        rjot(&rjot_dir)?
+            .args(["config", "unset", "list_count", "--scope", "notebook"])
+            .assert()
+            .success();
+        rjot(&rjot_dir)?
+            .args(["config", "get", "list_count"])
+            .assert()
+            .success()
+            .stdout("3\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_rejects_bad_values() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        rjot(&rjot_dir)?
+            .args(["config", "set", "colour", "true"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Unknown setting 'colour'"));
+        rjot(&rjot_dir)?
+            .args(["config", "set", "prompt", "{notebook} {tasks}> "])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Unknown placeholder '{tasks}' in prompt. Use {notebook}, {pending}, {today}.",
+            ));
+        rjot(&rjot_dir)?
+            .args(["config", "set", "timezone", "Mars/Base"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Unknown time zone"));
+        rjot(&rjot_dir)?
+            .args(["config", "set", "date_format", "%Q"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid date_format"));
+        assert!(!rjot_dir.join("config.toml").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_count_and_timezone_preferences() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let default = rjot_dir.join("notebooks").join("default");
+        for day in 1..=4 {
+            fs::write(
+                default.join(format!("2025-01-0{day}-100000.md")),
+                format!("jot {day}"),
+            )?;
+        }
+        fs::write(
+            rjot_dir.join("config.toml"),
+            "list_count = 2\ntimezone = \"Etc/GMT+12\"\n",
+        )?;
+
+        rjot(&rjot_dir)?
+            .arg("list")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("jot 3"))
+            .stdout(predicate::str::contains("jot 2").not());
+
+        rjot(&rjot_dir)?
+            .arg("a jot from far away")
+            .assert()
+            .success();
+        let utc_minus_12 = chrono::Utc::now() - chrono::Duration::hours(12);
+        let expected_prefix = utc_minus_12.format("%Y-%m-%d").to_string();
+        let ids: Vec<String> = fs::read_dir(&default)?
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert!(
+            ids.iter().any(|id| id.starts_with(&expected_prefix)),
+            "Expected a jot ID starting with {expected_prefix}, got {ids:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_columns() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let default = rjot_dir.join("notebooks").join("default");
+        fs::write(
+            default.join("2025-01-01-100000.md"),
+            "---\ntags:\n- work\npinned: true\n---\nPlan the week\n- [x] one\n- [ ] two",
+        )?;
+        fs::write(default.join("2025-01-02-100000.md"), "A long first line")?;
+
+        rjot(&rjot_dir)?
+            .arg("list")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("TASKS"))
+            .stdout(predicate::str::contains("* 2025-01-01-100000"))
+            .stdout(predicate::str::contains("1/2   Plan the week     #work\n"))
+            .stdout(predicate::str::contains("\x1b[").not());
+
+        rjot(&rjot_dir)?
+            .args(["config", "set", "list_columns", "id,words"])
+            .assert()
+            .success();
+        rjot(&rjot_dir)?
+            .arg("list")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("2025-01-02-100000     4\n"))
+            .stdout(predicate::str::contains("A long first line").not());
+
+        rjot(&rjot_dir)?
+            .args(["list", "--columns", "title,notebook"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("A long first line default\n"));
+        rjot(&rjot_dir)?
+            .args(["config", "list", "--columns", "title", "--no-color"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("list_columns     = title"))
+            .stdout(predicate::str::contains("(flags)"));
+        rjot(&rjot_dir)?
+            .args(["list", "--columns", "id,size"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Unknown column 'size'"));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_editor_preference_with_arguments() -> TestResult {
+        use std::os::unix::fs::PermissionsExt;
+        let (temp_dir, rjot_dir) = setup();
+        let script_path = temp_dir.path().join("editor.sh");
+        // Writes the first argument, if it isn't the jot's path, into the jot.
+        fs::write(
+            &script_path,
+            "#!/bin/sh\nfor last; do :; done\necho \"${1%\"$last\"}\" > \"$last\"",
+        )?;
+        fs::set_permissions(&script_path, fs::Permissions::from_mode(0o755))?;
+        fs::write(
+            rjot_dir.join("config.toml"),
+            format!("editor = \"{} --from-config\"\n", script_path.display()),
+        )?;
+
+        rjot(&rjot_dir)?
+            .arg("new")
+            .env("EDITOR", "false")
+            .assert()
+            .success();
+        let entries_dir = rjot_dir.join("notebooks").join("default");
+        let entry_path = fs::read_dir(&entries_dir)?.next().unwrap()?.path();
+        assert_eq!(fs::read_to_string(&entry_path)?.trim(), "--from-config");
+
+        // A path with spaces runs as one program, whole or quoted with arguments.
+        let spaced_dir = temp_dir.path().join("my editors");
+        fs::create_dir(&spaced_dir)?;
+        let spaced_path = spaced_dir.join("editor.sh");
+        fs::rename(&script_path, &spaced_path)?;
+        fs::remove_file(rjot_dir.join("config.toml"))?;
+        rjot(&rjot_dir)?
+            .args(["edit", "--last"])
+            .env("EDITOR", &spaced_path)
+            .assert()
+            .success();
+        assert_eq!(fs::read_to_string(&entry_path)?.trim(), "");
+        rjot(&rjot_dir)?
+            .args(["edit", "--last"])
+            .env("EDITOR", format!("'{}' 'two words'", spaced_path.display()))
+            .assert()
+            .success();
+        assert_eq!(fs::read_to_string(&entry_path)?.trim(), "two words");
+        Ok(())
+    }
+}
+
+// Test module for moving and copying jots between notebooks
+#[cfg(test)]
+mod transfer {
+    use super::*;
+
+    fn setup_notebooks() -> (TempDir, PathBuf) {
+        let (temp_dir, rjot_dir) = setup();
+        let notebooks = rjot_dir.join("notebooks");
+        fs::create_dir_all(notebooks.join("work")).unwrap();
+        let default = notebooks.join("default");
+        fs::write(
+            default.join("2025-07-01-090000.md"),
+            "---\ntags:\n- rust\n---\n\nrust jot",
+        )
+        .unwrap();
+        fs::write(default.join("2025-07-02-090000.md"), "plain jot").unwrap();
+        fs::write(default.join("2025-08-01-090000.md"), "august jot").unwrap();
+        (temp_dir, rjot_dir)
+    }
+
+    #[test]
+    fn test_move_and_copy_single_jot() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup_notebooks();
+        let notebooks = rjot_dir.join("notebooks");
+
+        Command::cargo_bin("rjot")?
+            .args(["move", "2025-07-02", "--to", "work"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
-            .success();
-        std::thread::sleep(std::time::Duration::from_millis(1200));
+            .success()
+            .stdout(predicate::str::contains(
+                "Moved '2025-07-02-090000' to 'work'.",
+            ));
+        assert!(!notebooks.join("default/2025-07-02-090000.md").exists());
+        assert!(notebooks.join("work/2025-07-02-090000.md").exists());
+
         Command::cargo_bin("rjot")?
-            .arg("work task")
-            .args(["--tags", "todo", "--notebook", "work"])
+            .args(["copy", "--last", "--to", "work"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
+        assert!(notebooks.join("default/2025-08-01-090000.md").exists());
+        assert!(notebooks.join("work/2025-08-01-090000.md").exists());
 
-        // Filter in the 'work' notebook
+        // Copying the same jot again gets the next free ID.
         Command::cargo_bin("rjot")?
-            .args(["tags", "todo", "--notebook", "work"])
+            .args(["cp", "--last", "--to", "work"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success()
-            .stdout(predicate::str::contains("work task"))
-            .stdout(predicate::str::contains("personal task").not());
+            .stdout(predicate::str::contains("as '2025-08-01-090001'"));
+        assert_eq!(
+            fs::read_to_string(notebooks.join("work/2025-08-01-090001.md"))?,
+            "august jot"
+        );
 
+        Command::cargo_bin("rjot")?
+            .args(["move", "--last", "--to", "missing"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Notebook 'missing' not found"));
         Ok(())
     }
 
     #[test]
-    fn test_new_with_template_in_notebook() -> TestResult {
+    fn test_bulk_move_by_tag_and_date() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup_notebooks();
+        let notebooks = rjot_dir.join("notebooks");
+
+        Command::cargo_bin("rjot")?
+            .args(["move", "--tag", "rust", "--to", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        assert!(notebooks.join("work/2025-07-01-090000.md").exists());
+        assert!(notebooks.join("default/2025-07-02-090000.md").exists());
+
+        Command::cargo_bin("rjot")?
+            .args(["move", "--on", "2025-07-01..2025-07-31", "--to", "work"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Moved '2025-07-02-090000'"));
+        assert!(notebooks.join("work/2025-07-02-090000.md").exists());
+        assert!(notebooks.join("default/2025-08-01-090000.md").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_move_reencrypts_for_destination() -> TestResult {
         let (_temp_dir, rjot_dir) = setup();
-        let templates_dir = rjot_dir.join("templates");
-        fs::create_dir(&templates_dir)?;
-        fs::write(templates_dir.join("meeting.md"), "## Meeting Notes")?;
+        fs::create_dir_all(rjot_dir.join("notebooks").join("vault"))?;
         Command::cargo_bin("rjot")?
-            .args(["notebook", "new", "work"])
+            .args(["--notebook", "vault", "encrypt"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        Command::cargo_bin("rjot")?
+            .args(["--notebook", "vault", "a vault secret"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
 
         Command::cargo_bin("rjot")?
-            .args(["new", "--template", "meeting.md", "--notebook", "work"])
+            .args(["--notebook", "vault", "move", "--last", "--to", "default"])
             .env("RJOT_DIR", &rjot_dir)
-            .env("EDITOR", "true") // Use `true` as a no-op editor
             .assert()
             .success();
 
-        let work_notebook = rjot_dir.join("notebooks").join("work");
-        let entry_path = fs::read_dir(work_notebook)?.next().unwrap()?.path();
-        let content = fs::read_to_string(entry_path)?;
-        assert!(content.contains("## Meeting Notes"));
+        let moved = fs::read_dir(rjot_dir.join("notebooks").join("default"))?
+            .next()
+            .unwrap()?
+            .path();
+        assert_eq!(fs::read_to_string(moved)?, "a vault secret");
+        Ok(())
+    }
+}
+
+// Test module for error handling
+#[cfg(test)]
+mod error_handling {
+    use super::*;
 
+    #[test]
+    fn test_fails_on_invalid_notebook_name() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "invalid/name"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid notebook name"));
         Ok(())
     }
 
     #[test]
-    fn test_find_is_scoped_and_global_search_works() -> TestResult {
+    #[cfg(not(windows))]
+    fn test_select_checks_options_before_opening() -> TestResult {
         let (_temp_dir, rjot_dir) = setup();
 
-        // 1. Create a couple of new notebooks
         Command::cargo_bin("rjot")?
-            .args(["notebook", "new", "work"])
+            .args(["select", "--action", "archive"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
-            .success();
+            .failure()
+            .stderr(predicate::str::contains("copy-id"));
 
         Command::cargo_bin("rjot")?
-            .args(["notebook", "new", "personal"])
+            .args(["select", "--action", "move", "--to", "wrok"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
-            .success();
+            .failure()
+            .stderr(predicate::str::contains("Notebook 'wrok' not found."));
 
-        // 2. Create notes with a shared keyword in different notebooks
-        // Note in 'default'
         Command::cargo_bin("rjot")?
-            .arg("A note about a database_migration in default.")
+            .args(["select", "--include-archived"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
-            .success();
-        std::thread::sleep(std::time::Duration::from_millis(1200));
+            .failure()
+            .stderr(predicate::str::contains("--all"));
+        Ok(())
+    }
 
-        // Note in 'work'
+    #[test]
+    fn test_tui_needs_a_terminal() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
         Command::cargo_bin("rjot")?
-            .arg("Work note on the database_migration plan.")
-            .args(["--notebook", "work"])
+            .arg("tui")
             .env("RJOT_DIR", &rjot_dir)
             .assert()
-            .success();
-        std::thread::sleep(std::time::Duration::from_millis(1200));
+            .failure()
+            .stderr(predicate::str::contains("needs an interactive terminal"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_notebook_is_not_created() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        fs::create_dir_all(rjot_dir.join("notebooks").join("work"))?;
 
-        // A note without the keyword
         Command::cargo_bin("rjot")?
-            .arg("A personal note about something else.")
-            .args(["--notebook", "personal"])
+            .args(["--notebook", "wrok", "a typo"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
-            .success();
+            .failure()
+            .stderr(predicate::str::contains("Notebook 'wrok' not found."))
+            .stderr(predicate::str::contains("Did you mean 'work'?"));
+        assert!(!rjot_dir.join("notebooks").join("wrok").exists());
 
-        // 3. Test that a normal find is scoped to the active notebook ('default')
         Command::cargo_bin("rjot")?
-            .args(["find", "database_migration"])
+            .args(["--notebook", "../escaped", "list"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
-            .success()
-            .stdout(predicate::str::contains("in default"))
-            .stdout(predicate::str::contains("in work").not());
+            .failure()
+            .stderr(predicate::str::contains("Invalid notebook name"));
+        assert!(!rjot_dir.join("escaped").exists());
 
-        // 4. Test that `find --all` searches across all notebooks
+        // Notebook management still works while the active notebook is missing.
         Command::cargo_bin("rjot")?
-            .args(["find", "database_migration", "--all"])
+            .args(["notebook", "list"])
             .env("RJOT_DIR", &rjot_dir)
+            .env("RJOT_ACTIVE_NOTEBOOK", "wrok")
             .assert()
             .success()
-            .stdout(predicate::str::contains("NOTEBOOK")) // Check for new header
-            .stdout(predicate::str::contains("default"))
-            .stdout(predicate::str::contains("work"))
-            .stdout(predicate::str::contains("something else").not());
-
+            .stdout(predicate::str::contains("work"));
         Ok(())
     }
-}
-
-// Test module for error handling
-#[cfg(test)]
-mod error_handling {
-    use super::*;
 
     #[test]
-    fn test_fails_on_invalid_notebook_name() -> TestResult {
+    fn test_auto_create_notebooks_opt_in() -> TestResult {
         let (_temp_dir, rjot_dir) = setup();
+        fs::write(
+            rjot_dir.join("config.toml"),
+            "auto_create_notebooks = true\n",
+        )?;
+
         Command::cargo_bin("rjot")?
-            .args(["notebook", "new", "invalid/name"])
+            .args(["--notebook", "fresh", "a first note"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
-            .failure()
-            .stderr(predicate::str::contains("Invalid notebook name"));
+            .success()
+            .stderr(predicate::str::contains("Created new notebook 'fresh'."));
+        assert_eq!(
+            fs::read_dir(rjot_dir.join("notebooks").join("fresh"))?.count(),
+            1
+        );
         Ok(())
     }
 
@@ -713,48 +2315,227 @@ fn test_full_encryption_and_decryption_lifecycle_across_notebooks() -> TestResul
         .assert()
         .success();
 
-    // 4. Verify both files on disk are encrypted
-    let default_note_path = fs::read_dir(rjot_dir.join("notebooks").join("default"))?
-        .next()
-        .unwrap()?
-        .path();
-    let secret_note_path = fs::read_dir(rjot_dir.join("notebooks").join("secrets"))?
-        .next()
-        .unwrap()?
-        .path();
-    assert!(fs::read(&default_note_path)?.starts_with(b"age-encryption.org"));
-    assert!(fs::read(&secret_note_path)?.starts_with(b"age-encryption.org"));
+    // 4. Verify both files on disk are encrypted
+    let default_note_path = fs::read_dir(rjot_dir.join("notebooks").join("default"))?
+        .next()
+        .unwrap()?
+        .path();
+    let secret_note_path = fs::read_dir(rjot_dir.join("notebooks").join("secrets"))?
+        .next()
+        .unwrap()?
+        .path();
+    assert!(fs::read(&default_note_path)?.starts_with(b"age-encryption.org"));
+    assert!(fs::read(&secret_note_path)?.starts_with(b"age-encryption.org"));
+
+    // 5. Verify rjot can read them transparently
+    Command::cargo_bin("rjot")?
+        .args(["show", "--last"])
+        .env("RJOT_DIR", &rjot_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("default secret"));
+    Command::cargo_bin("rjot")?
+        .args(["show", "--last", "--notebook", "secrets"])
+        .env("RJOT_DIR", &rjot_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("special secret"));
+
+    // 6. Decrypt the entire journal
+    Command::cargo_bin("rjot")?
+        .args(["decrypt", "--force"])
+        .env("RJOT_DIR", &rjot_dir)
+        .assert()
+        .success();
+
+    // 7. Verify both files are now plaintext
+    assert_eq!(fs::read_to_string(default_note_path)?, "default secret");
+    assert_eq!(fs::read_to_string(secret_note_path)?, "special secret");
+
+    // 8. Verify identity/config files are gone
+    assert!(!rjot_dir.join("identity.txt").exists());
+    assert!(!rjot_dir.join("config.toml").exists());
+
+    Ok(())
+}
+
+// Test module for per-notebook encryption.
+#[cfg(test)]
+mod notebook_encryption {
+    use super::*;
+
+    /// Tests that a single notebook can be encrypted with its own key while others
+    /// stay in plaintext, and that it can be decrypted again on its own.
+    #[test]
+    fn test_encrypt_and_decrypt_single_notebook() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let secrets_dir = rjot_dir.join("notebooks").join("secrets");
+
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "secrets"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // 1. An existing plaintext note should be encrypted when the notebook is.
+        Command::cargo_bin("rjot")?
+            .arg("written before encryption")
+            .args(["--notebook", "secrets"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        Command::cargo_bin("rjot")?
+            .args(["encrypt", "--notebook", "secrets"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Encrypted 1 jot(s)"));
+
+        assert!(secrets_dir.join("identity.txt").exists());
+        assert!(secrets_dir.join("notebook.toml").exists());
+        assert!(!rjot_dir.join("identity.txt").exists());
+
+        std::thread::sleep(std::time::Duration::from_millis(1200));
+
+        // 2. New notes follow each notebook's policy.
+        Command::cargo_bin("rjot")?
+            .arg("a secret note")
+            .args(["--notebook", "secrets"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        Command::cargo_bin("rjot")?
+            .arg("a public note")
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        let mut secret_notes: Vec<_> = fs::read_dir(&secrets_dir)?
+            .map(|r| r.unwrap().path())
+            .filter(|p| p.extension().is_some_and(|e| e == "md"))
+            .collect();
+        secret_notes.sort();
+        assert_eq!(secret_notes.len(), 2);
+        for path in &secret_notes {
+            assert!(fs::read(path)?.starts_with(b"age-encryption.org"));
+        }
+        let public_note = fs::read_dir(rjot_dir.join("notebooks").join("default"))?
+            .next()
+            .unwrap()?
+            .path();
+        assert_eq!(fs::read_to_string(public_note)?, "a public note");
+
+        // 3. Listing the notebook ignores its config and key files.
+        Command::cargo_bin("rjot")?
+            .args(["list", "--notebook", "secrets"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("a secret note"))
+            .stdout(predicate::str::contains("written before encryption"))
+            .stdout(predicate::str::contains("notebook").not());
+
+        // 4. Decrypt only this notebook.
+        Command::cargo_bin("rjot")?
+            .args(["decrypt", "--force", "--notebook", "secrets"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        assert_eq!(
+            fs::read_to_string(&secret_notes[1])?,
+            "a secret note",
+            "Notes should be plaintext after decrypting the notebook"
+        );
+        assert!(!secrets_dir.join("identity.txt").exists());
+
+        Ok(())
+    }
+
+    /// Tests that decrypting the journal turns off `encrypt = true` in notebooks that
+    /// relied on the global key, so they can still be written to.
+    #[test]
+    fn test_global_decrypt_resets_notebooks_using_global_key() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let default = rjot_dir.join("notebooks").join("default");
+        Command::cargo_bin("rjot")?
+            .args(["init", "--encrypt"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        fs::write(default.join("notebook.toml"), "encrypt = true\n")?;
+
+        Command::cargo_bin("rjot")?
+            .args(["decrypt", "--force"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Turned off `encrypt = true`"));
+        assert!(!fs::read_to_string(default.join("notebook.toml"))?.contains("encrypt"));
+
+        Command::cargo_bin("rjot")?
+            .arg("written after decrypting")
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        let jot = fs::read_dir(&default)?
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.extension().is_some_and(|ext| ext == "md"))
+            .unwrap();
+        assert_eq!(fs::read_to_string(jot)?, "written after decrypting");
 
-    // 5. Verify rjot can read them transparently
-    Command::cargo_bin("rjot")?
-        .args(["show", "--last"])
-        .env("RJOT_DIR", &rjot_dir)
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("default secret"));
-    Command::cargo_bin("rjot")?
-        .args(["show", "--last", "--notebook", "secrets"])
-        .env("RJOT_DIR", &rjot_dir)
-        .assert()
-        .success()
-        .stdout(predicate::str::contains("special secret"));
+        Ok(())
+    }
 
-    // 6. Decrypt the entire journal
-    Command::cargo_bin("rjot")?
-        .args(["decrypt", "--force"])
-        .env("RJOT_DIR", &rjot_dir)
-        .assert()
-        .success();
+    /// Tests that a notebook decrypted on its own stays plaintext while
+    /// global encryption keeps applying to the other notebooks.
+    #[test]
+    fn test_plaintext_notebook_overrides_global_encryption() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
 
-    // 7. Verify both files are now plaintext
-    assert_eq!(fs::read_to_string(default_note_path)?, "default secret");
-    assert_eq!(fs::read_to_string(secret_note_path)?, "special secret");
+        Command::cargo_bin("rjot")?
+            .args(["init", "--encrypt"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "public"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        Command::cargo_bin("rjot")?
+            .args(["decrypt", "--force", "--notebook", "public"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
 
-    // 8. Verify identity/config files are gone
-    assert!(!rjot_dir.join("identity.txt").exists());
-    assert!(!rjot_dir.join("config.toml").exists());
+        Command::cargo_bin("rjot")?
+            .arg("public log entry")
+            .args(["--notebook", "public"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        Command::cargo_bin("rjot")?
+            .arg("private entry")
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
 
-    Ok(())
+        let public_note = fs::read_dir(rjot_dir.join("notebooks").join("public"))?
+            .map(|r| r.unwrap().path())
+            .find(|p| p.extension().is_some_and(|e| e == "md"))
+            .unwrap();
+        assert_eq!(fs::read_to_string(public_note)?, "public log entry");
+
+        let private_note = fs::read_dir(rjot_dir.join("notebooks").join("default"))?
+            .next()
+            .unwrap()?
+            .path();
+        assert!(fs::read(private_note)?.starts_with(b"age-encryption.org"));
+
+        Ok(())
+    }
 }
 
 // Test module for pinning feature.
@@ -1020,127 +2801,346 @@ mod tasks {
         )?;
         fs::write(entries_dir.join("tasks3.md"), "- [x] done 2")?;
 
-        // 2. Run `info --stats` and check the summary.
+        // 2. Run `info --stats` and check the summary.
+        Command::cargo_bin("rjot")?
+            .args(["info", "--stats"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Task Summary:"))
+            .stdout(predicate::str::contains("Completed: 2"))
+            .stdout(predicate::str::contains("Pending:   3"));
+
+        Ok(())
+    }
+}
+
+/// Test module for import/export feature
+#[cfg(test)]
+mod import_export {
+    use super::*;
+
+    #[test]
+    fn test_export_and_import_zip() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let notebook_name = "zip-test-notebook";
+        let output_zip = rjot_dir.join("export.zip");
+
+        // 1. Create a notebook and a note
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", notebook_name])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        Command::cargo_bin("rjot")?
+            .arg("a note for zip export")
+            .args(["--notebook", notebook_name])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // 2. Export the notebook to a zip file
+        Command::cargo_bin("rjot")?
+            .args([
+                "export",
+                notebook_name,
+                "--output",
+                output_zip.to_str().unwrap(),
+            ])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        assert!(output_zip.exists());
+
+        // 3. Import the notebook from the zip file
+        Command::cargo_bin("rjot")?
+            .args(["import", output_zip.to_str().unwrap()])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // 4. Verify the imported notebook and its content
+        let imported_notebook_path = rjot_dir.join("notebooks").join("export"); // "export" from file stem
+        assert!(imported_notebook_path.exists());
+        assert_eq!(fs::read_dir(&imported_notebook_path)?.count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_and_import_json() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let notebook_name = "json-test-notebook";
+        let notebook_path = rjot_dir.join("notebooks").join(notebook_name); // Path to the original notebook
+        let output_json = rjot_dir.join("export.json");
+
+        // 1. Create a notebook and a note
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", notebook_name])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        Command::cargo_bin("rjot")?
+            .arg("a note for json export")
+            .args(["--notebook", notebook_name])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // 2. Export the notebook to a json file
+        Command::cargo_bin("rjot")?
+            .args([
+                "export",
+                notebook_name,
+                "--format",
+                "json",
+                "--output",
+                output_json.to_str().unwrap(),
+            ])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        assert!(output_json.exists());
+
+        // 3. ✅ REMOVE the original notebook to simulate a restore
+        fs::remove_dir_all(&notebook_path)?;
+        assert!(
+            !notebook_path.exists(),
+            "Original notebook should be deleted before import"
+        );
+
+        // 4. Import the notebook from the json file. This should now succeed.
+        Command::cargo_bin("rjot")?
+            .args(["import", output_json.to_str().unwrap()])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        // 5. Verify the imported notebook
+        let imported_notebook_path = rjot_dir.join("notebooks").join(notebook_name);
+        assert!(imported_notebook_path.exists());
+        assert_eq!(fs::read_dir(&imported_notebook_path)?.count(), 1);
+
+        Ok(())
+    }
+}
+
+/// Test module for encrypted export archives.
+#[cfg(test)]
+mod encrypted_export {
+    use super::*;
+
+    /// Tests a passphrase-protected zip export and its transparent import.
+    #[test]
+    fn test_export_and_import_with_passphrase() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let output = rjot_dir.join("backup.zip");
+
+        Command::cargo_bin("rjot")?
+            .arg("a very private thought")
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        Command::cargo_bin("rjot")?
+            .args(["export", "default", "--encrypt", "--passphrase", "--output"])
+            .arg(&output)
+            .env("RJOT_DIR", &rjot_dir)
+            .env("RJOT_PASSPHRASE", "correct horse battery staple")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("(encrypted)"));
+
+        assert!(fs::read(&output)?.starts_with(b"age-encryption.org"));
+
+        // A wrong passphrase must not import anything.
+        Command::cargo_bin("rjot")?
+            .arg("import")
+            .arg(&output)
+            .env("RJOT_DIR", &rjot_dir)
+            .env("RJOT_PASSPHRASE", "wrong passphrase")
+            .assert()
+            .failure();
+        assert!(!rjot_dir.join("notebooks").join("backup").exists());
+
+        Command::cargo_bin("rjot")?
+            .arg("import")
+            .arg(&output)
+            .env("RJOT_DIR", &rjot_dir)
+            .env("RJOT_PASSPHRASE", "correct horse battery staple")
+            .assert()
+            .success();
+
+        Command::cargo_bin("rjot")?
+            .args(["list", "--notebook", "backup"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("a very private thought"));
+
+        Ok(())
+    }
+
+    /// Tests that an encrypted journal exports to an archive encrypted for its own key.
+    #[test]
+    fn test_encrypted_json_export_uses_journal_key() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let output = rjot_dir.join("journal.json.age");
+
+        Command::cargo_bin("rjot")?
+            .args(["init", "--encrypt"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        Command::cargo_bin("rjot")?
+            .args(["notebook", "new", "journal"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+        Command::cargo_bin("rjot")?
+            .arg("secret json note")
+            .args(["--notebook", "journal"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        Command::cargo_bin("rjot")?
+            .args(["export", "journal", "--format", "json", "--encrypt"])
+            .arg("--output")
+            .arg(&output)
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        let exported = fs::read(&output)?;
+        assert!(exported.starts_with(b"age-encryption.org"));
+        assert!(!String::from_utf8_lossy(&exported).contains("secret json note"));
+
+        // Remove the original notebook so the import can recreate it.
+        fs::remove_dir_all(rjot_dir.join("notebooks").join("journal"))?;
         Command::cargo_bin("rjot")?
-            .args(["info", "--stats"])
+            .arg("import")
+            .arg(&output)
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success();
+
+        Command::cargo_bin("rjot")?
+            .args(["show", "--last", "--notebook", "journal"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success()
-            .stdout(predicate::str::contains("Task Summary:"))
-            .stdout(predicate::str::contains("Completed: 2"))
-            .stdout(predicate::str::contains("Pending:   3"));
+            .stdout(predicate::str::contains("secret json note"));
 
         Ok(())
     }
-}
-
-/// Test module for import/export feature
-#[cfg(test)]
-mod import_export {
-    use super::*;
 
+    /// Tests that a raw export keeps the jots encrypted inside the archive.
     #[test]
-    fn test_export_and_import_zip() -> TestResult {
+    fn test_raw_export_keeps_jots_encrypted() -> TestResult {
         let (_temp_dir, rjot_dir) = setup();
-        let notebook_name = "zip-test-notebook";
-        let output_zip = rjot_dir.join("export.zip");
+        let output = rjot_dir.join("raw-backup.zip");
 
-        // 1. Create a notebook and a note
         Command::cargo_bin("rjot")?
-            .args(["notebook", "new", notebook_name])
+            .args(["init", "--encrypt"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
         Command::cargo_bin("rjot")?
-            .arg("a note for zip export")
-            .args(["--notebook", notebook_name])
+            .arg("raw secret")
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
 
-        // 2. Export the notebook to a zip file
         Command::cargo_bin("rjot")?
-            .args([
-                "export",
-                notebook_name,
-                "--output",
-                output_zip.to_str().unwrap(),
-            ])
+            .args(["export", "default", "--raw", "--output"])
+            .arg(&output)
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
 
-        assert!(output_zip.exists());
+        Command::cargo_bin("rjot")?
+            .arg("import")
+            .arg(&output)
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("1 jot(s) are still encrypted"));
 
-        // 3. Import the notebook from the zip file
+        let imported = fs::read_dir(rjot_dir.join("notebooks").join("raw-backup"))?
+            .next()
+            .unwrap()?
+            .path();
+        assert!(fs::read(imported)?.starts_with(b"age-encryption.org"));
+
+        // The global identity can still read the restored jot.
         Command::cargo_bin("rjot")?
-            .args(["import", output_zip.to_str().unwrap()])
+            .args(["show", "--last", "--notebook", "raw-backup"])
             .env("RJOT_DIR", &rjot_dir)
             .assert()
-            .success();
+            .success()
+            .stdout(predicate::str::contains("raw secret"));
 
-        // 4. Verify the imported notebook and its content
-        let imported_notebook_path = rjot_dir.join("notebooks").join("export"); // "export" from file stem
-        assert!(imported_notebook_path.exists());
-        assert_eq!(fs::read_dir(&imported_notebook_path)?.count(), 1);
+        // Raw exports only make sense for zip archives.
+        Command::cargo_bin("rjot")?
+            .args(["export", "default", "--raw", "--format", "json", "--output"])
+            .arg(rjot_dir.join("raw.json"))
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("only supported for zip"));
 
         Ok(())
     }
 
+    /// Tests that an archive's notebook config only restores the encryption policy, and
+    /// only for raw exports.
     #[test]
-    fn test_export_and_import_json() -> TestResult {
+    fn test_import_restores_only_encryption_policy() -> TestResult {
         let (_temp_dir, rjot_dir) = setup();
-        let notebook_name = "json-test-notebook";
-        let notebook_path = rjot_dir.join("notebooks").join(notebook_name); // Path to the original notebook
-        let output_json = rjot_dir.join("export.json");
+        let default = rjot_dir.join("notebooks").join("default");
+        fs::write(default.join("2025-07-01-090000.md"), "a jot")?;
+        fs::write(
+            default.join("notebook.toml"),
+            "editor = \"touch pwned\"\nencrypt = false\narchived = true\n",
+        )?;
 
-        // 1. Create a notebook and a note
-        Command::cargo_bin("rjot")?
-            .args(["notebook", "new", notebook_name])
-            .env("RJOT_DIR", &rjot_dir)
-            .assert()
-            .success();
+        let raw = rjot_dir.join("raw.zip");
         Command::cargo_bin("rjot")?
-            .arg("a note for json export")
-            .args(["--notebook", notebook_name])
+            .args(["export", "default", "--raw", "--output"])
+            .arg(&raw)
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
-
-        // 2. Export the notebook to a json file
         Command::cargo_bin("rjot")?
-            .args([
-                "export",
-                notebook_name,
-                "--format",
-                "json",
-                "--output",
-                output_json.to_str().unwrap(),
-            ])
+            .arg("import")
+            .arg(&raw)
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
+        let restored = fs::read_to_string(rjot_dir.join("notebooks/raw/notebook.toml"))?;
+        assert_eq!(restored, "encrypt = false\narchived = true\n");
 
-        assert!(output_json.exists());
-
-        // 3. ✅ REMOVE the original notebook to simulate a restore
-        fs::remove_dir_all(&notebook_path)?;
-        assert!(
-            !notebook_path.exists(),
-            "Original notebook should be deleted before import"
-        );
-
-        // 4. Import the notebook from the json file. This should now succeed.
+        // A config in an archive that wasn't made by `export --raw` is dropped.
+        let crafted = rjot_dir.join("crafted.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&crafted)?);
+        let options = zip::write::SimpleFileOptions::default();
+        zip.start_file("notebook.toml", options)?;
+        std::io::Write::write_all(&mut zip, b"editor = \"touch pwned\"\n")?;
+        zip.start_file("2025-07-01-090000.md", options)?;
+        std::io::Write::write_all(&mut zip, b"a jot")?;
+        zip.finish()?;
         Command::cargo_bin("rjot")?
-            .args(["import", output_json.to_str().unwrap()])
+            .arg("import")
+            .arg(&crafted)
             .env("RJOT_DIR", &rjot_dir)
             .assert()
             .success();
-
-        // 5. Verify the imported notebook
-        let imported_notebook_path = rjot_dir.join("notebooks").join(notebook_name);
-        assert!(imported_notebook_path.exists());
-        assert_eq!(fs::read_dir(&imported_notebook_path)?.count(), 1);
+        let imported = rjot_dir.join("notebooks").join("crafted");
+        assert!(imported.join("2025-07-01-090000.md").exists());
+        assert!(!imported.join("notebook.toml").exists());
 
         Ok(())
     }
@@ -1241,6 +3241,226 @@ mod templating {
 
         Ok(())
     }
+
+    /// Tests filters, conditionals, and prompting for variables that weren't passed with `-v`.
+    #[test]
+    fn test_template_language_and_prompts() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        let templates_dir = rjot_dir.join("templates");
+        fs::create_dir(&templates_dir)?;
+        fs::write(templates_dir.join("footer.md"), "-- {{ author | upper }}")?;
+        fs::write(
+            templates_dir.join("bug.md"),
+            "# {{ title | default(\"Untitled\") }} ({{ date | format(\"%Y\") }})\n\
+             {% if urgent %}\nURGENT\n{% endif %}\n\
+             Ticket: {{ ticket }}\n\
+             {% include \"footer\" %}",
+        )?;
+
+        Command::cargo_bin("rjot")?
+            .args(["new", "--template", "bug", "-v", "author=ada"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("EDITOR", "true")
+            .write_stdin("PROJ-7\n")
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Value for 'ticket'"));
+
+        let entries_dir = rjot_dir.join("notebooks").join("default");
+        let entry_path = fs::read_dir(entries_dir)?.next().unwrap()?.path();
+        let content = fs::read_to_string(entry_path)?;
+        let year = Local::now().format("%Y").to_string();
+        assert_eq!(
+            content,
+            format!("# Untitled ({year})\nTicket: PROJ-7\n-- ADA")
+        );
+
+        // Without an answer, the missing variable is an error rather than literal text.
+        Command::cargo_bin("rjot")?
+            .args(["new", "--template", "bug"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("EDITOR", "true")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("-v ticket=<value>"));
+        Ok(())
+    }
+
+    /// Tests creating, listing, showing, and deleting templates with `rjot template`.
+    #[test]
+    fn test_template_management() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+
+        Command::cargo_bin("rjot")?
+            .args(["template", "new", "meeting"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("EDITOR", "true")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Created template 'meeting'"));
+        assert!(rjot_dir.join("templates").join("meeting.md").exists());
+
+        Command::cargo_bin("rjot")?
+            .args(["template", "new", "meeting"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("EDITOR", "true")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("already exists"));
+
+        Command::cargo_bin("rjot")?
+            .args(["template", "list"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("meeting"));
+
+        Command::cargo_bin("rjot")?
+            .args(["template", "show", "meeting"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("description: meeting"));
+
+        Command::cargo_bin("rjot")?
+            .args(["template", "delete", "meeting", "--force"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Deleted template 'meeting'."));
+        assert!(!rjot_dir.join("templates").join("meeting.md").exists());
+
+        Command::cargo_bin("rjot")?
+            .args(["template", "show", "meeting"])
+            .env("RJOT_DIR", &rjot_dir)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Template 'meeting' not found"));
+        Ok(())
+    }
+
+    /// Tests that a template's frontmatter routes the jot to a notebook,
+    /// applies tags and pinned state, and prompts for described variables.
+    #[test]
+    fn test_template_frontmatter() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+        fs::create_dir_all(rjot_dir.join("notebooks").join("bugs"))?;
+        let templates_dir = rjot_dir.join("templates");
+        fs::create_dir(&templates_dir)?;
+        fs::write(
+            templates_dir.join("bug.md"),
+            "---\n\
+             template:\n  \
+             description: Bug report\n  \
+             notebook: bugs\n  \
+             tags: [bug]\n  \
+             pinned: true\n  \
+             variables:\n    \
+             title: Short summary\n\
+             ---\n\
+             # {{ title }}",
+        )?;
+
+        Command::cargo_bin("rjot")?
+            .args(["new", "-t", "bug"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("EDITOR", "true")
+            .write_stdin("Crash on start\n")
+            .assert()
+            .success()
+            .stderr(predicate::str::contains("Short summary (title): "));
+
+        assert_eq!(
+            fs::read_dir(rjot_dir.join("notebooks").join("default"))?.count(),
+            0
+        );
+        let entry_path = fs::read_dir(rjot_dir.join("notebooks").join("bugs"))?
+            .next()
+            .unwrap()?
+            .path();
+        let content = fs::read_to_string(entry_path)?;
+        assert!(content.contains("- bug"));
+        assert!(content.contains("pinned: true"));
+        assert!(content.contains("# Crash on start"));
+        assert!(!content.contains("template:"));
+        Ok(())
+    }
+
+    /// Tests the git context variables and `{{ cmd:... }}` hooks.
+    #[test]
+    fn test_template_context_hooks() -> TestResult {
+        let (temp_dir, rjot_dir) = setup();
+        let project = temp_dir.path().join("project");
+        fs::create_dir(&project)?;
+        let repo = Repository::init(&project)?;
+        let signature = Signature::now("rjot-test", "test@rjot.com")?;
+        let mut parents = Vec::new();
+        for message in ["First commit", "Second commit"] {
+            let tree = repo.find_tree(repo.index()?.write_tree()?)?;
+            let parent_refs: Vec<_> = parents.iter().collect();
+            let oid = repo.commit(
+                Some("HEAD"),
+                &signature,
+                &signature,
+                message,
+                &tree,
+                &parent_refs,
+            )?;
+            parents = vec![repo.find_commit(oid)?];
+        }
+        fs::write(project.join("wip.rs"), "fn main() {}")?;
+
+        let templates_dir = rjot_dir.join("templates");
+        fs::create_dir(&templates_dir)?;
+        fs::write(
+            templates_dir.join("log.md"),
+            "---\n\
+             template:\n  \
+             commits: 1\n  \
+             commands:\n    \
+             - echo from the hook\n    \
+             - printf '%s|' \"two words\" one\n\
+             ---\n\
+             {% for commit in commits %}* {{ commit }}\n{% endfor %}\
+             Dirty: {{ dirty_files }}\n\
+             User: {{ user }}\n\
+             Quoted: {{cmd:printf '%s|' \"two words\" one}}\n\
+             Hook: {{cmd:echo from the hook}}",
+        )?;
+        fs::write(templates_dir.join("sneaky.md"), "{{cmd:echo not allowed}}")?;
+
+        Command::cargo_bin("rjot")?
+            .current_dir(&project)
+            .args(["new", "-t", "log"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("EDITOR", "true")
+            .env("USER", "ada")
+            .assert()
+            .success();
+        let entries_dir = rjot_dir.join("notebooks").join("default");
+        let entry_path = fs::read_dir(&entries_dir)?.next().unwrap()?.path();
+        let content = fs::read_to_string(entry_path)?;
+        assert!(is_match(r"^\* [0-9a-f]{7} Second commit\n")
+            .unwrap()
+            .eval(&content));
+        assert!(!content.contains("First commit"));
+        assert!(content.contains("Dirty: wip.rs\n"));
+        assert!(content.contains("User: ada\n"));
+        assert!(content.contains("Quoted: two words|one|\n"));
+        assert!(content.ends_with("Hook: from the hook"));
+
+        Command::cargo_bin("rjot")?
+            .current_dir(&project)
+            .args(["new", "-t", "sneaky"])
+            .env("RJOT_DIR", &rjot_dir)
+            .env("EDITOR", "true")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "isn't allowed to run 'echo not allowed'",
+            ));
+        Ok(())
+    }
 }
 
 // A module for testing the interactive shell.
@@ -1293,4 +3513,216 @@ mod shell {
 
         Ok(())
     }
+
+    /// Tests that shell lines are split like a real shell: quotes, escapes, and variables.
+    #[test]
+    fn test_shell_quoting() -> TestResult {
+        let (_temp_dir, rjot_dir) = setup();
+
+        let mut process = Command::new(env!("CARGO_BIN_EXE_rjot"))
+            .arg("shell")
+            .env("RJOT_DIR", &rjot_dir)
+            .env("RJOT_TEST_PROJECT", "icarus")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        {
+            let stdin = process.stdin.as_mut().expect("Failed to open stdin");
+            stdin.write_all(b"t 'Write unit tests'\n")?;
+            stdin.write_all(b"find 'unit tests'\n")?;
+            stdin.write_all(b"\"fighting the borrow checker\" on $RJOT_TEST_PROJECT\n")?;
+            stdin.write_all(b"find \"borrow checker\"\n")?;
+            stdin.write_all(b"find 'unterminated\n")?;
+            stdin.write_all(b"exit\n")?;
+        }
+        let output = process.wait_with_output()?;
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout)?;
+        assert!(stdout.contains("- [ ] Write unit tests"));
+        assert!(stdout.contains("fighting the borrow checker on icarus"));
+        let stderr = String::from_utf8(output.stderr)?;
+        assert!(stderr.contains("Unterminated single quote"));
+        Ok(())
+    }
+
+    /// Runs the shell in `cwd`, feeds it `input`, and returns everything it printed.
+    fn run_shell(
+        rjot_dir: &Path,
+        cwd: &Path,
+        input: &str,
+        envs: &[(&str, &str)],
+    ) -> Result<std::process::Output, Box<dyn std::error::Error>> {
+        let mut process = Command::new(env!("CARGO_BIN_EXE_rjot"))
+            .arg("shell")
+            .current_dir(cwd)
+            .env("RJOT_DIR", rjot_dir)
+            .envs(envs.iter().copied())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        process
+            .stdin
+            .as_mut()
+            .expect("Failed to open stdin")
+            .write_all(input.as_bytes())?;
+        let output = process.wait_with_output()?;
+        assert!(output.status.success());
+        Ok(output)
+    }
+
+    /// Tests that history is kept in the rjot directory, limited in size, and never
+    /// records jot bodies written to encrypted notebooks.
+    #[test]
+    fn test_shell_history() -> TestResult {
+        let (temp_dir, rjot_dir) = setup();
+        let cwd = temp_dir.path().join("somewhere");
+        fs::create_dir(&cwd)?;
+        Command::new(env!("CARGO_BIN_EXE_rjot"))
+            .args(["notebook", "new", "secrets"])
+            .env("RJOT_DIR", &rjot_dir)
+            .output()?;
+        Command::new(env!("CARGO_BIN_EXE_rjot"))
+            .args(["encrypt", "--notebook", "secrets"])
+            .env("RJOT_DIR", &rjot_dir)
+            .output()?;
+
+        run_shell(
+            &rjot_dir,
+            &cwd,
+            "list\n'a plain jot'\n--notebook=secrets 'secret memo' -x\n\
+             list --notebook=secrets --bogus 'secret list'\nuse secrets\n'the launch codes'\n\
+             t 'hide the codes'\nthe launch codes are 1234 --for-alice\n\
+             t \"wire the money\" -x\n--notebook default 'plain again'\nlist\nexit\n",
+            &[],
+        )?;
+        assert!(!cwd.join("history.txt").exists());
+        let history = fs::read_to_string(rjot_dir.join("shell_history.jsonl"))?;
+        assert!(history.contains(r#"{"notebook":"default","line":"list"}"#));
+        assert!(history.contains("a plain jot"));
+        assert!(history.contains(r#"{"notebook":"secrets","line":"list"}"#));
+        assert!(history.contains("plain again"));
+        assert!(!history.contains("launch codes"));
+        assert!(!history.contains("hide the codes"));
+        assert!(!history.contains("for-alice"));
+        assert!(!history.contains("wire the money"));
+        assert!(!history.contains("secret memo"));
+        assert!(!history.contains("secret list"));
+
+        // A smaller limit drops the oldest entries the next time the shell starts.
+        run_shell(&rjot_dir, &cwd, "exit\n", &[("RJOT_HISTORY_SIZE", "2")])?;
+        let history = fs::read_to_string(rjot_dir.join("shell_history.jsonl"))?;
+        let lines: Vec<&str> = history.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                r#"{"notebook":"secrets","line":"list"}"#,
+                r#"{"notebook":"secrets","line":"exit"}"#,
+                r#"{"notebook":"default","line":"exit"}"#,
+            ]
+        );
+        Ok(())
+    }
+
+    /// Tests composing a multi-line jot with `write`, including inline `#tags`.
+    #[test]
+    fn test_shell_compose() -> TestResult {
+        let (temp_dir, rjot_dir) = setup();
+        run_shell(
+            &rjot_dir,
+            temp_dir.path(),
+            "write\n# Retro notes #team\n\nShipped the parser. #rust #team\n\
+             ```\nlet x = \"#not-a-tag\";\n```\n.\nlist\nexit\n",
+            &[],
+        )?;
+        let entries_dir = rjot_dir.join("notebooks").join("default");
+        let entry_path = fs::read_dir(&entries_dir)?.next().unwrap()?.path();
+        assert_eq!(
+            fs::read_to_string(entry_path)?,
+            "---\ntags:\n- team\n- rust\n---\n\n# Retro notes #team\n\n\
+             Shipped the parser. #rust #team\n```\nlet x = \"#not-a-tag\";\n```"
+        );
+
+        // Ctrl-D ends the jot too, and an empty jot is discarded.
+        let output = run_shell(&rjot_dir, temp_dir.path(), ":compose\n", &[])?;
+        assert!(String::from_utf8(output.stdout)?.contains("Empty jot discarded."));
+        assert_eq!(fs::read_dir(&entries_dir)?.count(), 1);
+        Ok(())
+    }
+
+    /// Tests that `use` only switches the shell's notebook, and that `notebook use`
+    /// still takes effect afterwards.
+    #[test]
+    fn test_shell_use_stays_in_session() -> TestResult {
+        let (temp_dir, rjot_dir) = setup();
+        let notebooks = rjot_dir.join("notebooks");
+        fs::create_dir_all(notebooks.join("work"))?;
+        fs::create_dir_all(notebooks.join("home"))?;
+
+        let output = run_shell(
+            &rjot_dir,
+            temp_dir.path(),
+            "use work\nwork jot\nnotebook use home\nhome jot\nnotebook status\nexit\n",
+            &[],
+        )?;
+        let stdout = String::from_utf8(output.stdout)?;
+        assert!(stdout.contains("Active notebook: home (set by `rjot notebook use`)"));
+        let read_jot = |notebook: &str| -> Result<String, Box<dyn std::error::Error>> {
+            let entry = fs::read_dir(notebooks.join(notebook))?.next().unwrap()?;
+            Ok(fs::read_to_string(entry.path())?)
+        };
+        assert_eq!(read_jot("work")?, "work jot");
+        assert_eq!(read_jot("home")?, "home jot");
+
+        // Only `notebook use` is saved for later invocations.
+        let config = fs::read_to_string(rjot_dir.join("config.toml"))?;
+        assert!(config.contains("active_notebook = \"home\""));
+        assert!(!config.contains("work"));
+        Ok(())
+    }
+
+    /// Tests the `last`, `open`, `filter`, and `alias` shell commands.
+    #[test]
+    fn test_shell_builtins() -> TestResult {
+        let (temp_dir, rjot_dir) = setup();
+        let default = rjot_dir.join("notebooks").join("default");
+        fs::write(
+            default.join("2025-07-01-090000.md"),
+            "---\ntags:\n- rust\n---\n\nrust jot",
+        )?;
+        fs::write(default.join("2025-07-02-090000.md"), "plain jot")?;
+
+        let output = run_shell(
+            &rjot_dir,
+            temp_dir.path(),
+            "last\nt 'ship it'\nlast\nfilter --tag rust\nlist\nopen 1 pin\nopen 2\n\
+             filter off\nalias pins=list --pinned\npins\nlast bogus\nexit\n",
+            &[],
+        )?;
+        let stdout = String::from_utf8(output.stdout)?;
+        let stderr = String::from_utf8(output.stderr)?;
+        assert!(stderr.contains("No jot has been created in this shell yet."));
+        assert!(stdout.contains("- [ ] ship it\n"));
+        assert!(stdout.contains("Filtering lists with: --tag rust"));
+        assert!(stdout.contains("Successfully pinned jot '2025-07-01-090000'."));
+        assert!(stderr.contains("The last listing only has 1 jot(s)."));
+        assert!(stdout.contains("No list filter."));
+        assert!(stdout.contains("Showing pinned jots:"));
+        assert!(stderr.contains("Unknown action 'bogus'"));
+        assert!(fs::read_to_string(default.join("2025-07-01-090000.md"))?.contains("pinned: true"));
+
+        // Aliases are saved and available in later sessions.
+        let config = fs::read_to_string(rjot_dir.join("config.toml"))?;
+        assert!(config.contains("[aliases]\npins = \"list --pinned\""));
+        let output = run_shell(
+            &rjot_dir,
+            temp_dir.path(),
+            "unalias pins\npins\nexit\n",
+            &[],
+        )?;
+        assert!(String::from_utf8(output.stdout)?.contains("Removed alias 'pins'."));
+        Ok(())
+    }
 }